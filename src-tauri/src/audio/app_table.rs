@@ -0,0 +1,91 @@
+use crate::models::AudioSession;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// 依程式保存在本地儲存的設定，例如記住的音量與程式的輸出裝置
+/// 程式以分組鍵 (執行檔路徑) 識別，重新啟動後仍能對應回同一個程式
+pub struct AppTable<T> {
+    store_key: &'static str,
+    data: RwLock<T>,
+    /// 已處理過的會話，只有新出現的會話需要套用
+    seen: Mutex<HashSet<String>>,
+    /// 有變動但尚未寫入儲存
+    dirty: AtomicBool,
+}
+
+impl<T: Default + Serialize + DeserializeOwned> AppTable<T> {
+    pub fn new(store_key: &'static str) -> Self {
+        Self {
+            store_key,
+            data: RwLock::new(T::default()),
+            seen: Mutex::new(HashSet::new()),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.data.read().unwrap()
+    }
+
+    /// 修改後需呼叫 mark_dirty 才會寫入儲存
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.data.write().unwrap()
+    }
+
+    pub fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// 回傳第一次看到的會話並記為已處理
+    /// 結束的會話不再追蹤，避免集合無限成長
+    pub fn new_sessions<'a>(&self, sessions: &'a [AudioSession]) -> Vec<&'a AudioSession> {
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|id| sessions.iter().any(|s| &s.id == id));
+
+        sessions
+            .iter()
+            .filter(|session| seen.insert(session.id.clone()))
+            .collect()
+    }
+
+    /// 從本地儲存載入，應在啟動時呼叫
+    pub fn load<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>) -> Result<(), String> {
+        use tauri_plugin_store::StoreExt;
+
+        let store = app
+            .store("settings.json")
+            .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+        let data = store
+            .get(self.store_key)
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default();
+
+        *self.write() = data;
+        self.dirty.store(false, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// 有變動時才寫入本地儲存
+    pub fn save<R: tauri::Runtime>(&self, app: &tauri::AppHandle<R>) -> Result<(), String> {
+        use tauri_plugin_store::StoreExt;
+
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let store = app
+            .store("settings.json")
+            .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+        store.set(self.store_key, serde_json::json!(*self.read()));
+
+        store.save().map_err(|e| format!("無法保存設定: {:?}", e))?;
+
+        Ok(())
+    }
+}
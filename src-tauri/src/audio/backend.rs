@@ -0,0 +1,218 @@
+use crate::models::{AudioDevice, AudioLevels, AudioSession, DeviceDirection, DeviceRole};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, RwLock};
+
+pub const UNSUPPORTED_MESSAGE: &str = "音訊控制 只支持 Windows 與 Linux :(((";
+
+/// 後端在背景偵測到的音訊變化
+#[derive(Debug, Clone, PartialEq)]
+pub enum AudioEvent {
+    /// 應用程式會話新增、移除，或其音量/靜音改變
+    SessionsChanged,
+    /// 預設裝置的音量或靜音狀態改變
+    DeviceVolumeChanged {
+        direction: DeviceDirection,
+        volume: f32,
+        is_muted: bool,
+    },
+    /// 系統預設的裝置改變
+    DefaultDeviceChanged {
+        direction: DeviceDirection,
+        role: DeviceRole,
+        device_id: String,
+    },
+    /// 新增裝置
+    DeviceAdded {
+        direction: DeviceDirection,
+        device_id: String,
+    },
+    /// 移除裝置
+    DeviceRemoved {
+        direction: DeviceDirection,
+        device_id: String,
+    },
+}
+
+/// 音訊後端
+/// 封裝裝置列舉、預設裝置、端點音量/靜音，以及應用程式音量/靜音等平台相關操作
+/// 裝置相關的操作以 `direction` 區分輸出裝置與麥克風
+pub trait AudioBackend: Send + Sync {
+    /// 獲取指定方向的裝置
+    /// include_inactive 為 true 時一併列出停用、未接上與已移除的裝置
+    fn get_devices(
+        &self,
+        direction: DeviceDirection,
+        include_inactive: bool,
+    ) -> Result<Vec<AudioDevice>, String>;
+
+    /// 將裝置設為指定角色的預設裝置，方向由裝置本身決定
+    fn set_default_device(&self, device_id: &str, roles: &[DeviceRole]) -> Result<(), String>;
+
+    /// 獲取預設裝置的音量 (0.0 ~ 1.0)
+    fn get_default_device_volume(&self, direction: DeviceDirection) -> Result<f32, String>;
+
+    /// 設定預設裝置的音量 (0.0 ~ 1.0)
+    fn set_default_device_volume(
+        &self,
+        direction: DeviceDirection,
+        volume: f32,
+    ) -> Result<(), String>;
+
+    /// 獲取預設裝置的靜音狀態
+    fn get_default_device_mute(&self, direction: DeviceDirection) -> Result<bool, String>;
+
+    /// 設定預設裝置的靜音狀態
+    fn set_default_device_mute(&self, direction: DeviceDirection, mute: bool)
+        -> Result<(), String>;
+
+    /// 獲取指定裝置的音量 (0.0 ~ 1.0)，不需要是預設裝置
+    fn get_device_volume(&self, device_id: &str) -> Result<f32, String>;
+
+    /// 設定指定裝置的音量 (0.0 ~ 1.0)
+    fn set_device_volume(&self, device_id: &str, volume: f32) -> Result<(), String>;
+
+    /// 獲取指定裝置的靜音狀態
+    fn get_device_mute(&self, device_id: &str) -> Result<bool, String>;
+
+    /// 設定指定裝置的靜音狀態
+    fn set_device_mute(&self, device_id: &str, mute: bool) -> Result<(), String>;
+
+    /// 獲取所有應用程式的音訊會話，每個會話各自一筆，不做合併
+    fn get_sessions(&self) -> Result<Vec<AudioSession>, String>;
+
+    /// 設定單一會話的音量 (0.0 ~ 1.0)
+    fn set_session_volume(&self, session_id: &str, volume: f32) -> Result<(), String>;
+
+    /// 設定單一會話的靜音狀態
+    fn set_session_mute(&self, session_id: &str, mute: bool) -> Result<(), String>;
+
+    /// 將會話所屬的程式改用指定的輸出裝置，None 表示改回跟隨系統預設裝置
+    fn set_session_device(&self, session_id: &str, device_id: Option<&str>) -> Result<(), String>;
+
+    /// 開始在背景監聽音訊變化，事件會送到 sender
+    /// sender 的接收端關閉後停止監聽
+    fn watch(&self, sender: Sender<AudioEvent>) -> Result<(), String>;
+
+    /// 獲取每個輸出裝置與會話自上次呼叫以來的峰值
+    fn get_peak_levels(&self) -> Result<AudioLevels, String>;
+
+    /// 沒有人需要峰值時呼叫，釋放取樣用的資源
+    fn stop_peak_levels(&self) {}
+}
+
+/// 不支援的平台，所有操作都回傳錯誤
+pub struct UnsupportedBackend;
+
+impl AudioBackend for UnsupportedBackend {
+    fn get_devices(
+        &self,
+        _direction: DeviceDirection,
+        _include_inactive: bool,
+    ) -> Result<Vec<AudioDevice>, String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn set_default_device(&self, _device_id: &str, _roles: &[DeviceRole]) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn get_default_device_volume(&self, _direction: DeviceDirection) -> Result<f32, String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn set_default_device_volume(
+        &self,
+        _direction: DeviceDirection,
+        _volume: f32,
+    ) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn get_default_device_mute(&self, _direction: DeviceDirection) -> Result<bool, String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn set_default_device_mute(
+        &self,
+        _direction: DeviceDirection,
+        _mute: bool,
+    ) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn get_device_volume(&self, _device_id: &str) -> Result<f32, String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn set_device_volume(&self, _device_id: &str, _volume: f32) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn get_device_mute(&self, _device_id: &str) -> Result<bool, String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn set_device_mute(&self, _device_id: &str, _mute: bool) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn get_sessions(&self) -> Result<Vec<AudioSession>, String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn set_session_volume(&self, _session_id: &str, _volume: f32) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn set_session_mute(&self, _session_id: &str, _mute: bool) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn set_session_device(
+        &self,
+        _session_id: &str,
+        _device_id: Option<&str>,
+    ) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn watch(&self, _sender: Sender<AudioEvent>) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn get_peak_levels(&self) -> Result<AudioLevels, String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref AUDIO_BACKEND: RwLock<Arc<dyn AudioBackend>> = RwLock::new(Arc::new(UnsupportedBackend));
+}
+
+/// 依照目前平台選擇預設的音訊後端
+pub fn default_audio_backend() -> Arc<dyn AudioBackend> {
+    #[cfg(target_os = "windows")]
+    {
+        Arc::new(crate::audio::wasapi::WasapiBackend)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        Arc::new(crate::audio::pulse::PulseBackend)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        Arc::new(UnsupportedBackend)
+    }
+}
+
+/// 設定目前使用的音訊後端，應在啟動時呼叫
+pub fn set_audio_backend(backend: Arc<dyn AudioBackend>) {
+    *AUDIO_BACKEND.write().unwrap() = backend;
+}
+
+/// 取得目前使用的音訊後端
+pub fn audio_backend() -> Arc<dyn AudioBackend> {
+    AUDIO_BACKEND.read().unwrap().clone()
+}
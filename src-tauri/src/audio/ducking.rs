@@ -0,0 +1,584 @@
+use crate::audio::backend::audio_backend;
+use crate::audio::levels::subscribe_levels;
+use crate::audio::sessions::app_matches;
+use crate::models::{AudioLevels, AudioSession};
+use crate::utils::debug_log;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+const STORE_KEY: &str = "ducking";
+
+/// 淡出淡入每一步的間隔
+const FADE_STEP: Duration = Duration::from_millis(40);
+
+/// 音量差距在此範圍內視為相同，避免浮點誤差被當成手動調整
+const VOLUME_TOLERANCE: f32 = 0.005;
+
+/// 優先程式的峰值超過此值才視為正在發出聲音
+/// 通話程式在靜音時也會保持會話為活動狀態，所以不能只看會話狀態
+const PEAK_THRESHOLD: f32 = 0.01;
+
+/// 優先程式安靜超過這段時間才還原，避免說話的停頓讓音量忽大忽小
+const RELEASE_HOLD: Duration = Duration::from_millis(1500);
+
+/// 讀取優先程式峰值的間隔
+const LEVEL_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 通訊程式播放聲音時自動降低其他程式音量的設定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuckingSettings {
+    pub enabled: bool,
+    /// 優先程式，可填程式名稱、執行檔名稱或完整路徑
+    pub priority_apps: Vec<String>,
+    /// 降低的比例，0.7 表示降到原本音量的 30%
+    pub amount: f32,
+    /// 淡出與淡入的時間 (毫秒)
+    pub fade_ms: u64,
+}
+
+impl Default for DuckingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            priority_apps: vec![
+                "Teams.exe".to_string(),
+                "ms-teams.exe".to_string(),
+                "Discord.exe".to_string(),
+            ],
+            amount: 0.7,
+            fade_ms: 500,
+        }
+    }
+}
+
+impl DuckingSettings {
+    fn is_priority(&self, session: &AudioSession) -> bool {
+        self.priority_apps
+            .iter()
+            .any(|app| app_matches(app, &session.name, &session.path))
+    }
+}
+
+/// 被降低音量的會話
+struct DuckedSession {
+    /// 程式的分組鍵，會話結束後用來對應重新啟動的程式
+    group: String,
+    /// 降低前的音量，還原時使用
+    original: f32,
+    /// 最後一次由自動降低設定的音量
+    applied: f32,
+    /// 使用者在降低期間手動調整過，還原時不覆蓋
+    manual: bool,
+}
+
+#[derive(Default)]
+struct DuckState {
+    active: bool,
+    /// 目前套用的音量比例
+    ratio: f32,
+    sessions: HashMap<String, DuckedSession>,
+    /// 每次開始新的淡出淡入時遞增，讓進行中的淡出淡入停止
+    generation: u64,
+    fading: bool,
+    /// 未靜音的優先程式會話，以峰值判斷是否正在發出聲音
+    priority_sessions: Vec<String>,
+    /// 最後一次偵測到優先程式發出聲音的時間
+    last_heard: Option<Instant>,
+    /// 降低期間結束的會話，以分組鍵為索引
+    /// 系統會記住程式最後的音量，重新啟動的程式要依此還原
+    vanished: HashMap<String, DuckedSession>,
+}
+
+impl DuckState {
+    fn priority_audible(&self, now: Instant) -> bool {
+        self.last_heard
+            .is_some_and(|heard| now.duration_since(heard) < RELEASE_HOLD)
+    }
+}
+
+struct Fade {
+    session_id: String,
+    from: f32,
+    to: f32,
+}
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: RwLock<DuckingSettings> = RwLock::new(DuckingSettings::default());
+    static ref STATE: Mutex<DuckState> = Mutex::new(DuckState::default());
+}
+
+/// 依優先程式是否正在發出聲音降低或還原其他程式的音量
+/// 回傳 Some(是否正在降低) 表示狀態有改變
+pub fn update_ducking(sessions: &[AudioSession]) -> Option<bool> {
+    let settings = ducking_settings();
+
+    let mut state = STATE.lock().unwrap();
+    let was_active = state.active;
+
+    state.priority_sessions = sessions
+        .iter()
+        .filter(|s| settings.is_priority(s) && !s.is_muted)
+        .map(|s| s.id.clone())
+        .collect();
+    let priority_active = settings.enabled
+        && !state.priority_sessions.is_empty()
+        && state.priority_audible(Instant::now());
+
+    // 淡出淡入期間的變化是自己造成的，由淡出淡入的執行緒判斷
+    if !state.fading {
+        for session in sessions {
+            if let Some(ducked) = state.sessions.get_mut(&session.id) {
+                if (session.volume - ducked.applied).abs() > VOLUME_TOLERANCE {
+                    ducked.manual = true;
+                }
+            }
+        }
+    }
+
+    let ended: Vec<String> = state
+        .sessions
+        .keys()
+        .filter(|id| !sessions.iter().any(|s| &s.id == *id))
+        .cloned()
+        .collect();
+    for id in ended {
+        if let Some(ducked) = state.sessions.remove(&id) {
+            if !ducked.manual {
+                state.vanished.insert(ducked.group.clone(), ducked);
+            }
+        }
+    }
+
+    let mut fades: Vec<Fade> = Vec::new();
+
+    // 降低期間結束的程式重新啟動後還是降低後的音量，要接著降低或還原
+    for session in sessions {
+        if state.sessions.contains_key(&session.id) {
+            continue;
+        }
+        let Some(ducked) = state.vanished.remove(&session.group) else {
+            continue;
+        };
+        // 已經是其他音量 (例如同程式的其他會話，或套用了記住的音量) 時當作新的會話
+        if (session.volume - ducked.applied).abs() > VOLUME_TOLERANCE {
+            state.vanished.insert(session.group.clone(), ducked);
+            continue;
+        }
+
+        if priority_active {
+            state.sessions.insert(session.id.clone(), ducked);
+        } else {
+            fades.push(Fade {
+                session_id: session.id.clone(),
+                from: ducked.applied,
+                to: ducked.original,
+            });
+        }
+    }
+
+    if priority_active {
+        let ratio = 1.0 - settings.amount.clamp(0.0, 1.0);
+        let mut added = false;
+
+        for session in sessions.iter().filter(|s| !settings.is_priority(s)) {
+            if !state.sessions.contains_key(&session.id) {
+                state.sessions.insert(
+                    session.id.clone(),
+                    DuckedSession {
+                        group: session.group.clone(),
+                        original: session.volume,
+                        applied: session.volume,
+                        manual: false,
+                    },
+                );
+                added = true;
+            }
+        }
+
+        if !was_active || added || (state.ratio - ratio).abs() > f32::EPSILON {
+            state.active = true;
+            state.ratio = ratio;
+            fades.extend(
+                state
+                    .sessions
+                    .iter()
+                    .filter(|(_, ducked)| !ducked.manual)
+                    .map(|(id, ducked)| Fade {
+                        session_id: id.clone(),
+                        from: ducked.applied,
+                        to: ducked.original * ratio,
+                    }),
+            );
+        }
+    } else if was_active {
+        state.active = false;
+        fades.extend(
+            state
+                .sessions
+                .iter()
+                .filter(|(_, ducked)| !ducked.manual)
+                .map(|(id, ducked)| Fade {
+                    session_id: id.clone(),
+                    from: ducked.applied,
+                    to: ducked.original,
+                }),
+        );
+    }
+
+    fades.retain(|fade| (fade.from - fade.to).abs() > VOLUME_TOLERANCE);
+
+    let changed = (state.active != was_active).then_some(state.active);
+
+    if fades.is_empty() {
+        if !state.active && !state.fading {
+            state.sessions.clear();
+        }
+        return changed;
+    }
+
+    state.generation += 1;
+    state.fading = true;
+    let generation = state.generation;
+    drop(state);
+
+    let duration = Duration::from_millis(settings.fade_ms);
+    if duration.is_zero() {
+        run_fade(generation, fades, duration);
+    } else {
+        std::thread::spawn(move || run_fade(generation, fades, duration));
+    }
+
+    changed
+}
+
+/// 逐步調整音量，使用者中途調整過的會話不再變更
+fn run_fade(generation: u64, fades: Vec<Fade>, duration: Duration) {
+    let backend = audio_backend();
+    let steps = (duration.as_millis() / FADE_STEP.as_millis()).max(1) as u32;
+    let mut expected: Vec<Option<f32>> = fades.iter().map(|fade| Some(fade.from)).collect();
+
+    for step in 1..=steps {
+        std::thread::sleep(duration / steps);
+
+        let current = match backend.get_sessions() {
+            Ok(sessions) => sessions,
+            Err(_e) => {
+                debug_log!("無法獲取音訊會話: {}", _e);
+                break;
+            }
+        };
+
+        let mut state = STATE.lock().unwrap();
+        if state.generation != generation {
+            return;
+        }
+
+        for (fade, expected) in fades.iter().zip(expected.iter_mut()) {
+            let Some(last) = *expected else {
+                continue;
+            };
+            let Some(session) = current.iter().find(|s| s.id == fade.session_id) else {
+                *expected = None;
+                continue;
+            };
+
+            if (session.volume - last).abs() > VOLUME_TOLERANCE {
+                if let Some(ducked) = state.sessions.get_mut(&fade.session_id) {
+                    ducked.manual = true;
+                }
+                *expected = None;
+                continue;
+            }
+
+            let volume = fade.from + (fade.to - fade.from) * step as f32 / steps as f32;
+            match backend.set_session_volume(&fade.session_id, volume) {
+                Ok(()) => {
+                    *expected = Some(volume);
+                    if let Some(ducked) = state.sessions.get_mut(&fade.session_id) {
+                        ducked.applied = volume;
+                    }
+                }
+                Err(_e) => {
+                    debug_log!("無法調整 {} 的音量: {}", session.name, _e);
+                    *expected = None;
+                }
+            }
+        }
+    }
+
+    let mut state = STATE.lock().unwrap();
+    if state.generation == generation {
+        state.fading = false;
+        if !state.active {
+            state.sessions.clear();
+        }
+    }
+}
+
+/// 依峰值記錄優先程式是否正在發出聲音
+/// 回傳 true 表示需要以最新的會話重新判斷是否降低音量
+pub fn record_priority_levels(levels: &AudioLevels) -> bool {
+    let mut state = STATE.lock().unwrap();
+    let now = Instant::now();
+
+    let audible = state.priority_sessions.iter().any(|id| {
+        levels
+            .sessions
+            .get(id)
+            .is_some_and(|peak| *peak > PEAK_THRESHOLD)
+    });
+    if audible {
+        state.last_heard = Some(now);
+    }
+
+    state.priority_audible(now) != state.active
+}
+
+/// 啟用時持續讀取峰值，優先程式開始或停止發出聲音時降低或還原音量
+/// 狀態改變時呼叫 on_change，應在獨立的執行緒中執行
+pub fn run_ducking_monitor<F>(mut on_change: F)
+where
+    F: FnMut(bool),
+{
+    let mut levels = None;
+
+    loop {
+        std::thread::sleep(LEVEL_CHECK_INTERVAL);
+
+        // 停用時取消訂閱，沒有其他訂閱者時就不會持續取樣
+        if !ducking_settings().enabled {
+            levels = None;
+            continue;
+        }
+
+        let current = levels
+            .get_or_insert_with(subscribe_levels)
+            .borrow_and_update()
+            .clone();
+        if !record_priority_levels(&current) {
+            continue;
+        }
+
+        match audio_backend().get_sessions() {
+            Ok(sessions) => {
+                if let Some(active) = update_ducking(&sessions) {
+                    on_change(active);
+                }
+            }
+            Err(_e) => {
+                debug_log!("無法獲取音訊會話: {}", _e);
+            }
+        }
+    }
+}
+
+/// 目前是否正在降低其他程式的音量
+pub fn is_ducking() -> bool {
+    STATE.lock().unwrap().active
+}
+
+pub fn ducking_settings() -> DuckingSettings {
+    SETTINGS.read().unwrap().clone()
+}
+
+pub fn set_ducking_settings(settings: DuckingSettings) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&settings.amount) {
+        return Err(format!("降低比例必須介於 0 到 1 之間: {}", settings.amount));
+    }
+
+    *SETTINGS.write().unwrap() = DuckingSettings {
+        priority_apps: settings
+            .priority_apps
+            .into_iter()
+            .map(|app| app.trim().to_string())
+            .filter(|app| !app.is_empty())
+            .collect(),
+        ..settings
+    };
+
+    Ok(())
+}
+
+/// 從本地儲存載入設定，應在啟動時呼叫
+pub fn load_ducking_settings<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    use tauri_plugin_store::StoreExt;
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+    let settings = store
+        .get(STORE_KEY)
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+
+    *SETTINGS.write().unwrap() = settings;
+
+    Ok(())
+}
+
+/// 獲取自動降低音量的設定
+#[tauri::command]
+pub fn get_ducking_settings() -> DuckingSettings {
+    ducking_settings()
+}
+
+/// 儲存自動降低音量的設定並立即生效
+#[tauri::command]
+pub fn save_ducking_settings(
+    app: tauri::AppHandle,
+    settings: DuckingSettings,
+) -> Result<(), String> {
+    use tauri::Emitter;
+    use tauri_plugin_store::StoreExt;
+
+    set_ducking_settings(settings)?;
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+    store.set(STORE_KEY, serde_json::json!(ducking_settings()));
+
+    store.save().map_err(|e| format!("無法保存設定: {:?}", e))?;
+
+    if let Some(active) = update_ducking(&audio_backend().get_sessions()?) {
+        let _ = app.emit("ducking-changed", serde_json::json!({ "active": active }));
+    }
+
+    Ok(())
+}
+
+/// 目前是否正在降低其他程式的音量
+#[tauri::command]
+pub fn get_ducking_active() -> bool {
+    is_ducking()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::backend::set_audio_backend;
+    use crate::audio::mock::{mock_session, MockAudioBackend};
+    use crate::utils::backend_test_lock;
+    use std::sync::Arc;
+
+    /// 以模擬的峰值更新，回傳狀態的變化
+    fn hear(mock: &MockAudioBackend, peak: f32) -> Option<bool> {
+        mock.update(|state| {
+            state.levels.sessions.insert("discord-1".to_string(), peak);
+        });
+        if !record_priority_levels(&mock.state().levels) {
+            return None;
+        }
+        update_ducking(&mock.state().sessions)
+    }
+
+    /// 讓最後一次聽到優先程式的時間超過保留時間
+    fn wait_for_release() {
+        STATE.lock().unwrap().last_heard = Some(Instant::now() - RELEASE_HOLD);
+    }
+
+    fn start_call() -> Arc<MockAudioBackend> {
+        let mock = Arc::new(MockAudioBackend::fixture());
+        set_audio_backend(mock.clone());
+        *STATE.lock().unwrap() = DuckState::default();
+        // 通話程式一直保持活動狀態，只有峰值代表真的有聲音
+        mock.update(|state| {
+            state
+                .sessions
+                .push(mock_session("discord-1", "Discord.exe", 300, 1.0));
+        });
+        set_ducking_settings(DuckingSettings {
+            enabled: true,
+            priority_apps: vec!["discord.exe".to_string()],
+            amount: 0.5,
+            fade_ms: 0,
+        })
+        .unwrap();
+
+        assert_eq!(update_ducking(&mock.state().sessions), None);
+        mock
+    }
+
+    #[tokio::test]
+    async fn ducking_restores_volumes_except_manual_changes() {
+        let _guard = backend_test_lock().await;
+        let mock = start_call();
+
+        assert_eq!(hear(&mock, 0.0), None);
+        assert_eq!(hear(&mock, 0.3), Some(true));
+
+        let sessions = mock.state().sessions;
+        assert!((sessions[0].volume - 0.4).abs() < 1e-6);
+        assert!((sessions[2].volume - 0.3).abs() < 1e-6);
+        assert!((sessions[3].volume - 1.0).abs() < 1e-6);
+
+        // 降低期間手動調整的音量在還原時保留
+        crate::set_session_volume("chrome-1".to_string(), 0.9).unwrap();
+        assert_eq!(update_ducking(&mock.state().sessions), None);
+
+        // 說話的停頓不會馬上還原
+        assert_eq!(hear(&mock, 0.0), None);
+        wait_for_release();
+        assert_eq!(hear(&mock, 0.0), Some(false));
+
+        let sessions = mock.state().sessions;
+        assert!((sessions[0].volume - 0.8).abs() < 1e-6);
+        assert!((sessions[1].volume - 0.9).abs() < 1e-6);
+        assert!((sessions[2].volume - 0.6).abs() < 1e-6);
+
+        set_ducking_settings(DuckingSettings::default()).unwrap();
+        crate::forget(None).unwrap();
+    }
+
+    #[tokio::test]
+    async fn apps_restarted_while_ducked_are_restored() {
+        let _guard = backend_test_lock().await;
+        let mock = start_call();
+        assert_eq!(hear(&mock, 0.3), Some(true));
+
+        // Spotify 在降低期間重新啟動，系統沿用降低後的音量
+        mock.update(|state| {
+            state.sessions.remove(0);
+        });
+        assert_eq!(update_ducking(&mock.state().sessions), None);
+        mock.update(|state| {
+            state
+                .sessions
+                .push(mock_session("spotify-2", "Spotify.exe", 101, 0.4));
+        });
+        assert_eq!(update_ducking(&mock.state().sessions), None);
+
+        wait_for_release();
+        assert_eq!(hear(&mock, 0.0), Some(false));
+
+        let spotify = mock.state().sessions.pop().unwrap();
+        assert_eq!(spotify.id, "spotify-2");
+        assert!((spotify.volume - 0.8).abs() < 1e-6);
+
+        // 還原後才重新啟動的程式也回到原本的音量
+        assert_eq!(hear(&mock, 0.3), Some(true));
+        mock.update(|state| {
+            state.sessions.retain(|s| s.id != "chrome-2");
+        });
+        assert_eq!(update_ducking(&mock.state().sessions), None);
+        wait_for_release();
+        assert_eq!(hear(&mock, 0.0), Some(false));
+
+        mock.update(|state| {
+            state
+                .sessions
+                .push(mock_session("chrome-3", "chrome.exe", 202, 0.3));
+        });
+        assert_eq!(update_ducking(&mock.state().sessions), None);
+        let chrome = mock.state().sessions.pop().unwrap();
+        assert!((chrome.volume - 0.6).abs() < 1e-6);
+
+        set_ducking_settings(DuckingSettings::default()).unwrap();
+        crate::forget(None).unwrap();
+    }
+}
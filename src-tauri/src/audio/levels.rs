@@ -0,0 +1,117 @@
+use crate::audio::backend::audio_backend;
+use crate::models::AudioLevels;
+use crate::utils::debug_log;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// 後端取樣的間隔，訂閱者再依各自的間隔節流
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 訂閱者可選的傳送間隔範圍 (毫秒)
+const MIN_INTERVAL_MS: u64 = 50;
+const MAX_INTERVAL_MS: u64 = 2000;
+const DEFAULT_INTERVAL_MS: u64 = 100;
+
+lazy_static::lazy_static! {
+    static ref LEVELS: watch::Sender<AudioLevels> = watch::channel(AudioLevels::default()).0;
+    /// 取樣執行緒是否正在執行
+    static ref SAMPLING: Mutex<bool> = Mutex::new(false);
+    /// 主視窗的訂閱，設為 true 時停止送出
+    static ref WINDOW_SUBSCRIPTION: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+}
+
+/// 訂閱者要求的傳送間隔，限制在合理範圍內
+pub fn levels_interval(interval_ms: Option<u64>) -> Duration {
+    Duration::from_millis(
+        interval_ms
+            .unwrap_or(DEFAULT_INTERVAL_MS)
+            .clamp(MIN_INTERVAL_MS, MAX_INTERVAL_MS),
+    )
+}
+
+/// 訂閱音量峰值
+/// 第一個訂閱者出現時開始取樣，所有接收端都關閉後停止，避免閒置時持續讀取
+pub fn subscribe_levels() -> watch::Receiver<AudioLevels> {
+    let mut sampling = SAMPLING.lock().unwrap();
+    let receiver = LEVELS.subscribe();
+
+    if !*sampling {
+        *sampling = true;
+        std::thread::spawn(run_sampler);
+    }
+
+    receiver
+}
+
+fn run_sampler() {
+    let backend = audio_backend();
+
+    loop {
+        {
+            let mut sampling = SAMPLING.lock().unwrap();
+            if LEVELS.receiver_count() == 0 {
+                backend.stop_peak_levels();
+                *sampling = false;
+                return;
+            }
+        }
+
+        match backend.get_peak_levels() {
+            // 沒有變化 (例如全部靜音) 時不通知，訂閱者就不會重複送出相同的資料
+            Ok(levels) => {
+                LEVELS.send_if_modified(|current| {
+                    if *current == levels {
+                        return false;
+                    }
+                    *current = levels;
+                    true
+                });
+            }
+            Err(_e) => {
+                debug_log!("無法獲取音量峰值: {}", _e);
+            }
+        }
+
+        std::thread::sleep(SAMPLE_INTERVAL);
+    }
+}
+
+/// 讓主視窗接收 `levels` 事件
+/// @param interval_ms 傳送間隔 (毫秒)，預設 100
+#[tauri::command]
+pub fn subscribe_audio_levels(app: tauri::AppHandle, interval_ms: Option<u64>) {
+    use tauri::Emitter;
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = WINDOW_SUBSCRIPTION
+        .lock()
+        .unwrap()
+        .replace(Arc::clone(&stopped))
+    {
+        previous.store(true, Ordering::Relaxed);
+    }
+
+    let mut receiver = subscribe_levels();
+    let interval = levels_interval(interval_ms);
+
+    std::thread::spawn(move || {
+        while !stopped.load(Ordering::Relaxed) {
+            std::thread::sleep(interval);
+
+            if receiver.has_changed().unwrap_or(false) {
+                let levels = receiver.borrow_and_update().clone();
+                let _ = app.emit("levels", levels);
+            }
+        }
+    });
+}
+
+/// 停止送出 `levels` 事件給主視窗
+#[tauri::command]
+pub fn unsubscribe_audio_levels() {
+    if let Some(stopped) = WINDOW_SUBSCRIPTION.lock().unwrap().take() {
+        stopped.store(true, Ordering::Relaxed);
+    }
+}
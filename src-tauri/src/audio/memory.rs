@@ -0,0 +1,223 @@
+use crate::audio::app_table::AppTable;
+use crate::audio::backend::audio_backend;
+use crate::models::AudioSession;
+use crate::utils::debug_log;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const STORE_KEY: &str = "volume_memory";
+
+/// 記住的音量與靜音狀態
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RememberedVolume {
+    pub name: String,
+    pub volume: f32,
+    pub is_muted: bool,
+}
+
+/// 每個程式最後設定的音量，以分組鍵 (執行檔路徑) 為索引
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VolumeMemory {
+    pub entries: BTreeMap<String, RememberedVolume>,
+    /// 不記住音量的程式，可填入執行檔名稱或完整路徑
+    pub excluded: Vec<String>,
+}
+
+impl VolumeMemory {
+    pub fn is_excluded(&self, session: &AudioSession) -> bool {
+        self.excluded
+            .iter()
+            .any(|excluded| matches_excluded(excluded, &session.group, &session.name))
+    }
+}
+
+/// 排除清單的項目可以是分組鍵、程式名稱或執行檔名稱
+fn matches_excluded(excluded: &str, group: &str, name: &str) -> bool {
+    let file_name = group.rsplit(['\\', '/']).next().unwrap_or_default();
+
+    excluded.eq_ignore_ascii_case(group)
+        || excluded.eq_ignore_ascii_case(name)
+        || excluded.eq_ignore_ascii_case(file_name)
+}
+
+lazy_static::lazy_static! {
+    static ref MEMORY: AppTable<VolumeMemory> = AppTable::new(STORE_KEY);
+}
+
+/// 記住使用者對這些會話設定的音量或靜音，未指定的值沿用會話目前的狀態
+pub fn remember(sessions: &[AudioSession], volume: Option<f32>, mute: Option<bool>) {
+    let mut memory = MEMORY.write();
+
+    for session in sessions {
+        if memory.is_excluded(session) {
+            continue;
+        }
+
+        let entry = RememberedVolume {
+            name: session.name.clone(),
+            volume: volume.unwrap_or(session.volume),
+            is_muted: mute.unwrap_or(session.is_muted),
+        };
+
+        if memory.entries.get(&session.group) != Some(&entry) {
+            memory.entries.insert(session.group.clone(), entry);
+            MEMORY.mark_dirty();
+        }
+    }
+}
+
+/// 對新出現的會話套用記住的音量，回傳是否有修改任何會話
+pub fn reapply_remembered(sessions: &[AudioSession]) -> bool {
+    let memory = MEMORY.read();
+    let backend = audio_backend();
+    let mut changed = false;
+
+    for session in MEMORY.new_sessions(sessions) {
+        if memory.is_excluded(session) {
+            continue;
+        }
+
+        let Some(remembered) = memory.entries.get(&session.group) else {
+            continue;
+        };
+
+        if (session.volume - remembered.volume).abs() > f32::EPSILON {
+            match backend.set_session_volume(&session.id, remembered.volume) {
+                Ok(()) => changed = true,
+                Err(_e) => {
+                    debug_log!("無法還原 {} 的音量: {}", session.name, _e);
+                }
+            }
+        }
+        if session.is_muted != remembered.is_muted {
+            match backend.set_session_mute(&session.id, remembered.is_muted) {
+                Ok(()) => changed = true,
+                Err(_e) => {
+                    debug_log!("無法還原 {} 的靜音狀態: {}", session.name, _e);
+                }
+            }
+        }
+    }
+
+    changed
+}
+
+pub fn volume_memory() -> VolumeMemory {
+    MEMORY.read().clone()
+}
+
+/// 忘記指定程式的音量，未指定時清除全部
+pub fn forget(group: Option<&str>) -> Result<(), String> {
+    let mut memory = MEMORY.write();
+
+    match group {
+        Some(group) => {
+            if memory.entries.remove(group).is_none() {
+                return Err(format!("找不到: '{}'", group));
+            }
+        }
+        None => memory.entries.clear(),
+    }
+
+    MEMORY.mark_dirty();
+    Ok(())
+}
+
+/// 設定不記住音量的程式，已記住的資料會一併移除
+pub fn set_excluded(excluded: Vec<String>) {
+    let mut memory = MEMORY.write();
+
+    memory.excluded = excluded
+        .into_iter()
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty())
+        .collect();
+
+    let excluded = memory.excluded.clone();
+    memory.entries.retain(|group, entry| {
+        !excluded
+            .iter()
+            .any(|e| matches_excluded(e, group, &entry.name))
+    });
+
+    MEMORY.mark_dirty();
+}
+
+/// 從本地儲存載入記住的音量，應在啟動時呼叫
+pub fn load_volume_memory<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    MEMORY.load(app)
+}
+
+/// 有變動時才寫入本地儲存
+pub fn save_volume_memory<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    MEMORY.save(app)
+}
+
+/// 獲取記住的各程式音量與排除清單
+#[tauri::command]
+pub fn get_volume_memory() -> VolumeMemory {
+    volume_memory()
+}
+
+/// 清除記住的音量
+/// @param group 程式的分組鍵，未指定時清除全部
+#[tauri::command]
+pub fn clear_volume_memory(app: tauri::AppHandle, group: Option<String>) -> Result<(), String> {
+    forget(group.as_deref())?;
+    save_volume_memory(&app)
+}
+
+/// 設定不記住音量的程式
+/// @param excluded 執行檔名稱或完整路徑
+#[tauri::command]
+pub fn set_volume_memory_excluded(
+    app: tauri::AppHandle,
+    excluded: Vec<String>,
+) -> Result<(), String> {
+    set_excluded(excluded);
+    save_volume_memory(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::backend::set_audio_backend;
+    use crate::audio::mock::{mock_session, MockAudioBackend};
+    use crate::utils::backend_test_lock;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn restarted_apps_get_their_remembered_volume() {
+        let _guard = backend_test_lock().await;
+        let mock = Arc::new(MockAudioBackend::fixture());
+        set_audio_backend(mock.clone());
+        forget(None).unwrap();
+        set_excluded(vec!["chrome.exe".to_string()]);
+        reapply_remembered(&mock.state().sessions);
+
+        crate::set_session_volume("Spotify.exe".to_string(), 0.25).unwrap();
+        crate::set_session_volume("chrome-1".to_string(), 0.4).unwrap();
+        assert!(volume_memory().entries.values().all(|e| e.name == "Spotify.exe"));
+
+        // 程式重新啟動後會話 ID 不同，音量回到預設值
+        mock.update(|state| {
+            state.sessions = vec![
+                mock_session("spotify-2", "Spotify.exe", 300, 1.0),
+                mock_session("chrome-3", "chrome.exe", 301, 1.0),
+            ];
+        });
+        assert!(reapply_remembered(&mock.state().sessions));
+
+        let sessions = mock.state().sessions;
+        assert!((sessions[0].volume - 0.25).abs() < 1e-6);
+        assert!((sessions[1].volume - 1.0).abs() < 1e-6);
+
+        // 已處理過的會話不會再被覆蓋
+        crate::audio_backend().set_session_volume("spotify-2", 0.9).unwrap();
+        assert!(!reapply_remembered(&mock.state().sessions));
+
+        forget(None).unwrap();
+        set_excluded(Vec::new());
+    }
+}
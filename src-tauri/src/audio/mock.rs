@@ -0,0 +1,345 @@
+use crate::audio::backend::{AudioBackend, AudioEvent};
+use crate::audio::sessions::session_group_key;
+use crate::models::{
+    AudioDevice, AudioLevels, AudioSession, DeviceDirection, DeviceRole, DeviceState, FormFactor,
+};
+use std::collections::HashMap;
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+
+/// 模擬的混音器狀態，測試可直接讀寫
+#[derive(Debug, Clone, Default)]
+pub struct MockAudioState {
+    pub devices: Vec<AudioDevice>,
+    pub default_volume: f32,
+    pub default_mute: bool,
+    /// 預設麥克風的音量與靜音
+    pub capture_volume: f32,
+    pub capture_mute: bool,
+    /// 非預設裝置的 (音量, 靜音)，未記錄的裝置為 (1.0, false)
+    pub device_volumes: HashMap<String, (f32, bool)>,
+    pub sessions: Vec<AudioSession>,
+    pub levels: AudioLevels,
+    /// 設定後下一次操作會回傳此錯誤
+    pub fail_next: Option<String>,
+}
+
+impl MockAudioState {
+    /// 裝置的音量與靜音，預設裝置對應 `default_*` 與 `capture_*` 欄位
+    fn device_volume(&mut self, device_id: &str) -> Result<(&mut f32, &mut bool), String> {
+        let (is_default, direction) = self
+            .devices
+            .iter()
+            .find(|d| d.id == device_id)
+            .map(|d| (d.is_default, d.direction))
+            .ok_or(format!("找不到裝置: '{}'", device_id))?;
+
+        Ok(match (is_default, direction) {
+            (true, DeviceDirection::Render) => (&mut self.default_volume, &mut self.default_mute),
+            (true, DeviceDirection::Capture) => (&mut self.capture_volume, &mut self.capture_mute),
+            (false, _) => {
+                let (volume, mute) = self
+                    .device_volumes
+                    .entry(device_id.to_string())
+                    .or_insert((1.0, false));
+                (volume, mute)
+            }
+        })
+    }
+}
+
+/// 建立模擬的應用程式會話，路徑與分組鍵由名稱推得
+pub fn mock_session(id: &str, name: &str, pid: u32, volume: f32) -> AudioSession {
+    let path = format!("C:\\Program Files\\{}", name);
+
+    AudioSession {
+        id: id.to_string(),
+        name: name.to_string(),
+        pid,
+        group: session_group_key(&path, name),
+        path,
+        volume,
+        is_muted: false,
+        is_active: true,
+        device_id: Some("speakers".to_string()),
+    }
+}
+
+/// 記憶體內的音訊後端，供測試使用
+pub struct MockAudioBackend {
+    state: Mutex<MockAudioState>,
+    watchers: Mutex<Vec<Sender<AudioEvent>>>,
+}
+
+impl MockAudioBackend {
+    pub fn new(state: MockAudioState) -> Self {
+        Self {
+            state: Mutex::new(state),
+            watchers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 兩個輸出裝置、一個麥克風、一個未接上的耳機、Spotify 與兩個 Chrome 會話的預設場景
+    pub fn fixture() -> Self {
+        Self::new(MockAudioState {
+            devices: vec![
+                AudioDevice {
+                    id: "speakers".to_string(),
+                    name: "Speakers".to_string(),
+                    is_default: true,
+                    direction: DeviceDirection::Render,
+                    default_roles: DeviceRole::ALL.to_vec(),
+                    form_factor: FormFactor::Speakers,
+                    ..Default::default()
+                },
+                AudioDevice {
+                    id: "headset".to_string(),
+                    name: "Headset".to_string(),
+                    is_default: false,
+                    direction: DeviceDirection::Render,
+                    default_roles: Vec::new(),
+                    form_factor: FormFactor::Headset,
+                    ..Default::default()
+                },
+                AudioDevice {
+                    id: "microphone".to_string(),
+                    name: "Microphone".to_string(),
+                    is_default: true,
+                    direction: DeviceDirection::Capture,
+                    default_roles: DeviceRole::ALL.to_vec(),
+                    form_factor: FormFactor::Microphone,
+                    ..Default::default()
+                },
+                AudioDevice {
+                    id: "headphones".to_string(),
+                    name: "Headphones".to_string(),
+                    is_default: false,
+                    direction: DeviceDirection::Render,
+                    default_roles: Vec::new(),
+                    state: DeviceState::Unplugged,
+                    form_factor: FormFactor::Headphones,
+                    jack_connected: Some(false),
+                    ..Default::default()
+                },
+            ],
+            default_volume: 0.5,
+            default_mute: false,
+            capture_volume: 0.7,
+            capture_mute: false,
+            device_volumes: HashMap::new(),
+            sessions: vec![
+                mock_session("spotify-1", "Spotify.exe", 100, 0.8),
+                mock_session("chrome-1", "chrome.exe", 200, 1.0),
+                mock_session("chrome-2", "chrome.exe", 201, 0.6),
+            ],
+            levels: AudioLevels::default(),
+            fail_next: None,
+        })
+    }
+
+    /// 取得目前狀態的快照
+    pub fn state(&self) -> MockAudioState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// 修改狀態，模擬外部程式造成的變化
+    pub fn update<F: FnOnce(&mut MockAudioState)>(&self, f: F) {
+        f(&mut self.state.lock().unwrap());
+    }
+
+    /// 模擬後端偵測到變化，送給所有監聽者
+    pub fn emit(&self, event: AudioEvent) {
+        self.watchers
+            .lock()
+            .unwrap()
+            .retain(|sender| sender.send(event.clone()).is_ok());
+    }
+
+    /// 關閉所有監聽者的 sender，讓監聽循環結束
+    pub fn stop_watching(&self) {
+        self.watchers.lock().unwrap().clear();
+    }
+
+    fn with_state<T, F>(&self, f: F) -> Result<T, String>
+    where
+        F: FnOnce(&mut MockAudioState) -> Result<T, String>,
+    {
+        let mut state = self.state.lock().unwrap();
+        if let Some(error) = state.fail_next.take() {
+            return Err(error);
+        }
+        f(&mut state)
+    }
+}
+
+impl AudioBackend for MockAudioBackend {
+    fn get_devices(
+        &self,
+        direction: DeviceDirection,
+        include_inactive: bool,
+    ) -> Result<Vec<AudioDevice>, String> {
+        self.with_state(|state| {
+            Ok(state
+                .devices
+                .iter()
+                .filter(|d| d.direction == direction)
+                .filter(|d| include_inactive || d.state == DeviceState::Active)
+                .cloned()
+                .collect())
+        })
+    }
+
+    fn set_default_device(&self, device_id: &str, roles: &[DeviceRole]) -> Result<(), String> {
+        self.with_state(|state| {
+            let direction = state
+                .devices
+                .iter()
+                .find(|d| d.id == device_id)
+                .map(|d| d.direction)
+                .ok_or(format!("找不到裝置: '{}'", device_id))?;
+            for device in state.devices.iter_mut() {
+                if device.direction != direction {
+                    continue;
+                }
+                device.default_roles.retain(|role| !roles.contains(role));
+                if device.id == device_id {
+                    device.default_roles.extend_from_slice(roles);
+                }
+                device.is_default = device.default_roles.contains(&DeviceRole::Console);
+            }
+            Ok(())
+        })
+    }
+
+    fn get_default_device_volume(&self, direction: DeviceDirection) -> Result<f32, String> {
+        self.with_state(|state| {
+            Ok(match direction {
+                DeviceDirection::Render => state.default_volume,
+                DeviceDirection::Capture => state.capture_volume,
+            })
+        })
+    }
+
+    fn set_default_device_volume(
+        &self,
+        direction: DeviceDirection,
+        volume: f32,
+    ) -> Result<(), String> {
+        self.with_state(|state| {
+            let volume = volume.clamp(0.0, 1.0);
+            match direction {
+                DeviceDirection::Render => state.default_volume = volume,
+                DeviceDirection::Capture => state.capture_volume = volume,
+            }
+            Ok(())
+        })
+    }
+
+    fn get_default_device_mute(&self, direction: DeviceDirection) -> Result<bool, String> {
+        self.with_state(|state| {
+            Ok(match direction {
+                DeviceDirection::Render => state.default_mute,
+                DeviceDirection::Capture => state.capture_mute,
+            })
+        })
+    }
+
+    fn set_default_device_mute(
+        &self,
+        direction: DeviceDirection,
+        mute: bool,
+    ) -> Result<(), String> {
+        self.with_state(|state| {
+            match direction {
+                DeviceDirection::Render => state.default_mute = mute,
+                DeviceDirection::Capture => state.capture_mute = mute,
+            }
+            Ok(())
+        })
+    }
+
+    fn get_device_volume(&self, device_id: &str) -> Result<f32, String> {
+        self.with_state(|state| Ok(*state.device_volume(device_id)?.0))
+    }
+
+    fn set_device_volume(&self, device_id: &str, volume: f32) -> Result<(), String> {
+        self.with_state(|state| {
+            *state.device_volume(device_id)?.0 = volume.clamp(0.0, 1.0);
+            Ok(())
+        })
+    }
+
+    fn get_device_mute(&self, device_id: &str) -> Result<bool, String> {
+        self.with_state(|state| Ok(*state.device_volume(device_id)?.1))
+    }
+
+    fn set_device_mute(&self, device_id: &str, mute: bool) -> Result<(), String> {
+        self.with_state(|state| {
+            *state.device_volume(device_id)?.1 = mute;
+            Ok(())
+        })
+    }
+
+    fn get_sessions(&self) -> Result<Vec<AudioSession>, String> {
+        self.with_state(|state| Ok(state.sessions.clone()))
+    }
+
+    fn set_session_volume(&self, session_id: &str, volume: f32) -> Result<(), String> {
+        self.with_state(|state| {
+            let session = state
+                .sessions
+                .iter_mut()
+                .find(|s| s.id == session_id)
+                .ok_or(format!("找不到: '{}'", session_id))?;
+            session.volume = volume;
+            Ok(())
+        })
+    }
+
+    fn set_session_mute(&self, session_id: &str, mute: bool) -> Result<(), String> {
+        self.with_state(|state| {
+            let session = state
+                .sessions
+                .iter_mut()
+                .find(|s| s.id == session_id)
+                .ok_or(format!("找不到: '{}'", session_id))?;
+            session.is_muted = mute;
+            Ok(())
+        })
+    }
+
+    fn set_session_device(&self, session_id: &str, device_id: Option<&str>) -> Result<(), String> {
+        self.with_state(|state| {
+            let device_id = match device_id {
+                Some(id) => state
+                    .devices
+                    .iter()
+                    .find(|d| d.id == id && d.direction == DeviceDirection::Render)
+                    .map(|d| d.id.clone())
+                    .ok_or(format!("找不到裝置: '{}'", id))?,
+                None => state
+                    .devices
+                    .iter()
+                    .find(|d| d.is_default && d.direction == DeviceDirection::Render)
+                    .map(|d| d.id.clone())
+                    .ok_or("無法取得默認設備".to_string())?,
+            };
+            let session = state
+                .sessions
+                .iter_mut()
+                .find(|s| s.id == session_id)
+                .ok_or(format!("找不到: '{}'", session_id))?;
+            session.device_id = Some(device_id);
+            Ok(())
+        })
+    }
+
+    fn watch(&self, sender: Sender<AudioEvent>) -> Result<(), String> {
+        self.watchers.lock().unwrap().push(sender);
+        Ok(())
+    }
+
+    fn get_peak_levels(&self) -> Result<AudioLevels, String> {
+        self.with_state(|state| Ok(state.levels.clone()))
+    }
+}
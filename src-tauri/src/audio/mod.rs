@@ -1,5 +1,26 @@
+mod app_table;
+mod backend;
 mod sessions;
 mod devices;
+mod ducking;
+mod levels;
+mod memory;
+#[cfg(test)]
+pub mod mock;
+mod monitor;
+#[cfg(target_os = "linux")]
+mod pulse;
+mod routing;
+#[cfg(target_os = "windows")]
+mod wasapi;
+#[cfg(target_os = "windows")]
+mod wasapi_watch;
 
+pub use backend::*;
 pub use sessions::*;
 pub use devices::*;
+pub use ducking::*;
+pub use levels::*;
+pub use memory::*;
+pub use monitor::*;
+pub use routing::*;
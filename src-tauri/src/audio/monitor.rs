@@ -0,0 +1,289 @@
+use crate::audio::backend::{audio_backend, AudioEvent};
+use crate::audio::ducking::{run_ducking_monitor, update_ducking};
+use crate::audio::memory::{reapply_remembered, save_volume_memory};
+use crate::audio::routing::{reapply_routes, save_app_routes};
+use crate::audio::sessions::get_audio_sessions;
+use crate::models::{DeviceDirection, DeviceRole};
+use crate::rules::{handle_rule_event, session_rule_events, RuleEvent};
+use crate::utils::debug_log;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+/// 拖動音量時事件會連續觸發，這段時間內的事件合併後再送出
+const COALESCE_WINDOW: Duration = Duration::from_millis(100);
+
+/// 定時寫入記住的音量與輸出裝置，後端無法推送變化時也不會遺失
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 音訊監聽循環
+/// 由後端推送變化，同時送往 Tauri 前端與所有 WebSocket 客戶端
+pub fn audio_monitor_loop<R: tauri::Runtime>(
+    app_handle: tauri::AppHandle<R>,
+    http_server: Arc<crate::http_server::HttpServer<R>>,
+) {
+    let publisher = (app_handle.clone(), Arc::clone(&http_server));
+    std::thread::spawn(move || {
+        let (app_handle, http_server) = publisher;
+        run_ducking_monitor(|active| {
+            publish_event(
+                &app_handle,
+                &http_server,
+                "ducking_changed",
+                serde_json::json!({ "active": active }),
+            );
+        });
+    });
+
+    // 透過 WebSocket 設定的音量沒有 AppHandle，由這裡寫入
+    let saver = app_handle.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(SAVE_INTERVAL);
+        save_app_tables(&saver);
+    });
+
+    // 啟動前就在執行的程式也套用記住的音量與輸出裝置
+    if let Ok(sessions) = audio_backend().get_sessions() {
+        reapply_remembered(&sessions);
+        reapply_routes(&sessions);
+    }
+
+    // 啟動時已經在通話中也要降低其他程式的音量
+    if let Ok(sessions) = audio_backend().get_sessions() {
+        update_ducking(&sessions);
+    }
+
+    let (sender, receiver) = mpsc::channel();
+
+    if let Err(_e) = audio_backend().watch(sender) {
+        debug_log!("無法監聽音訊變化: {}", _e);
+        return;
+    }
+
+    run_audio_monitor(receiver, |event_type, data| {
+        // 有事件時立即寫入，不必等到下一次定時保存
+        save_app_tables(&app_handle);
+
+        publish_event(&app_handle, &http_server, event_type, data);
+    });
+}
+
+/// 寫入有變動的記住音量與程式的輸出裝置
+fn save_app_tables<R: tauri::Runtime>(app_handle: &tauri::AppHandle<R>) {
+    if let Err(_e) = save_volume_memory(app_handle) {
+        debug_log!("無法保存記住的音量: {}", _e);
+    }
+    if let Err(_e) = save_app_routes(app_handle) {
+        debug_log!("無法保存程式的輸出裝置: {}", _e);
+    }
+}
+
+/// 同時送往 Tauri 前端與所有 WebSocket 客戶端
+fn publish_event<R: tauri::Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+    http_server: &crate::http_server::HttpServer<R>,
+    event_type: &str,
+    data: serde_json::Value,
+) {
+    let _ = app_handle.emit(&event_type.replace('_', "-"), &data);
+
+    let ws_message = serde_json::json!({
+        "type": event_type,
+        "data": data
+    });
+    http_server.broadcast(ws_message.to_string());
+}
+
+/// 裝置方向對應的事件名稱 (音量, 預設裝置)
+/// 輸出裝置沿用原本的事件名稱，避免影響既有的客戶端
+fn device_event_types(direction: DeviceDirection) -> (&'static str, &'static str) {
+    match direction {
+        DeviceDirection::Render => ("device_volume_updated", "default_device_changed"),
+        DeviceDirection::Capture => ("capture_volume_updated", "default_capture_device_changed"),
+    }
+}
+
+/// 接收後端事件並轉換成要發布的訊息，直到所有 sender 都關閉
+pub fn run_audio_monitor<F>(receiver: Receiver<AudioEvent>, mut publish: F)
+where
+    F: FnMut(&str, serde_json::Value),
+{
+    let mut last_volume: HashMap<DeviceDirection, (f32, bool)> = HashMap::new();
+    // 用來判斷哪些程式開始或結束播放
+    let mut known_sessions = audio_backend().get_sessions().unwrap_or_default();
+
+    while let Ok(first) = receiver.recv() {
+        let mut events = vec![first];
+        let deadline = Instant::now() + COALESCE_WINDOW;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match receiver.recv_timeout(remaining) {
+                Ok(event) => events.push(event),
+                Err(_) => break,
+            }
+        }
+
+        let mut sessions_changed = false;
+        let mut volumes = HashMap::new();
+        let mut default_devices = HashMap::new();
+        let mut rule_events = Vec::new();
+
+        for event in events {
+            match event {
+                AudioEvent::SessionsChanged => sessions_changed = true,
+                AudioEvent::DeviceVolumeChanged {
+                    direction,
+                    volume,
+                    is_muted,
+                } => {
+                    volumes.insert(direction, (volume, is_muted));
+                }
+                AudioEvent::DefaultDeviceChanged {
+                    direction,
+                    role,
+                    device_id,
+                } => {
+                    default_devices.insert((direction, role), device_id);
+                }
+                AudioEvent::DeviceAdded {
+                    direction,
+                    device_id,
+                } => {
+                    publish(
+                        "device_added",
+                        serde_json::json!({ "device_id": device_id, "direction": direction }),
+                    );
+                    rule_events.push(RuleEvent::DeviceAdded { device_id });
+                }
+                AudioEvent::DeviceRemoved {
+                    direction,
+                    device_id,
+                } => {
+                    publish(
+                        "device_removed",
+                        serde_json::json!({ "device_id": device_id, "direction": direction }),
+                    );
+                    rule_events.push(RuleEvent::DeviceRemoved { device_id });
+                }
+            }
+        }
+
+        for direction in [DeviceDirection::Render, DeviceDirection::Capture] {
+            let (volume_event, default_event) = device_event_types(direction);
+
+            for role in DeviceRole::ALL {
+                let Some(device_id) = default_devices.remove(&(direction, role)) else {
+                    continue;
+                };
+                publish(
+                    default_event,
+                    serde_json::json!({ "device_id": device_id, "role": role }),
+                );
+
+                // 規則與音量都以一般用途的預設裝置為準
+                if role != DeviceRole::Console {
+                    continue;
+                }
+                rule_events.push(RuleEvent::DefaultDeviceChanged { device_id });
+
+                // 換了預設裝置後音量跟著不同，重新讀取一次
+                let backend = audio_backend();
+                if let (Ok(v), Ok(is_muted)) = (
+                    backend.get_default_device_volume(direction),
+                    backend.get_default_device_mute(direction),
+                ) {
+                    volumes.insert(direction, (v, is_muted));
+                }
+            }
+
+            if let Some((v, is_muted)) = volumes.remove(&direction) {
+                if last_volume.insert(direction, (v, is_muted)) != Some((v, is_muted)) {
+                    publish(
+                        volume_event,
+                        serde_json::json!({ "volume": v, "is_muted": is_muted }),
+                    );
+                }
+            }
+        }
+
+        if sessions_changed {
+            // 新出現的會話套用記住的音量與輸出裝置後再發布，避免客戶端看到兩次變化
+            if let Ok(mut sessions) = audio_backend().get_sessions() {
+                if reapply_remembered(&sessions) | reapply_routes(&sessions) {
+                    sessions = audio_backend().get_sessions().unwrap_or(sessions);
+                }
+
+                // 降低音量前要先有記住的音量，還原時才會回到正確的值
+                if let Some(active) = update_ducking(&sessions) {
+                    publish("ducking_changed", serde_json::json!({ "active": active }));
+                }
+
+                rule_events.extend(session_rule_events(&known_sessions, &sessions));
+                known_sessions = sessions;
+            }
+
+            match get_audio_sessions(None) {
+                Ok(sessions) => publish("audio_sessions_updated", serde_json::json!(sessions)),
+                Err(_e) => {
+                    debug_log!("無法獲取音訊會話: {}", _e);
+                }
+            }
+        }
+
+        for event in &rule_events {
+            handle_rule_event(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::backend::set_audio_backend;
+    use crate::audio::mock::MockAudioBackend;
+    use crate::utils::backend_test_lock;
+
+    #[tokio::test]
+    async fn backend_events_become_published_messages() {
+        let _guard = backend_test_lock().await;
+        let mock = Arc::new(MockAudioBackend::fixture());
+        set_audio_backend(mock.clone());
+
+        let (sender, receiver) = mpsc::channel();
+        audio_backend().watch(sender).unwrap();
+
+        mock.update(|state| state.sessions.truncate(1));
+        mock.emit(AudioEvent::SessionsChanged);
+        mock.emit(AudioEvent::SessionsChanged);
+        mock.emit(AudioEvent::DeviceVolumeChanged {
+            direction: DeviceDirection::Render,
+            volume: 0.3,
+            is_muted: false,
+        });
+        mock.emit(AudioEvent::DeviceVolumeChanged {
+            direction: DeviceDirection::Render,
+            volume: 0.4,
+            is_muted: true,
+        });
+        mock.emit(AudioEvent::DeviceAdded {
+            direction: DeviceDirection::Render,
+            device_id: "usb".to_string(),
+        });
+        mock.stop_watching();
+
+        let mut published = Vec::new();
+        run_audio_monitor(receiver, |event_type, data| {
+            published.push((event_type.to_string(), data));
+        });
+
+        let types: Vec<&str> = published.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(
+            types,
+            ["device_added", "device_volume_updated", "audio_sessions_updated"]
+        );
+        assert_eq!(published[1].1["volume"], 0.4f32 as f64);
+        assert_eq!(published[1].1["is_muted"], true);
+        assert_eq!(published[2].1.as_array().unwrap().len(), 1);
+    }
+}
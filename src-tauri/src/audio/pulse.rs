@@ -0,0 +1,1134 @@
+use crate::audio::backend::{AudioBackend, AudioEvent};
+use crate::audio::sessions::session_group_key;
+use crate::models::{
+    AudioDevice, AudioLevels, AudioSession, DeviceDirection, DeviceRole, DeviceState, FormFactor,
+};
+use crate::utils::debug_log;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// PulseAudio 的 100% 音量 (PA_VOLUME_NORM)
+const PA_VOLUME_NORM: f32 = 65536.0;
+
+/// 峰值只用來顯示音量條，單聲道低取樣率即可
+const METER_FORMAT: [&str; 5] = [
+    "--raw",
+    "--format=float32le",
+    "--channels=1",
+    "--rate=8000",
+    "--latency-msec=50",
+];
+
+/// 重新比對輸出裝置與串流的間隔，避免每次取樣都執行 pactl
+const METER_REFRESH: Duration = Duration::from_secs(1);
+
+/// Linux PulseAudio 後端
+/// 透過 `pactl` 操作，因此同樣適用於 PipeWire (pipewire-pulse)
+pub struct PulseBackend;
+
+#[derive(Debug, Deserialize)]
+struct PaChannelVolume {
+    value: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaServerInfo {
+    default_sink_name: Option<String>,
+    default_source_name: Option<String>,
+}
+
+impl PaServerInfo {
+    fn default_name(self, direction: DeviceDirection) -> Option<String> {
+        match direction {
+            DeviceDirection::Render => self.default_sink_name,
+            DeviceDirection::Capture => self.default_source_name,
+        }
+    }
+}
+
+/// sink (輸出) 或 source (輸入)
+#[derive(Debug, Deserialize)]
+struct PaDevice {
+    #[serde(default)]
+    index: u32,
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    mute: bool,
+    #[serde(default)]
+    volume: HashMap<String, PaChannelVolume>,
+    #[serde(default)]
+    properties: HashMap<String, String>,
+    /// 例如 "s16le 2ch 48000Hz"
+    #[serde(default)]
+    sample_specification: String,
+    #[serde(default)]
+    ports: Vec<PaPort>,
+    #[serde(default)]
+    active_port: Option<String>,
+}
+
+/// 裝置上的連接埠，例如喇叭與耳機插孔
+#[derive(Debug, Deserialize)]
+struct PaPort {
+    name: String,
+    #[serde(default, rename = "type")]
+    port_type: String,
+    /// "available"、"not available" 或 "availability unknown"
+    #[serde(default)]
+    availability: String,
+}
+
+impl PaDevice {
+    /// 每個 sink 都附帶一個錄製其輸出的 monitor source，不是真正的麥克風
+    fn is_monitor(&self) -> bool {
+        self.properties.get("device.class").map(String::as_str) == Some("monitor")
+            || self.name.ends_with(".monitor")
+    }
+
+    fn active_port(&self) -> Option<&PaPort> {
+        let active = self.active_port.as_ref()?;
+        self.ports.iter().find(|port| &port.name == active)
+    }
+
+    /// 使用中的連接埠是否接上，無法偵測時為 None
+    fn jack_connected(&self) -> Option<bool> {
+        match self.active_port()?.availability.as_str() {
+            "available" => Some(true),
+            "not available" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// PulseAudio 只列出存在的裝置，唯一能判斷的是插孔沒有接上
+    fn state(&self) -> DeviceState {
+        if self.jack_connected() == Some(false) {
+            DeviceState::Unplugged
+        } else {
+            DeviceState::Active
+        }
+    }
+
+    /// 音效卡名稱，藍牙等沒有音效卡的裝置使用產品名稱
+    fn interface_name(&self) -> Option<String> {
+        ["alsa.card_name", "device.product.name"]
+            .iter()
+            .find_map(|key| self.properties.get(*key))
+            .cloned()
+    }
+
+    /// 依序參考 device.form_factor、連接埠類型與連接埠名稱
+    fn form_factor(&self, direction: DeviceDirection) -> FormFactor {
+        let form_factor_property = self.properties.get("device.form_factor");
+        let from_property = match form_factor_property.map(String::as_str) {
+            Some("speaker") => Some(FormFactor::Speakers),
+            Some("headphone") => Some(FormFactor::Headphones),
+            Some("headset" | "hands-free") => Some(FormFactor::Headset),
+            Some("handset") => Some(FormFactor::Handset),
+            Some("microphone" | "webcam") => Some(FormFactor::Microphone),
+            Some("tv") => Some(FormFactor::Hdmi),
+            _ => None,
+        };
+        if let Some(form_factor) = from_property {
+            return form_factor;
+        }
+
+        let Some(port) = self.active_port() else {
+            return FormFactor::Unknown;
+        };
+        let port_type = port.port_type.to_lowercase();
+        let port_name = port.name.to_lowercase();
+        let matches = |keyword: &str| port_type.contains(keyword) || port_name.contains(keyword);
+
+        if matches("hdmi") || matches("displayport") {
+            FormFactor::Hdmi
+        } else if matches("spdif") || matches("iec958") {
+            FormFactor::Spdif
+        } else if matches("headset") {
+            FormFactor::Headset
+        } else if matches("headphone") {
+            FormFactor::Headphones
+        } else if matches("line") {
+            FormFactor::LineLevel
+        } else if matches("mic") {
+            FormFactor::Microphone
+        } else if matches("speaker")
+            || (direction == DeviceDirection::Render && port_name.contains("analog-output"))
+        {
+            FormFactor::Speakers
+        } else {
+            FormFactor::Unknown
+        }
+    }
+
+    /// 從 sample_specification 取得聲道數與取樣率
+    fn sample_spec(&self) -> (Option<u16>, Option<u32>) {
+        let mut channels = None;
+        let mut sample_rate = None;
+
+        for part in self.sample_specification.split_whitespace() {
+            if let Some(value) = part.strip_suffix("ch") {
+                channels = value.parse().ok();
+            } else if let Some(value) = part.strip_suffix("Hz") {
+                sample_rate = value.parse().ok();
+            }
+        }
+
+        (channels, sample_rate)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PaSinkInput {
+    index: u32,
+    #[serde(default)]
+    mute: bool,
+    /// 暫停播放的串流會被 cork
+    #[serde(default)]
+    corked: bool,
+    /// 串流所在的 sink 編號
+    #[serde(default)]
+    sink: u32,
+    #[serde(default)]
+    volume: HashMap<String, PaChannelVolume>,
+    #[serde(default)]
+    properties: HashMap<String, String>,
+}
+
+/// 執行 pactl 並回傳標準輸出
+fn pactl(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("pactl")
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| format!("無法執行 pactl: {:?}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "pactl {} 失敗: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 以 JSON 格式執行 pactl 並解析結果
+fn pactl_json<T: for<'de> Deserialize<'de>>(args: &[&str]) -> Result<T, String> {
+    let mut full_args = vec!["--format=json"];
+    full_args.extend_from_slice(args);
+
+    let output = pactl(&full_args)?;
+    serde_json::from_str(&output).map_err(|e| format!("無法解析 pactl 輸出: {:?}", e))
+}
+
+/// 各聲道音量的平均值 (0.0 ~ 1.0)
+fn average_volume(volume: &HashMap<String, PaChannelVolume>) -> f32 {
+    if volume.is_empty() {
+        return 0.0;
+    }
+
+    let total: f32 = volume.values().map(|v| v.value as f32).sum();
+    (total / volume.len() as f32 / PA_VOLUME_NORM).clamp(0.0, 1.0)
+}
+
+/// 將音量 (0.0 ~ 1.0) 轉換為 pactl 接受的原始值
+fn raw_volume(volume: f32) -> String {
+    ((volume.clamp(0.0, 1.0) * PA_VOLUME_NORM).round() as u32).to_string()
+}
+
+/// 應用程式名稱，優先使用執行檔名稱，與 Windows 一致
+fn sink_input_name(input: &PaSinkInput) -> String {
+    let props = &input.properties;
+
+    props
+        .get("application.process.binary")
+        .or_else(|| props.get("application.name"))
+        .filter(|name| !name.is_empty())
+        .cloned()
+        .or_else(|| {
+            props
+                .get("application.process.id")
+                .map(|pid| format!("PID {}", pid))
+        })
+        .unwrap_or_else(|| format!("Sink Input {}", input.index))
+}
+
+/// pactl 中對應裝置方向的類別，"sink" 或 "source"
+fn pa_facility(direction: DeviceDirection) -> &'static str {
+    match direction {
+        DeviceDirection::Render => "sink",
+        DeviceDirection::Capture => "source",
+    }
+}
+
+/// pactl 代表預設裝置的名稱
+fn pa_default(direction: DeviceDirection) -> &'static str {
+    match direction {
+        DeviceDirection::Render => "@DEFAULT_SINK@",
+        DeviceDirection::Capture => "@DEFAULT_SOURCE@",
+    }
+}
+
+/// 列出指定方向的裝置，不含 monitor source
+fn pa_devices(direction: DeviceDirection) -> Result<Vec<PaDevice>, String> {
+    let list = format!("{}s", pa_facility(direction));
+    let devices: Vec<PaDevice> = pactl_json(&["list", &list])?;
+    Ok(devices
+        .into_iter()
+        .filter(|device| !device.is_monitor())
+        .collect())
+}
+
+fn default_device(direction: DeviceDirection) -> Result<PaDevice, String> {
+    let info: PaServerInfo = pactl_json(&["info"])?;
+    let default_name = info
+        .default_name(direction)
+        .ok_or("無法取得默認設備".to_string())?;
+
+    pa_devices(direction)?
+        .into_iter()
+        .find(|device| device.name == default_name)
+        .ok_or(format!("找不到默認設備: '{}'", default_name))
+}
+
+/// 依名稱尋找輸出裝置或麥克風
+fn find_device(device_id: &str) -> Result<(DeviceDirection, PaDevice), String> {
+    for direction in [DeviceDirection::Render, DeviceDirection::Capture] {
+        if let Some(device) = pa_devices(direction)?
+            .into_iter()
+            .find(|device| device.name == device_id)
+        {
+            return Ok((direction, device));
+        }
+    }
+
+    Err(format!("找不到裝置: '{}'", device_id))
+}
+
+fn default_device_name(direction: DeviceDirection) -> Option<String> {
+    pactl_json::<PaServerInfo>(&["info"])
+        .ok()
+        .and_then(|info| info.default_name(direction))
+}
+
+/// 裝置索引 -> 名稱，移除事件只帶索引，需要事先記下
+fn device_names(direction: DeviceDirection) -> HashMap<String, String> {
+    pa_devices(direction)
+        .map(|devices| {
+            devices
+                .into_iter()
+                .map(|device| (device.index.to_string(), device.name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 監聽期間記下的裝置名稱與預設裝置
+#[derive(Default)]
+struct KnownDevices {
+    names: HashMap<DeviceDirection, HashMap<String, String>>,
+    defaults: HashMap<DeviceDirection, Option<String>>,
+}
+
+impl KnownDevices {
+    fn load() -> Self {
+        let mut known = Self::default();
+        for direction in [DeviceDirection::Render, DeviceDirection::Capture] {
+            known.names.insert(direction, device_names(direction));
+            known
+                .defaults
+                .insert(direction, default_device_name(direction));
+        }
+        known
+    }
+}
+
+/// 解析 `pactl subscribe` 的一行輸出，例如 "Event 'change' on sink-input #12"
+/// 回傳 (事件, 類別, 索引)
+fn parse_subscribe_line(line: &str) -> Option<(&str, &str, &str)> {
+    let rest = line.trim().strip_prefix("Event '")?;
+    let (event, rest) = rest.split_once("' on ")?;
+    let (facility, index) = rest.split_once(" #")?;
+    Some((event, facility, index))
+}
+
+/// 將一筆訂閱事件轉換成音訊事件，並更新記下的裝置名稱與預設裝置
+fn subscribe_events(
+    event: &str,
+    facility: &str,
+    index: &str,
+    known: &mut KnownDevices,
+) -> Vec<AudioEvent> {
+    let mut events = Vec::new();
+
+    let direction = match facility {
+        "sink-input" => {
+            events.push(AudioEvent::SessionsChanged);
+            return events;
+        }
+        "server" => {
+            for direction in [DeviceDirection::Render, DeviceDirection::Capture] {
+                let current = default_device_name(direction);
+                let previous = known.defaults.entry(direction).or_default();
+                if current != *previous {
+                    *previous = current.clone();
+                    // 只有一個預設裝置，所有角色同時改變
+                    if let Some(device_id) = current {
+                        events.extend(DeviceRole::ALL.into_iter().map(|role| {
+                            AudioEvent::DefaultDeviceChanged {
+                                direction,
+                                role,
+                                device_id: device_id.clone(),
+                            }
+                        }));
+                    }
+                }
+            }
+            return events;
+        }
+        "sink" => DeviceDirection::Render,
+        "source" => DeviceDirection::Capture,
+        _ => return events,
+    };
+
+    let names = known.names.entry(direction).or_default();
+    let default_name = known.defaults.get(&direction).cloned().flatten();
+
+    match event {
+        "new" => {
+            *names = device_names(direction);
+            if let Some(name) = names.get(index) {
+                events.push(AudioEvent::DeviceAdded {
+                    direction,
+                    device_id: name.clone(),
+                });
+            }
+        }
+        "remove" => {
+            if let Some(name) = names.remove(index) {
+                events.push(AudioEvent::DeviceRemoved {
+                    direction,
+                    device_id: name,
+                });
+            }
+        }
+        _ if names.get(index).is_some() && names.get(index) == default_name.as_ref() => {
+            let devices = pa_devices(direction).unwrap_or_default();
+            if let Some(device) = devices.iter().find(|d| d.index.to_string() == index) {
+                events.push(AudioEvent::DeviceVolumeChanged {
+                    direction,
+                    volume: average_volume(&device.volume),
+                    is_muted: device.mute,
+                });
+            }
+        }
+        _ => {}
+    }
+
+    events
+}
+
+fn sink_inputs() -> Result<Vec<PaSinkInput>, String> {
+    pactl_json(&["list", "sink-inputs"])
+}
+
+fn find_sink_input(session_id: &str) -> Result<PaSinkInput, String> {
+    sink_inputs()?
+        .into_iter()
+        .find(|input| input.index.to_string() == session_id)
+        .ok_or(format!("找不到: '{}'", session_id))
+}
+
+/// 以 parec 錄製 monitor 來源，持續記錄收到的最大振幅
+struct PeakMeter {
+    child: Child,
+    /// 非負的 f32 以位元比較時與數值大小的順序相同，可以直接使用 fetch_max
+    peak: Arc<AtomicU32>,
+}
+
+impl PeakMeter {
+    fn spawn(target: String) -> Result<Self, String> {
+        let mut child = Command::new("parec")
+            .args(METER_FORMAT)
+            .arg(target)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("無法執行 parec: {:?}", e))?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or("無法讀取 parec 輸出".to_string())?;
+
+        let peak = Arc::new(AtomicU32::new(0));
+        let writer = Arc::clone(&peak);
+
+        thread::spawn(move || track_peak(stdout, &writer));
+
+        Ok(Self { child, peak })
+    }
+
+    /// 取出上次讀取以來的峰值並歸零
+    fn take_peak(&self) -> f32 {
+        f32::from_bits(self.peak.swap(0, Ordering::Relaxed)).min(1.0)
+    }
+}
+
+/// 讀取 little-endian f32 取樣直到結束，持續記錄最大振幅
+fn track_peak(mut reader: impl Read, peak: &AtomicU32) {
+    let mut buffer = [0u8; 1600];
+    // 上次讀取剩下不足一個取樣的位元組，保留到下次才不會讓之後的取樣錯位
+    let mut carried = 0;
+
+    while let Ok(read) = reader.read(&mut buffer[carried..]) {
+        if read == 0 {
+            break;
+        }
+        let filled = carried + read;
+        let complete = filled - filled % 4;
+
+        let max = buffer[..complete]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]).abs())
+            .fold(0.0f32, f32::max);
+        peak.fetch_max(max.to_bits(), Ordering::Relaxed);
+
+        buffer.copy_within(complete..filled, 0);
+        carried = filled - complete;
+    }
+}
+
+impl Drop for PeakMeter {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// 每個輸出裝置 (以名稱為索引) 與串流 (以索引為索引) 各一個 parec
+#[derive(Default)]
+struct PeakMeters {
+    devices: HashMap<String, PeakMeter>,
+    sessions: HashMap<String, PeakMeter>,
+    refreshed_at: Option<Instant>,
+}
+
+impl PeakMeters {
+    /// 為新的裝置與串流啟動 parec，並結束已經消失的
+    fn refresh(&mut self) -> Result<(), String> {
+        let sinks: Vec<PaDevice> = pactl_json(&["list", "sinks"])?;
+        let inputs = sink_inputs()?;
+
+        self.devices
+            .retain(|name, _| sinks.iter().any(|sink| &sink.name == name));
+        for sink in sinks {
+            if self.devices.contains_key(&sink.name) {
+                continue;
+            }
+            match PeakMeter::spawn(format!("--device={}.monitor", sink.name)) {
+                Ok(meter) => {
+                    self.devices.insert(sink.name, meter);
+                }
+                Err(_e) => {
+                    debug_log!("無法取得 {} 的峰值: {}", sink.name, _e);
+                }
+            }
+        }
+
+        self.sessions
+            .retain(|id, _| inputs.iter().any(|input| &input.index.to_string() == id));
+        for input in inputs {
+            let id = input.index.to_string();
+            if self.sessions.contains_key(&id) {
+                continue;
+            }
+            // 只錄製這個串流，不混入同一個裝置上的其他聲音
+            match PeakMeter::spawn(format!("--monitor-stream={}", input.index)) {
+                Ok(meter) => {
+                    self.sessions.insert(id, meter);
+                }
+                Err(_e) => {
+                    debug_log!("無法取得串流 {} 的峰值: {}", id, _e);
+                }
+            }
+        }
+
+        self.refreshed_at = Some(Instant::now());
+        Ok(())
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref PEAK_METERS: Mutex<PeakMeters> = Mutex::new(PeakMeters::default());
+}
+
+/// 透過 /proc 取得行程的執行檔完整路徑
+fn process_path(pid: u32) -> String {
+    std::fs::read_link(format!("/proc/{}/exe", pid))
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// 裝置 ID 使用 PulseAudio 的名稱，顯示名稱使用描述
+fn audio_device(device: PaDevice, direction: DeviceDirection, default_name: &str) -> AudioDevice {
+    let is_default = device.name == default_name;
+    let (channels, sample_rate) = device.sample_spec();
+
+    AudioDevice {
+        is_default,
+        state: device.state(),
+        interface_name: device.interface_name(),
+        form_factor: device.form_factor(direction),
+        jack_connected: device.jack_connected(),
+        channels,
+        sample_rate,
+        name: if device.description.is_empty() {
+            device.name.clone()
+        } else {
+            device.description
+        },
+        id: device.name,
+        direction,
+        default_roles: if is_default {
+            DeviceRole::ALL.to_vec()
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+/// @param sinks sink 編號對應的名稱，用來填入會話的裝置 ID
+fn sink_input_session(input: &PaSinkInput, sinks: &HashMap<String, String>) -> AudioSession {
+    let name = sink_input_name(input);
+    let pid = input
+        .properties
+        .get("application.process.id")
+        .and_then(|pid| pid.parse().ok())
+        .unwrap_or(0);
+    let path = if pid == 0 {
+        String::new()
+    } else {
+        process_path(pid)
+    };
+
+    AudioSession {
+        id: input.index.to_string(),
+        group: session_group_key(&path, &name),
+        name,
+        pid,
+        path,
+        volume: average_volume(&input.volume),
+        is_muted: input.mute,
+        is_active: !input.corked,
+        device_id: sinks.get(&input.sink.to_string()).cloned(),
+    }
+}
+
+impl AudioBackend for PulseBackend {
+    fn get_devices(
+        &self,
+        direction: DeviceDirection,
+        include_inactive: bool,
+    ) -> Result<Vec<AudioDevice>, String> {
+        let default_name = default_device_name(direction).unwrap_or_default();
+
+        Ok(pa_devices(direction)?
+            .into_iter()
+            .filter(|device| include_inactive || device.state() == DeviceState::Active)
+            .map(|device| audio_device(device, direction, &default_name))
+            .collect())
+    }
+
+    /// PulseAudio 沒有角色之分，任何角色都會設為唯一的預設裝置
+    fn set_default_device(&self, device_id: &str, _roles: &[DeviceRole]) -> Result<(), String> {
+        // 名稱可能是 sink 也可能是 source，依所在的清單決定
+        let direction = if pa_devices(DeviceDirection::Capture)?
+            .iter()
+            .any(|device| device.name == device_id)
+        {
+            DeviceDirection::Capture
+        } else {
+            DeviceDirection::Render
+        };
+
+        let command = format!("set-default-{}", pa_facility(direction));
+        pactl(&[&command, device_id]).map_err(|e| format!("設定默認設備失敗: {}", e))?;
+        Ok(())
+    }
+
+    fn get_default_device_volume(&self, direction: DeviceDirection) -> Result<f32, String> {
+        Ok(average_volume(&default_device(direction)?.volume))
+    }
+
+    fn set_default_device_volume(
+        &self,
+        direction: DeviceDirection,
+        volume: f32,
+    ) -> Result<(), String> {
+        let command = format!("set-{}-volume", pa_facility(direction));
+        pactl(&[&command, pa_default(direction), &raw_volume(volume)])
+            .map_err(|e| format!("無法設定音量: {}", e))?;
+        Ok(())
+    }
+
+    fn get_default_device_mute(&self, direction: DeviceDirection) -> Result<bool, String> {
+        Ok(default_device(direction)?.mute)
+    }
+
+    fn set_default_device_mute(
+        &self,
+        direction: DeviceDirection,
+        mute: bool,
+    ) -> Result<(), String> {
+        let mute = if mute { "1" } else { "0" };
+        let command = format!("set-{}-mute", pa_facility(direction));
+        pactl(&[&command, pa_default(direction), mute])
+            .map_err(|e| format!("無法設定靜音狀態: {}", e))?;
+        Ok(())
+    }
+
+    fn get_device_volume(&self, device_id: &str) -> Result<f32, String> {
+        Ok(average_volume(&find_device(device_id)?.1.volume))
+    }
+
+    fn set_device_volume(&self, device_id: &str, volume: f32) -> Result<(), String> {
+        let (direction, _) = find_device(device_id)?;
+        let command = format!("set-{}-volume", pa_facility(direction));
+        pactl(&[&command, device_id, &raw_volume(volume)])
+            .map_err(|e| format!("無法設定音量: {}", e))?;
+        Ok(())
+    }
+
+    fn get_device_mute(&self, device_id: &str) -> Result<bool, String> {
+        Ok(find_device(device_id)?.1.mute)
+    }
+
+    fn set_device_mute(&self, device_id: &str, mute: bool) -> Result<(), String> {
+        let (direction, _) = find_device(device_id)?;
+        let mute = if mute { "1" } else { "0" };
+        let command = format!("set-{}-mute", pa_facility(direction));
+        pactl(&[&command, device_id, mute]).map_err(|e| format!("無法設定靜音狀態: {}", e))?;
+        Ok(())
+    }
+
+    fn get_sessions(&self) -> Result<Vec<AudioSession>, String> {
+        let sinks = device_names(DeviceDirection::Render);
+
+        Ok(sink_inputs()?
+            .iter()
+            .map(|input| sink_input_session(input, &sinks))
+            .collect())
+    }
+
+    fn set_session_volume(&self, session_id: &str, volume: f32) -> Result<(), String> {
+        let input = find_sink_input(session_id)?;
+
+        pactl(&[
+            "set-sink-input-volume",
+            &input.index.to_string(),
+            &raw_volume(volume),
+        ])
+        .map_err(|e| format!("無法設定音量: {}", e))?;
+
+        Ok(())
+    }
+
+    fn set_session_mute(&self, session_id: &str, mute: bool) -> Result<(), String> {
+        let input = find_sink_input(session_id)?;
+        let mute = if mute { "1" } else { "0" };
+
+        pactl(&["set-sink-input-mute", &input.index.to_string(), mute])
+            .map_err(|e| format!("無法設定靜音: {}", e))?;
+
+        Ok(())
+    }
+
+    fn set_session_device(&self, session_id: &str, device_id: Option<&str>) -> Result<(), String> {
+        let input = find_sink_input(session_id)?;
+        let sink = match device_id {
+            Some(device_id) => pa_devices(DeviceDirection::Render)?
+                .into_iter()
+                .find(|device| device.name == device_id)
+                .map(|device| device.name)
+                .ok_or(format!("找不到裝置: '{}'", device_id))?,
+            // PulseAudio 沒有「跟隨預設裝置」的設定，移回目前的預設裝置
+            None => pa_default(DeviceDirection::Render).to_string(),
+        };
+
+        pactl(&["move-sink-input", &input.index.to_string(), &sink])
+            .map_err(|e| format!("無法切換輸出裝置: {}", e))?;
+
+        Ok(())
+    }
+
+    fn watch(&self, sender: Sender<AudioEvent>) -> Result<(), String> {
+        let mut child = Command::new("pactl")
+            .arg("subscribe")
+            .env("LC_ALL", "C")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("無法執行 pactl: {:?}", e))?;
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or("無法讀取 pactl 輸出".to_string())?;
+
+        thread::spawn(move || {
+            let mut known = KnownDevices::load();
+
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                let Some((event, facility, index)) = parse_subscribe_line(&line) else {
+                    continue;
+                };
+
+                let events = subscribe_events(event, facility, index, &mut known);
+                if events.into_iter().any(|event| sender.send(event).is_err()) {
+                    break;
+                }
+            }
+
+            let _ = child.kill();
+            let _ = child.wait();
+        });
+
+        Ok(())
+    }
+
+    fn get_peak_levels(&self) -> Result<AudioLevels, String> {
+        let mut meters = PEAK_METERS.lock().unwrap();
+
+        if meters
+            .refreshed_at
+            .map_or(true, |refreshed_at| refreshed_at.elapsed() >= METER_REFRESH)
+        {
+            meters.refresh()?;
+        }
+
+        Ok(AudioLevels {
+            devices: meters
+                .devices
+                .iter()
+                .map(|(id, meter)| (id.clone(), meter.take_peak()))
+                .collect(),
+            sessions: meters
+                .sessions
+                .iter()
+                .map(|(id, meter)| (id.clone(), meter.take_peak()))
+                .collect(),
+        })
+    }
+
+    fn stop_peak_levels(&self) {
+        *PEAK_METERS.lock().unwrap() = PeakMeters::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每次只回傳固定長度的讀取器，模擬管線的部分讀取
+    struct Chunked<'a> {
+        data: &'a [u8],
+        size: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.size.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    /// `pactl --format=json list sinks` 在 PipeWire 上的輸出 (節錄)
+    const SINKS_JSON: &str = r#"[
+        {
+            "index": 55, "state": "RUNNING",
+            "name": "alsa_output.pci-0000_00_1f.3.analog-stereo",
+            "description": "Built-in Audio Analog Stereo", "driver": "PipeWire",
+            "sample_specification": "s32le 2ch 48000Hz", "channel_map": "front-left,front-right",
+            "owner_module": 4294967295, "mute": false,
+            "volume": {
+                "front-left": {"value": 39322, "value_percent": "60%", "db": "-13.31 dB"},
+                "front-right": {"value": 26214, "value_percent": "40%", "db": "-23.88 dB"}
+            },
+            "balance": -0.33,
+            "base_volume": {"value": 65536, "value_percent": "100%", "db": "0.00 dB"},
+            "monitor_source": "alsa_output.pci-0000_00_1f.3.analog-stereo.monitor",
+            "flags": ["HARDWARE", "HW_MUTE_CTRL", "HW_VOLUME_CTRL", "DECIBEL_VOLUME", "LATENCY"],
+            "properties": {"alsa.card_name": "HDA Intel PCH", "device.class": "sound"},
+            "ports": [
+                {"name": "analog-output-speaker", "description": "Speakers", "type": "Speaker",
+                 "priority": 10000, "availability_group": "Legacy 3", "availability": "availability unknown"},
+                {"name": "analog-output-headphones", "description": "Headphones", "type": "Headphones",
+                 "priority": 9900, "availability_group": "Legacy 2", "availability": "not available"}
+            ],
+            "active_port": "analog-output-speaker", "formats": ["pcm"]
+        },
+        {
+            "index": 61, "state": "SUSPENDED",
+            "name": "bluez_output.AA_BB_CC_DD_EE_FF.1",
+            "description": "WH-1000XM4", "driver": "PipeWire",
+            "sample_specification": "s16le 2ch 44100Hz", "mute": true,
+            "volume": {"front-left": {"value": 65536}, "front-right": {"value": 65536}},
+            "properties": {"device.product.name": "WH-1000XM4", "device.form_factor": "headset"},
+            "ports": [], "active_port": null
+        },
+        {
+            "index": 70, "state": "IDLE",
+            "name": "alsa_output.pci-0000_01_00.1.hdmi-stereo",
+            "description": "HDMI Audio", "sample_specification": "s16le 2ch 48000Hz",
+            "mute": false, "volume": {"mono": {"value": 65536}},
+            "properties": {"alsa.card_name": "HDA NVidia"},
+            "ports": [
+                {"name": "hdmi-output-0", "type": "HDMI", "availability": "not available"}
+            ],
+            "active_port": "hdmi-output-0"
+        }
+    ]"#;
+
+    /// `pactl --format=json list sink-inputs` 的輸出 (節錄)
+    const SINK_INPUTS_JSON: &str = r#"[
+        {
+            "index": 87, "driver": "PipeWire", "owner_module": "4294967295", "client": "86",
+            "sink": 55, "sample_specification": "float32le 2ch 48000Hz",
+            "corked": false, "mute": false,
+            "volume": {
+                "front-left": {"value": 32768, "value_percent": "50%", "db": "-18.06 dB"},
+                "front-right": {"value": 32768, "value_percent": "50%", "db": "-18.06 dB"}
+            },
+            "properties": {
+                "application.name": "Firefox", "application.process.binary": "firefox",
+                "application.process.id": "4194999", "media.name": "AudioStream"
+            }
+        },
+        {
+            "index": 90, "sink": 99, "corked": true, "mute": true,
+            "volume": {"mono": {"value": 65536}},
+            "properties": {"application.name": "speech-dispatcher"}
+        },
+        {
+            "index": 91, "sink": 61,
+            "properties": {"application.process.id": "4194998"}
+        }
+    ]"#;
+
+    fn sinks() -> Vec<PaDevice> {
+        serde_json::from_str(SINKS_JSON).unwrap()
+    }
+
+    #[test]
+    fn sinks_map_to_audio_devices() {
+        let devices: Vec<AudioDevice> = sinks()
+            .into_iter()
+            .map(|device| {
+                audio_device(
+                    device,
+                    DeviceDirection::Render,
+                    "bluez_output.AA_BB_CC_DD_EE_FF.1",
+                )
+            })
+            .collect();
+
+        let builtin = &devices[0];
+        assert_eq!(builtin.id, "alsa_output.pci-0000_00_1f.3.analog-stereo");
+        assert_eq!(builtin.name, "Built-in Audio Analog Stereo");
+        assert!(!builtin.is_default);
+        assert!(builtin.default_roles.is_empty());
+        assert_eq!(builtin.state, DeviceState::Active);
+        assert_eq!(builtin.interface_name.as_deref(), Some("HDA Intel PCH"));
+        assert_eq!(builtin.form_factor, FormFactor::Speakers);
+        assert_eq!(builtin.jack_connected, None);
+        assert_eq!(
+            (builtin.channels, builtin.sample_rate),
+            (Some(2), Some(48000))
+        );
+
+        let headset = &devices[1];
+        assert!(headset.is_default);
+        assert_eq!(headset.default_roles, DeviceRole::ALL.to_vec());
+        assert_eq!(headset.interface_name.as_deref(), Some("WH-1000XM4"));
+        assert_eq!(headset.form_factor, FormFactor::Headset);
+        assert_eq!(headset.state, DeviceState::Active);
+
+        // 沒有接上螢幕的 HDMI 輸出
+        let hdmi = &devices[2];
+        assert_eq!(hdmi.form_factor, FormFactor::Hdmi);
+        assert_eq!(hdmi.jack_connected, Some(false));
+        assert_eq!(hdmi.state, DeviceState::Unplugged);
+    }
+
+    #[test]
+    fn device_helpers_read_pactl_fields() {
+        let sinks = sinks();
+        assert!((average_volume(&sinks[0].volume) - 0.5).abs() < 1e-4);
+        assert_eq!(average_volume(&sinks[1].volume), 1.0);
+        assert_eq!(average_volume(&HashMap::new()), 0.0);
+        assert!(sinks.iter().all(|sink| !sink.is_monitor()));
+
+        // (device.form_factor, 使用中的連接埠, 連接埠類型)
+        let cases = [
+            ("speaker", "", "", FormFactor::Speakers),
+            ("", "analog-input-mic", "Mic", FormFactor::Microphone),
+            ("", "analog-output-headset", "Headset", FormFactor::Headset),
+            ("", "iec958-stereo-output", "SPDIF", FormFactor::Spdif),
+            ("", "analog-output-lineout", "Line", FormFactor::LineLevel),
+            ("", "analog-output", "Unknown", FormFactor::Speakers),
+            ("", "", "", FormFactor::Unknown),
+        ];
+        for (form_factor, port, port_type, expected) in cases {
+            let mut device: PaDevice =
+                serde_json::from_value(serde_json::json!({ "name": "test" })).unwrap();
+            if !form_factor.is_empty() {
+                device
+                    .properties
+                    .insert("device.form_factor".to_string(), form_factor.to_string());
+            }
+            if !port.is_empty() {
+                device.ports.push(PaPort {
+                    name: port.to_string(),
+                    port_type: port_type.to_string(),
+                    availability: String::new(),
+                });
+                device.active_port = Some(port.to_string());
+            }
+            assert_eq!(
+                device.form_factor(DeviceDirection::Render),
+                expected,
+                "{}",
+                port
+            );
+        }
+
+        let monitor: PaDevice = serde_json::from_value(serde_json::json!({
+            "name": "alsa_output.pci-0000_00_1f.3.analog-stereo.monitor",
+            "sample_specification": "float32le 1ch 8000Hz"
+        }))
+        .unwrap();
+        assert!(monitor.is_monitor());
+        assert_eq!(monitor.sample_spec(), (Some(1), Some(8000)));
+        assert_eq!(monitor.state(), DeviceState::Active);
+    }
+
+    #[test]
+    fn sink_inputs_map_to_sessions() {
+        let inputs: Vec<PaSinkInput> = serde_json::from_str(SINK_INPUTS_JSON).unwrap();
+        let sinks: HashMap<String, String> = sinks()
+            .into_iter()
+            .map(|sink| (sink.index.to_string(), sink.name))
+            .collect();
+        let sessions: Vec<AudioSession> = inputs
+            .iter()
+            .map(|input| sink_input_session(input, &sinks))
+            .collect();
+
+        let firefox = &sessions[0];
+        assert_eq!(firefox.id, "87");
+        assert_eq!(firefox.name, "firefox");
+        assert_eq!(firefox.pid, 4194999);
+        assert_eq!(firefox.path, "");
+        assert_eq!(firefox.group, "firefox");
+        assert!((firefox.volume - 0.5).abs() < 1e-6);
+        assert!(firefox.is_active);
+        assert!(!firefox.is_muted);
+        assert_eq!(
+            firefox.device_id.as_deref(),
+            Some("alsa_output.pci-0000_00_1f.3.analog-stereo")
+        );
+
+        // 暫停的串流與已經不存在的 sink
+        let speech = &sessions[1];
+        assert_eq!(speech.name, "speech-dispatcher");
+        assert_eq!(speech.pid, 0);
+        assert!(!speech.is_active);
+        assert!(speech.is_muted);
+        assert_eq!(speech.device_id, None);
+
+        assert_eq!(sessions[2].name, "PID 4194998");
+        assert_eq!(sessions[2].volume, 0.0);
+        assert_eq!(raw_volume(0.5), "32768");
+        assert_eq!(raw_volume(1.5), "65536");
+    }
+
+    #[test]
+    fn subscribe_lines_become_audio_events() {
+        let lines = [
+            (
+                "Event 'change' on sink-input #12",
+                Some(("change", "sink-input", "12")),
+            ),
+            ("Event 'new' on sink #55\n", Some(("new", "sink", "55"))),
+            (
+                "Event 'remove' on source-output #3",
+                Some(("remove", "source-output", "3")),
+            ),
+            ("Event 'change' on server", None),
+            (
+                "Event 'change' on server #4294967295",
+                Some(("change", "server", "4294967295")),
+            ),
+            ("garbage", None),
+        ];
+        for (line, expected) in lines {
+            assert_eq!(parse_subscribe_line(line), expected, "{}", line);
+        }
+
+        let mut known = KnownDevices::default();
+        known.names.insert(
+            DeviceDirection::Render,
+            HashMap::from([
+                ("55".to_string(), "speakers".to_string()),
+                ("61".to_string(), "headset".to_string()),
+            ]),
+        );
+        known
+            .defaults
+            .insert(DeviceDirection::Render, Some("speakers".to_string()));
+
+        assert_eq!(
+            subscribe_events("new", "sink-input", "12", &mut known),
+            vec![AudioEvent::SessionsChanged]
+        );
+        assert_eq!(
+            subscribe_events("remove", "sink", "61", &mut known),
+            vec![AudioEvent::DeviceRemoved {
+                direction: DeviceDirection::Render,
+                device_id: "headset".to_string(),
+            }]
+        );
+        assert!(!known.names[&DeviceDirection::Render].contains_key("61"));
+
+        // 已經移除或不是預設裝置的變化不會產生事件
+        assert!(subscribe_events("remove", "sink", "61", &mut known).is_empty());
+        assert!(subscribe_events("change", "sink", "99", &mut known).is_empty());
+        assert!(subscribe_events("change", "client", "7", &mut known).is_empty());
+    }
+
+    #[test]
+    fn partial_reads_keep_samples_aligned() {
+        let samples = [0.1f32, -0.25, 0.5, 0.05, -0.75, 0.2];
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+
+        for size in [1, 3, 5, 7, 24] {
+            let peak = AtomicU32::new(0);
+            track_peak(Chunked { data: &bytes, size }, &peak);
+            assert_eq!(f32::from_bits(peak.into_inner()), 0.75, "讀取長度 {}", size);
+        }
+    }
+}
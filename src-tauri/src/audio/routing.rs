@@ -0,0 +1,161 @@
+use crate::audio::app_table::AppTable;
+use crate::audio::backend::audio_backend;
+use crate::models::AudioSession;
+use crate::utils::debug_log;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const STORE_KEY: &str = "app_routes";
+
+/// 程式指定使用的輸出裝置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppRoute {
+    pub name: String,
+    pub device_id: String,
+}
+
+lazy_static::lazy_static! {
+    /// 以分組鍵 (執行檔路徑) 為索引
+    static ref ROUTES: AppTable<BTreeMap<String, AppRoute>> = AppTable::new(STORE_KEY);
+}
+
+/// 記住這些會話所屬程式的輸出裝置，None 表示改回跟隨系統預設裝置
+pub fn remember_route(sessions: &[AudioSession], device_id: Option<&str>) {
+    let mut routes = ROUTES.write();
+
+    for session in sessions {
+        let changed = match device_id {
+            Some(device_id) => {
+                let route = AppRoute {
+                    name: session.name.clone(),
+                    device_id: device_id.to_string(),
+                };
+                routes.insert(session.group.clone(), route.clone()) != Some(route)
+            }
+            None => routes.remove(&session.group).is_some(),
+        };
+
+        if changed {
+            ROUTES.mark_dirty();
+        }
+    }
+}
+
+/// 對新出現的會話套用指定的輸出裝置，回傳是否有修改任何會話
+pub fn reapply_routes(sessions: &[AudioSession]) -> bool {
+    let routes = ROUTES.read();
+    let backend = audio_backend();
+    let mut changed = false;
+
+    for session in ROUTES.new_sessions(sessions) {
+        let Some(route) = routes.get(&session.group) else {
+            continue;
+        };
+
+        if session.device_id.as_deref() == Some(route.device_id.as_str()) {
+            continue;
+        }
+
+        // 指定的裝置沒有連接時會失敗，程式會留在目前的裝置上
+        match backend.set_session_device(&session.id, Some(&route.device_id)) {
+            Ok(()) => changed = true,
+            Err(_e) => {
+                debug_log!("無法還原 {} 的輸出裝置: {}", session.name, _e);
+            }
+        }
+    }
+
+    changed
+}
+
+pub fn app_routes() -> BTreeMap<String, AppRoute> {
+    ROUTES.read().clone()
+}
+
+/// 忘記程式指定的輸出裝置，程式不需要正在執行
+pub fn forget_route(group: &str) -> Result<(), String> {
+    if ROUTES.write().remove(group).is_none() {
+        return Err(format!("找不到: '{}'", group));
+    }
+
+    ROUTES.mark_dirty();
+    Ok(())
+}
+
+/// 從本地儲存載入各程式的輸出裝置，應在啟動時呼叫
+pub fn load_app_routes<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    ROUTES.load(app)
+}
+
+/// 有變動時才寫入本地儲存
+pub fn save_app_routes<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    ROUTES.save(app)
+}
+
+/// 獲取各程式指定的輸出裝置
+#[tauri::command]
+pub fn get_app_routes() -> BTreeMap<String, AppRoute> {
+    app_routes()
+}
+
+/// 清除程式指定的輸出裝置，之後改回跟隨系統預設裝置
+/// @param group 程式的分組鍵 (get_app_routes 的索引)
+#[tauri::command]
+pub fn clear_app_route(app: tauri::AppHandle, group: String) -> Result<(), String> {
+    forget_route(&group)?;
+    save_app_routes(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::backend::set_audio_backend;
+    use crate::audio::mock::{mock_session, MockAudioBackend};
+    use crate::utils::backend_test_lock;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn restarted_apps_return_to_their_routed_device() {
+        let _guard = backend_test_lock().await;
+        let mock = Arc::new(MockAudioBackend::fixture());
+        set_audio_backend(mock.clone());
+        reapply_routes(&mock.state().sessions);
+
+        crate::set_session_device("Spotify.exe".to_string(), Some("headset".to_string())).unwrap();
+        assert_eq!(
+            mock.state().sessions[0].device_id.as_deref(),
+            Some("headset")
+        );
+        assert!(
+            crate::set_session_device("chrome-1".to_string(), Some("nowhere".to_string())).is_err()
+        );
+
+        // 程式重新啟動後回到預設裝置
+        mock.update(|state| {
+            state.sessions = vec![
+                mock_session("spotify-2", "Spotify.exe", 300, 1.0),
+                mock_session("chrome-3", "chrome.exe", 301, 1.0),
+            ];
+        });
+        assert!(reapply_routes(&mock.state().sessions));
+
+        let sessions = mock.state().sessions;
+        assert_eq!(sessions[0].device_id.as_deref(), Some("headset"));
+        assert_eq!(sessions[1].device_id.as_deref(), Some("speakers"));
+
+        // 改回跟隨預設裝置後不再記住
+        crate::set_session_device("spotify-2".to_string(), None).unwrap();
+        assert_eq!(
+            mock.state().sessions[0].device_id.as_deref(),
+            Some("speakers")
+        );
+        assert!(app_routes().is_empty());
+
+        // 沒有在執行的程式也能清除
+        crate::set_session_device("chrome-3".to_string(), Some("headset".to_string())).unwrap();
+        mock.update(|state| state.sessions.clear());
+        forget_route(&app_routes().into_keys().next().unwrap()).unwrap();
+        assert!(app_routes().is_empty());
+        assert!(forget_route("missing").is_err());
+    }
+}
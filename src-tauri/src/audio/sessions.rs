@@ -1,152 +1,141 @@
+use crate::audio::backend::audio_backend;
+use crate::audio::memory::remember;
+use crate::audio::routing::remember_route;
 use crate::models::AudioSession;
-use std::collections::HashMap;
 
-#[tauri::command]
-pub fn get_audio_sessions() -> Result<Vec<AudioSession>, String> {
-    #[cfg(target_os = "windows")]
-    {
-        use std::path::Path;
-        use winmix::WinMix;
-
-        unsafe {
-            let winmix = WinMix::default();
-            let sessions_result = winmix
-                .enumerate()
-                .map_err(|e| format!("無法獲取音樂資料清單: {:?}", e))?;
-
-            let mut sessions_map: HashMap<String, AudioSession> = HashMap::new();
-
-            for session in sessions_result {
-                let name = if !session.path.is_empty() {
-                    Path::new(&session.path)
-                        .file_name()
-                        .and_then(|f| f.to_str())
-                        .unwrap_or(&session.path)
-                        .to_string()
-                } else {
-                    format!("PID {}", session.pid)
-                };
-
-                let volume = session.vol.get_master_volume().unwrap_or(0.0);
-                let is_muted = session.vol.get_mute().unwrap_or(false);
-
-                sessions_map.entry(name.clone()).or_insert(AudioSession {
-                    name,
-                    volume,
-                    is_muted,
-                });
-            }
+/// 分組鍵
+/// 有執行檔路徑時使用不分大小寫的完整路徑，否則使用名稱
+pub fn session_group_key(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_lowercase()
+    } else {
+        path.to_lowercase()
+    }
+}
+
+/// 以程式名稱、完整路徑或執行檔名稱比對程式，不分大小寫
+pub fn app_matches(pattern: &str, name: &str, path: &str) -> bool {
+    let file_name = path.rsplit(['\\', '/']).next().unwrap_or_default();
 
-            let audio_sessions: Vec<AudioSession> = sessions_map.into_values().collect();
+    pattern.eq_ignore_ascii_case(name)
+        || pattern.eq_ignore_ascii_case(path)
+        || (!file_name.is_empty() && pattern.eq_ignore_ascii_case(file_name))
+}
+
+/// 將同一個程式的多個會話合併為一筆，音量取第一個會話的值
+/// 合併後的 id 為分組鍵，可直接用於設定整組的音量
+fn group_sessions(sessions: Vec<AudioSession>) -> Vec<AudioSession> {
+    let mut grouped: Vec<AudioSession> = Vec::new();
 
-            Ok(audio_sessions)
+    for session in sessions {
+        match grouped.iter_mut().find(|g| g.group == session.group) {
+            Some(existing) => {
+                existing.is_muted = existing.is_muted && session.is_muted;
+                existing.is_active = existing.is_active || session.is_active;
+            }
+            None => grouped.push(AudioSession {
+                id: session.group.clone(),
+                ..session
+            }),
         }
     }
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        Err("音樂控制 只支持 Windows :(((".to_string())
+    grouped
+}
+
+/// 找出操作對象的會話
+/// 依序比對會話 ID、分組鍵、名稱，後兩者會包含同一個程式的所有會話
+fn resolve_sessions(target: &str) -> Result<Vec<AudioSession>, String> {
+    let sessions = audio_backend().get_sessions()?;
+
+    let by_id: Vec<AudioSession> = sessions.iter().filter(|s| s.id == target).cloned().collect();
+    if !by_id.is_empty() {
+        return Ok(by_id);
+    }
+
+    let by_group: Vec<AudioSession> = sessions
+        .iter()
+        .filter(|s| s.group == target)
+        .cloned()
+        .collect();
+    if !by_group.is_empty() {
+        return Ok(by_group);
+    }
+
+    let by_name: Vec<AudioSession> = sessions
+        .into_iter()
+        .filter(|s| s.name == target)
+        .collect();
+    if !by_name.is_empty() {
+        return Ok(by_name);
     }
+
+    Err(format!("找不到: '{}'", target))
 }
 
-/// 設定指定應用程式的音量
-/// @param session_name 應用程式名稱
-/// @param volume 音量大小 (0.0 ~ 1.0)
-#[tauri::command]
-pub fn set_session_volume(session_name: String, volume: f32) -> Result<(), String> {
-    #[cfg(target_os = "windows")]
-    {
-        use std::path::Path;
-        use winmix::WinMix;
-
-        unsafe {
-            let winmix = WinMix::default();
-            let sessions = winmix
-                .enumerate()
-                .map_err(|e| format!("無法獲取音樂資料清單: {:?}", e))?;
-
-            let mut found = false;
-            for session in sessions {
-                let name = if !session.path.is_empty() {
-                    Path::new(&session.path)
-                        .file_name()
-                        .and_then(|f| f.to_str())
-                        .unwrap_or(&session.path)
-                        .to_string()
-                } else {
-                    format!("PID {}", session.pid)
-                };
-
-                if name == session_name {
-                    session
-                        .vol
-                        .set_master_volume(volume)
-                        .map_err(|e| format!("無法設定音量: {:?}", e))?;
-                    found = true;
-                }
-            }
+/// 設定程式的音量但不記住，供規則等自動化操作使用，回傳被修改的會話
+pub fn apply_session_volume(target: &str, volume: f32) -> Result<Vec<AudioSession>, String> {
+    let backend = audio_backend();
+    let sessions = resolve_sessions(target)?;
+    for session in &sessions {
+        backend.set_session_volume(&session.id, volume)?;
+    }
+    Ok(sessions)
+}
 
-            if found {
-                Ok(())
-            } else {
-                Err(format!("找不到: '{}'", session_name))
-            }
-        }
+/// 設定程式的靜音狀態但不記住，回傳被修改的會話
+pub fn apply_session_mute(target: &str, mute: bool) -> Result<Vec<AudioSession>, String> {
+    let backend = audio_backend();
+    let sessions = resolve_sessions(target)?;
+    for session in &sessions {
+        backend.set_session_mute(&session.id, mute)?;
     }
+    Ok(sessions)
+}
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        Err("音樂控制 只支持 Windows :(((".to_string())
+/// 獲取所有應用程式的音訊會話
+/// @param grouped 是否將同一個程式的多個會話合併為一筆 (預設為否)
+#[tauri::command]
+pub fn get_audio_sessions(grouped: Option<bool>) -> Result<Vec<AudioSession>, String> {
+    let sessions = audio_backend().get_sessions()?;
+
+    if grouped.unwrap_or(false) {
+        Ok(group_sessions(sessions))
+    } else {
+        Ok(sessions)
     }
 }
 
+/// 設定指定應用程式的音量，程式重新啟動後會自動套用
+/// @param session_id 會話 ID；傳入分組鍵或程式名稱時會套用到該程式的所有會話
+/// @param volume 音量大小 (0.0 ~ 1.0)
+#[tauri::command]
+pub fn set_session_volume(session_id: String, volume: f32) -> Result<(), String> {
+    let sessions = apply_session_volume(&session_id, volume)?;
+    remember(&sessions, Some(volume), None);
+    Ok(())
+}
+
 /// 設定指定應用程式的靜音狀態
-/// @param session_name 應用程式名稱
+/// @param session_id 會話 ID；傳入分組鍵或程式名稱時會套用到該程式的所有會話
 /// @param mute 是否靜音 (true=靜音, false=取消靜音)
 #[tauri::command]
-pub fn set_session_mute(session_name: String, mute: bool) -> Result<(), String> {
-    #[cfg(target_os = "windows")]
-    {
-        use std::path::Path;
-        use winmix::WinMix;
-
-        unsafe {
-            let winmix = WinMix::default();
-            let sessions = winmix
-                .enumerate()
-                .map_err(|e| format!("無法獲取音樂資料清單: {:?}", e))?;
-
-            let mut found = false;
-            for session in sessions {
-                let name = if !session.path.is_empty() {
-                    Path::new(&session.path)
-                        .file_name()
-                        .and_then(|f| f.to_str())
-                        .unwrap_or(&session.path)
-                        .to_string()
-                } else {
-                    format!("PID {}", session.pid)
-                };
-
-                if name == session_name {
-                    session
-                        .vol
-                        .set_mute(mute)
-                        .map_err(|e| format!("無法設定靜音: {:?}", e))?;
-                    found = true;
-                }
-            }
-
-            if found {
-                Ok(())
-            } else {
-                Err(format!("找不到: '{}'", session_name))
-            }
-        }
-    }
+pub fn set_session_mute(session_id: String, mute: bool) -> Result<(), String> {
+    let sessions = apply_session_mute(&session_id, mute)?;
+    remember(&sessions, None, Some(mute));
+    Ok(())
+}
 
-    #[cfg(not(target_os = "windows"))]
-    {
-        Err("音樂控制 只支持 Windows :(((".to_string())
+/// 設定指定應用程式使用的輸出裝置，程式重新啟動後會自動套用
+/// @param session_id 會話 ID；傳入分組鍵或程式名稱時會套用到該程式的所有會話
+/// @param device_id 輸出裝置 ID，未指定時改回跟隨系統預設裝置
+#[tauri::command]
+pub fn set_session_device(session_id: String, device_id: Option<String>) -> Result<(), String> {
+    let backend = audio_backend();
+    let sessions = resolve_sessions(&session_id)?;
+    for session in &sessions {
+        backend.set_session_device(&session.id, device_id.as_deref())?;
     }
+    remember_route(&sessions, device_id.as_deref());
+    Ok(())
 }
@@ -0,0 +1,968 @@
+use crate::audio::backend::{AudioBackend, AudioEvent};
+use crate::audio::sessions::session_group_key;
+use crate::models::{
+    AudioDevice, AudioLevels, AudioSession, DeviceDirection, DeviceRole, DeviceState, FormFactor,
+};
+use crate::utils::ComGuard;
+use std::cell::RefCell;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+use windows::Win32::Media::Audio::Endpoints::{IAudioEndpointVolume, IAudioMeterInformation};
+use windows::Win32::Media::Audio::*;
+use windows::Win32::System::Com::*;
+
+/// Windows WASAPI 後端
+pub struct WasapiBackend;
+
+/// 裝置方向對應的 WASAPI 資料流
+pub(crate) fn data_flow(direction: DeviceDirection) -> EDataFlow {
+    match direction {
+        DeviceDirection::Render => eRender,
+        DeviceDirection::Capture => eCapture,
+    }
+}
+
+/// 裝置角色對應的 WASAPI 角色
+pub(crate) fn endpoint_role(role: DeviceRole) -> ERole {
+    match role {
+        DeviceRole::Console => eConsole,
+        DeviceRole::Multimedia => eMultimedia,
+        DeviceRole::Communications => eCommunications,
+    }
+}
+
+/// WASAPI 角色對應的裝置角色
+pub(crate) fn device_role(role: ERole) -> Option<DeviceRole> {
+    DeviceRole::ALL
+        .into_iter()
+        .find(|r| endpoint_role(*r) == role)
+}
+
+/// WASAPI 裝置狀態對應的裝置狀態
+fn device_state(state: DEVICE_STATE) -> DeviceState {
+    match state {
+        DEVICE_STATE_DISABLED => DeviceState::Disabled,
+        DEVICE_STATE_NOTPRESENT => DeviceState::NotPresent,
+        DEVICE_STATE_UNPLUGGED => DeviceState::Unplugged,
+        _ => DeviceState::Active,
+    }
+}
+
+/// PKEY_AudioEndpoint_FormFactor 的值對應的裝置外型
+fn form_factor(value: u32) -> FormFactor {
+    let form_factors = [
+        (Speakers, FormFactor::Speakers),
+        (Headphones, FormFactor::Headphones),
+        (Headset, FormFactor::Headset),
+        (Handset, FormFactor::Handset),
+        (Microphone, FormFactor::Microphone),
+        (LineLevel, FormFactor::LineLevel),
+        (DigitalAudioDisplayDevice, FormFactor::Hdmi),
+        (SPDIF, FormFactor::Spdif),
+        (RemoteNetworkDevice, FormFactor::Network),
+    ];
+
+    form_factors
+        .into_iter()
+        .find(|(endpoint_form_factor, _)| endpoint_form_factor.0 == value as i32)
+        .map(|(_, form_factor)| form_factor)
+        .unwrap_or_default()
+}
+
+/// 混音格式的聲道數與取樣率，只有啟用中的裝置可以取得
+/// 呼叫前需先初始化 COM
+unsafe fn mix_format(device: &IMMDevice) -> Option<(u16, u32)> {
+    let client: IAudioClient = device.Activate(CLSCTX_ALL, None).ok()?;
+    let format = client.GetMixFormat().ok()?;
+    if format.is_null() {
+        return None;
+    }
+
+    let WAVEFORMATEX {
+        nChannels,
+        nSamplesPerSec,
+        ..
+    } = format.read_unaligned();
+    CoTaskMemFree(Some(format as *const _));
+
+    Some((nChannels, nSamplesPerSec))
+}
+
+/// 透過裝置拓樸查詢插孔是否接上，有多個插孔時任一接上即為接上
+/// 不支援插孔偵測的裝置 (例如 USB 與藍牙) 返回 None
+/// 呼叫前需先初始化 COM
+unsafe fn jack_connected(device: &IMMDevice) -> Option<bool> {
+    use windows::core::Interface;
+    use windows::Win32::Media::KernelStreaming::{IKsJackDescription, KSJACK_DESCRIPTION};
+
+    let topology: IDeviceTopology = device.Activate(CLSCTX_ALL, None).ok()?;
+    let part: IPart = topology
+        .GetConnector(0)
+        .and_then(|connector| connector.GetConnectedTo())
+        .and_then(|connector| connector.cast())
+        .ok()?;
+
+    let mut jack_description = std::ptr::null_mut();
+    part.Activate(
+        CLSCTX_ALL.0,
+        &IKsJackDescription::IID,
+        Some(&mut jack_description),
+    )
+    .ok()?;
+    let jack_description = IKsJackDescription::from_raw(jack_description);
+
+    (0..jack_description.GetJackCount().ok()?)
+        .filter_map(|i| {
+            let mut description = KSJACK_DESCRIPTION::default();
+            jack_description
+                .GetJackDescription(i, &mut description)
+                .ok()
+                .map(|_| description.IsConnected.as_bool())
+        })
+        .reduce(|a, b| a || b)
+}
+
+/// 取得預設裝置的音量控制接口
+/// 呼叫前需先初始化 COM
+unsafe fn default_endpoint_volume(
+    direction: DeviceDirection,
+) -> Result<IAudioEndpointVolume, String> {
+    let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+        .map_err(|e| format!("無法獲取設備資料清單: {:?}", e))?;
+
+    let device = enumerator
+        .GetDefaultAudioEndpoint(data_flow(direction), eConsole)
+        .map_err(|e| format!("無法取得默認設備: {:?}", e))?;
+
+    device
+        .Activate(CLSCTX_ALL, None)
+        .map_err(|e| format!("無法連接音量控制接口: {:?}", e))
+}
+
+/// 取得指定裝置的音量控制接口
+/// 呼叫前需先初始化 COM
+unsafe fn device_endpoint_volume(device_id: &str) -> Result<IAudioEndpointVolume, String> {
+    use windows::core::PCWSTR;
+
+    let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+        .map_err(|e| format!("無法獲取設備資料清單: {:?}", e))?;
+
+    let device_id_wide: Vec<u16> = device_id.encode_utf16().chain(std::iter::once(0)).collect();
+    let device = enumerator
+        .GetDevice(PCWSTR::from_raw(device_id_wide.as_ptr()))
+        .map_err(|e| format!("找不到裝置 '{}': {:?}", device_id, e))?;
+
+    device
+        .Activate(CLSCTX_ALL, None)
+        .map_err(|e| format!("無法連接音量控制接口: {:?}", e))
+}
+
+/// 取得行程的執行檔完整路徑，權限不足時返回空字串
+unsafe fn process_path(pid: u32) -> String {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::*;
+
+    let handle = match OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) {
+        Ok(h) => h,
+        Err(_) => return String::new(),
+    };
+
+    let mut buffer = [0u16; 1024];
+    let mut size = buffer.len() as u32;
+    let result = QueryFullProcessImageNameW(
+        handle,
+        PROCESS_NAME_WIN32,
+        PWSTR(buffer.as_mut_ptr()),
+        &mut size,
+    );
+    let _ = CloseHandle(handle);
+
+    if result.is_err() {
+        return String::new();
+    }
+
+    String::from_utf16_lossy(&buffer[..size as usize])
+}
+
+/// 列舉所有輸出裝置上的應用程式會話
+/// 呼叫前需先初始化 COM
+unsafe fn enumerate_sessions() -> Result<Vec<(AudioSession, ISimpleAudioVolume)>, String> {
+    use windows::core::Interface;
+
+    let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+        .map_err(|e| format!("無法獲取設備資料清單: {:?}", e))?;
+
+    let collection = enumerator
+        .EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)
+        .map_err(|e| format!("無法獲取設備資料清單: {:?}", e))?;
+
+    let device_count = collection
+        .GetCount()
+        .map_err(|e| format!("無法獲取設備數量: {:?}", e))?;
+
+    let mut sessions = Vec::new();
+
+    for i in 0..device_count {
+        let device = match collection.Item(i) {
+            Ok(d) => d,
+            Err(_) => continue,
+        };
+
+        let manager: IAudioSessionManager2 = match device.Activate(CLSCTX_ALL, None) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+
+        let device_id = match device.GetId() {
+            Ok(pwstr) => {
+                let id = pwstr.to_string().ok();
+                CoTaskMemFree(Some(pwstr.0 as *const _));
+                id
+            }
+            Err(_) => None,
+        };
+
+        let session_enumerator = manager
+            .GetSessionEnumerator()
+            .map_err(|e| format!("無法獲取音樂資料清單: {:?}", e))?;
+
+        let session_count = session_enumerator.GetCount().unwrap_or(0);
+
+        for j in 0..session_count {
+            let control = match session_enumerator.GetSession(j) {
+                Ok(c) => c,
+                Err(_) => continue,
+            };
+
+            let control2: IAudioSessionControl2 = match control.cast() {
+                Ok(c) => c,
+                Err(_) => continue,
+            };
+
+            let pid = control2.GetProcessId().unwrap_or(0);
+
+            // 系統音效會話
+            if pid == 0 {
+                continue;
+            }
+
+            let id = match control2.GetSessionInstanceIdentifier() {
+                Ok(pwstr) => {
+                    let id = pwstr.to_string().unwrap_or_default();
+                    CoTaskMemFree(Some(pwstr.0 as *const _));
+                    id
+                }
+                Err(_) => continue,
+            };
+
+            let volume: ISimpleAudioVolume = match control2.cast() {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+
+            let path = process_path(pid);
+            let name = if !path.is_empty() {
+                Path::new(&path)
+                    .file_name()
+                    .and_then(|f| f.to_str())
+                    .unwrap_or(&path)
+                    .to_string()
+            } else {
+                format!("PID {}", pid)
+            };
+
+            sessions.push((
+                AudioSession {
+                    id,
+                    group: session_group_key(&path, &name),
+                    name,
+                    pid,
+                    path,
+                    volume: volume.GetMasterVolume().unwrap_or(0.0),
+                    is_muted: volume.GetMute().map(|m| m.as_bool()).unwrap_or(false),
+                    is_active: control2.GetState() == Ok(AudioSessionStateActive),
+                    device_id: device_id.clone(),
+                },
+                volume,
+            ));
+        }
+    }
+
+    Ok(sessions)
+}
+
+/// 將程式的預設輸出裝置設為指定裝置，device_id 為 None 時清除設定
+/// 透過未公開的 AudioPolicyConfig factory，與系統「應用程式音量與裝置喜好設定」相同
+/// 呼叫前需先初始化 COM
+unsafe fn set_process_render_endpoint(pid: u32, device_id: Option<&str>) -> Result<(), String> {
+    use std::ffi::c_void;
+    use windows::core::{IInspectable, Interface, GUID, HRESULT, HSTRING};
+    use windows::Win32::System::WinRT::RoGetActivationFactory;
+
+    #[repr(C)]
+    struct IAudioPolicyConfigFactoryVtbl {
+        query_interface: usize,
+        add_ref: usize,
+        release: unsafe extern "system" fn(*mut c_void) -> u32,
+        get_iids: usize,
+        get_runtime_class_name: usize,
+        get_trust_level: usize,
+        /// 音量群組、鈴聲與通話程式相關的方法，這裡不會用到
+        _unused: [usize; 19],
+        set_persisted_default_audio_endpoint:
+            unsafe extern "system" fn(*mut c_void, u32, EDataFlow, ERole, *mut c_void) -> HRESULT,
+        get_persisted_default_audio_endpoint: usize,
+        clear_all_persisted_application_default_endpoints: usize,
+    }
+
+    #[repr(C)]
+    struct IAudioPolicyConfigFactory {
+        vtable: *const IAudioPolicyConfigFactoryVtbl,
+    }
+
+    /// Windows 11 (組建 21390) 之後的版本
+    const IID_AUDIO_POLICY_CONFIG_FACTORY: GUID =
+        GUID::from_u128(0xab3d4648_e242_459f_b02f_541c70306324);
+    /// 較舊的 Windows 10
+    const IID_AUDIO_POLICY_CONFIG_FACTORY_DOWNLEVEL: GUID =
+        GUID::from_u128(0x2a59116d_6c4f_45e0_a74f_707e3fef9258);
+
+    const MMDEVAPI_TOKEN: &str = r"\\?\SWD#MMDEVAPI#";
+    const DEVINTERFACE_AUDIO_RENDER: &str = "#{e6327cad-dcec-4949-ae8a-991e976a79d2}";
+
+    let factory: IInspectable =
+        RoGetActivationFactory(&HSTRING::from("Windows.Media.Internal.AudioPolicyConfig"))
+            .map_err(|e| format!("無法取得 AudioPolicyConfig: {:?}", e))?;
+
+    let mut policy_config: *mut c_void = std::ptr::null_mut();
+    let mut last_error = None;
+    for iid in [
+        IID_AUDIO_POLICY_CONFIG_FACTORY,
+        IID_AUDIO_POLICY_CONFIG_FACTORY_DOWNLEVEL,
+    ] {
+        let hr = factory.query(&iid, &mut policy_config);
+        if hr.is_ok() && !policy_config.is_null() {
+            break;
+        }
+        last_error = Some(hr);
+        policy_config = std::ptr::null_mut();
+    }
+
+    if policy_config.is_null() {
+        return Err(format!(
+            "無法創建 IAudioPolicyConfigFactory 實例: {:?}",
+            last_error
+        ));
+    }
+
+    // 空字串代表清除設定，改回跟隨系統預設裝置
+    let endpoint = match device_id {
+        Some(id) => HSTRING::from(format!(
+            "{}{}{}",
+            MMDEVAPI_TOKEN, id, DEVINTERFACE_AUDIO_RENDER
+        )),
+        None => HSTRING::new(),
+    };
+
+    let vtable = (*(policy_config as *mut IAudioPolicyConfigFactory)).vtable;
+    let failed: Vec<String> = [eConsole, eMultimedia]
+        .into_iter()
+        .map(|role| {
+            (
+                role,
+                ((*vtable).set_persisted_default_audio_endpoint)(
+                    policy_config,
+                    pid,
+                    eRender,
+                    role,
+                    std::mem::transmute_copy::<HSTRING, *mut c_void>(&endpoint),
+                ),
+            )
+        })
+        .filter(|(_, hr)| hr.is_err())
+        .map(|(role, hr)| format!("{:?}={:?}", role, hr))
+        .collect();
+
+    ((*vtable).release)(policy_config);
+
+    if !failed.is_empty() {
+        return Err(format!("無法切換輸出裝置: {}", failed.join(", ")));
+    }
+
+    Ok(())
+}
+
+/// 依照會話 ID 取得音量控制接口
+unsafe fn find_session_volume(session_id: &str) -> Result<ISimpleAudioVolume, String> {
+    enumerate_sessions()?
+        .into_iter()
+        .find(|(session, _)| session.id == session_id)
+        .map(|(_, volume)| volume)
+        .ok_or(format!("找不到: '{}'", session_id))
+}
+
+/// 沒有收到變化通知 (例如監聽失敗) 時重新列舉峰值表的間隔
+const METER_REFRESH: Duration = Duration::from_secs(5);
+
+/// 裝置與會話的峰值表，避免每次取樣都重新列舉所有會話與行程
+struct PeakMeters {
+    devices: Vec<(String, IAudioMeterInformation)>,
+    sessions: Vec<(String, IAudioMeterInformation)>,
+    refreshed_at: Instant,
+    /// 保持 COM 初始化直到介面釋放，必須放在最後一個欄位
+    _com_guard: ComGuard,
+}
+
+impl PeakMeters {
+    unsafe fn new() -> Result<Self, String> {
+        use windows::core::Interface;
+
+        let com_guard = ComGuard::new();
+
+        let enumerator: IMMDeviceEnumerator =
+            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+                .map_err(|e| format!("無法獲取設備資料清單: {:?}", e))?;
+
+        let collection = enumerator
+            .EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)
+            .map_err(|e| format!("無法獲取設備資料清單: {:?}", e))?;
+
+        let count = collection
+            .GetCount()
+            .map_err(|e| format!("無法獲取設備數量: {:?}", e))?;
+
+        let mut devices = Vec::new();
+        for i in 0..count {
+            let Ok(device) = collection.Item(i) else {
+                continue;
+            };
+            let Ok(id) = device.GetId() else {
+                continue;
+            };
+            let Ok(id) = id.to_string() else {
+                continue;
+            };
+            let Ok(meter) = device.Activate::<IAudioMeterInformation>(CLSCTX_ALL, None) else {
+                continue;
+            };
+            devices.push((id, meter));
+        }
+
+        // 會話物件同時實作了音量與峰值的介面
+        let sessions = enumerate_sessions()?
+            .into_iter()
+            .filter_map(|(session, volume)| {
+                let meter = volume.cast::<IAudioMeterInformation>().ok()?;
+                Some((session.id, meter))
+            })
+            .collect();
+
+        Ok(Self {
+            devices,
+            sessions,
+            refreshed_at: Instant::now(),
+            _com_guard: com_guard,
+        })
+    }
+}
+
+thread_local! {
+    /// COM 介面不能跨執行緒使用，峰值只會在取樣執行緒上讀取
+    static PEAK_METERS: RefCell<Option<PeakMeters>> = const { RefCell::new(None) };
+}
+
+/// 裝置或會話有增減，下次取樣時重新列舉峰值表
+static PEAK_METERS_STALE: AtomicBool = AtomicBool::new(false);
+
+/// 由 WASAPI 通知呼叫
+pub(crate) fn invalidate_peak_meters() {
+    PEAK_METERS_STALE.store(true, Ordering::Relaxed);
+}
+
+impl AudioBackend for WasapiBackend {
+    fn get_devices(
+        &self,
+        direction: DeviceDirection,
+        include_inactive: bool,
+    ) -> Result<Vec<AudioDevice>, String> {
+        use windows::core::PWSTR;
+        use windows::Win32::Devices::FunctionDiscovery::PKEY_DeviceInterface_FriendlyName;
+        use windows::Win32::UI::Shell::PropertiesSystem::*;
+
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            let enumerator: IMMDeviceEnumerator =
+                CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+                    .map_err(|e| format!("無法獲取設備資料清單: {:?}", e))?;
+
+            // 沒有麥克風時不會有預設裝置，仍然回傳空的清單
+            let default_ids: Vec<(DeviceRole, String)> = DeviceRole::ALL
+                .into_iter()
+                .filter_map(|role| {
+                    enumerator
+                        .GetDefaultAudioEndpoint(data_flow(direction), endpoint_role(role))
+                        .and_then(|device| device.GetId())
+                        .ok()
+                        .and_then(|id| id.to_string().ok())
+                        .map(|id| (role, id))
+                })
+                .collect();
+
+            let state_mask = if include_inactive {
+                DEVICE_STATE(DEVICE_STATEMASK_ALL)
+            } else {
+                DEVICE_STATE_ACTIVE
+            };
+
+            let collection = enumerator
+                .EnumAudioEndpoints(data_flow(direction), state_mask)
+                .map_err(|e| format!("無法獲取設備資料清單: {:?}", e))?;
+
+            let count = collection
+                .GetCount()
+                .map_err(|e| format!("無法獲取設備數量: {:?}", e))?;
+
+            let mut audio_devices = Vec::new();
+
+            for i in 0..count {
+                let device = collection
+                    .Item(i)
+                    .map_err(|e| format!("無法獲取設備 {}: {:?}", i, e))?;
+
+                let id_pwstr = device
+                    .GetId()
+                    .map_err(|e| format!("無法獲取設備ID: {:?}", e))?;
+                let id = id_pwstr
+                    .to_string()
+                    .map_err(|e| format!("無法轉換ID: {:?}", e))?;
+
+                let property_store = device
+                    .OpenPropertyStore(STGM_READ)
+                    .map_err(|e| format!("無法開啟屬性儲存: {:?}", e))?;
+
+                let pkey = PROPERTYKEY {
+                    fmtid: windows::core::GUID::from_u128(0xa45c254e_df1c_4efd_8020_67d146a850e0),
+                    pid: 14,
+                };
+
+                let prop_variant = property_store
+                    .GetValue(&pkey as *const _)
+                    .map_err(|e| format!("無法獲取設備名稱: {:?}", e))?;
+
+                #[repr(C)]
+                struct PropVariantData {
+                    vt: u16,
+                    _reserved: [u16; 3],
+                    data: usize,
+                }
+
+                let pv_data = std::mem::transmute::<_, &PropVariantData>(&prop_variant);
+                let name = if pv_data.vt == 31 {
+                    let pwstr = PWSTR(pv_data.data as *mut u16);
+                    pwstr
+                        .to_string()
+                        .unwrap_or_else(|_| format!("Device {}", i))
+                } else {
+                    format!("Device {}", i)
+                };
+
+                let default_roles: Vec<DeviceRole> = default_ids
+                    .iter()
+                    .filter(|(_, default_id)| *default_id == id)
+                    .map(|(role, _)| *role)
+                    .collect();
+
+                let state = device.GetState().map(device_state).unwrap_or_default();
+
+                let interface_name = property_store
+                    .GetValue(&PKEY_DeviceInterface_FriendlyName)
+                    .map(|value| value.to_string())
+                    .ok()
+                    .filter(|value| !value.is_empty());
+
+                let form_factor = property_store
+                    .GetValue(&PKEY_AudioEndpoint_FormFactor)
+                    .ok()
+                    .and_then(|value| u32::try_from(&value).ok())
+                    .map(form_factor)
+                    .unwrap_or_default();
+
+                let mix_format = if state == DeviceState::Active {
+                    mix_format(&device)
+                } else {
+                    None
+                };
+
+                audio_devices.push(AudioDevice {
+                    is_default: default_roles.contains(&DeviceRole::Console),
+                    id,
+                    name,
+                    direction,
+                    default_roles,
+                    state,
+                    interface_name,
+                    form_factor,
+                    jack_connected: jack_connected(&device),
+                    channels: mix_format.map(|(channels, _)| channels),
+                    sample_rate: mix_format.map(|(_, sample_rate)| sample_rate),
+                });
+            }
+
+            Ok(audio_devices)
+        }
+    }
+
+    fn set_default_device(&self, device_id: &str, roles: &[DeviceRole]) -> Result<(), String> {
+        use std::ptr;
+        use windows::core::{GUID, HRESULT, PCWSTR};
+
+        #[repr(C)]
+        struct IPolicyConfigVtbl {
+            query_interface: unsafe extern "system" fn(
+                *mut std::ffi::c_void,
+                *const GUID,
+                *mut *mut std::ffi::c_void,
+            ) -> HRESULT,
+            add_ref: unsafe extern "system" fn(*mut std::ffi::c_void) -> u32,
+            release: unsafe extern "system" fn(*mut std::ffi::c_void) -> u32,
+            get_mix_format: usize,
+            get_device_format: usize,
+            reset_device_format: usize,
+            set_device_format: usize,
+            get_processing_period: usize,
+            set_processing_period: usize,
+            get_share_mode: usize,
+            set_share_mode: usize,
+            get_property_value: usize,
+            set_property_value: usize,
+            set_default_endpoint:
+                unsafe extern "system" fn(*mut std::ffi::c_void, PCWSTR, ERole) -> HRESULT,
+            set_endpoint_visibility: usize,
+        }
+
+        #[repr(C)]
+        struct IPolicyConfig {
+            vtable: *const IPolicyConfigVtbl,
+        }
+
+        #[repr(C)]
+        struct IPolicyConfigVistaVtbl {
+            query_interface: unsafe extern "system" fn(
+                *mut std::ffi::c_void,
+                *const GUID,
+                *mut *mut std::ffi::c_void,
+            ) -> HRESULT,
+            add_ref: unsafe extern "system" fn(*mut std::ffi::c_void) -> u32,
+            release: unsafe extern "system" fn(*mut std::ffi::c_void) -> u32,
+            get_mix_format: usize,
+            get_device_format: usize,
+            set_device_format: usize,
+            get_processing_period: usize,
+            set_processing_period: usize,
+            get_share_mode: usize,
+            set_share_mode: usize,
+            get_property_value: usize,
+            set_property_value: usize,
+            set_default_endpoint:
+                unsafe extern "system" fn(*mut std::ffi::c_void, PCWSTR, ERole) -> HRESULT,
+        }
+
+        #[repr(C)]
+        struct IPolicyConfigVista {
+            vtable: *const IPolicyConfigVistaVtbl,
+        }
+
+        const CLSID_POLICY_CONFIG_CLIENT: GUID =
+            GUID::from_u128(0x870af99c_171d_4f9e_af0d_e73ae00e0e4d);
+        const CLSID_POLICY_CONFIG_CLIENT_WIN7: GUID =
+            GUID::from_u128(0x294935CE_F637_4E7C_A41B_AB255460B862);
+        const CLSID_POLICY_CONFIG_CLIENT_WIN10: GUID =
+            GUID::from_u128(0x2A07407E_6497_4A18_9706_CBFCB32D35B8);
+
+        const IID_POLICY_CONFIG: GUID = GUID::from_u128(0xf8679f50_850a_41cf_9c72_430f290290c8);
+        const IID_POLICY_CONFIG_VISTA: GUID =
+            GUID::from_u128(0x568b9108_44bf_40b4_9006_86afe5b5a620);
+
+        extern "system" {
+            fn CoCreateInstance(
+                rclsid: *const GUID,
+                punkouter: *mut std::ffi::c_void,
+                dwclscontext: u32,
+                riid: *const GUID,
+                ppv: *mut *mut std::ffi::c_void,
+            ) -> HRESULT;
+        }
+
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            let attempts = [
+                (
+                    "CLSID_POLICY_CONFIG_CLIENT_WIN10",
+                    "IID_POLICY_CONFIG",
+                    CLSID_POLICY_CONFIG_CLIENT_WIN10,
+                    IID_POLICY_CONFIG,
+                ),
+                (
+                    "CLSID_POLICY_CONFIG_CLIENT_WIN7",
+                    "IID_POLICY_CONFIG",
+                    CLSID_POLICY_CONFIG_CLIENT_WIN7,
+                    IID_POLICY_CONFIG,
+                ),
+                (
+                    "CLSID_POLICY_CONFIG_CLIENT",
+                    "IID_POLICY_CONFIG_VISTA",
+                    CLSID_POLICY_CONFIG_CLIENT,
+                    IID_POLICY_CONFIG_VISTA,
+                ),
+                (
+                    "CLSID_POLICY_CONFIG_CLIENT_WIN7",
+                    "IID_POLICY_CONFIG_VISTA",
+                    CLSID_POLICY_CONFIG_CLIENT_WIN7,
+                    IID_POLICY_CONFIG_VISTA,
+                ),
+            ];
+
+            let mut policy_config: *mut std::ffi::c_void = ptr::null_mut();
+            let mut success = false;
+            let mut last_error = None;
+            let mut use_vista_interface = false;
+
+            for (i, (_clsid_name, _iid_name, clsid, iid)) in attempts.iter().enumerate() {
+                let hr = CoCreateInstance(
+                    clsid,
+                    ptr::null_mut(),
+                    CLSCTX_ALL.0,
+                    iid,
+                    &mut policy_config,
+                );
+
+                if hr.is_ok() && !policy_config.is_null() {
+                    success = true;
+                    use_vista_interface = i >= 2;
+                    break;
+                }
+                last_error = Some(hr);
+                policy_config = ptr::null_mut();
+            }
+
+            if !success || policy_config.is_null() {
+                return Err(format!(
+                    "無法創建 IPolicyConfig 實例 (嘗試了所有 CLSID/IID 組合): {:?}",
+                    last_error
+                ));
+            }
+
+            let device_id_wide: Vec<u16> =
+                device_id.encode_utf16().chain(std::iter::once(0)).collect();
+            let device_id_pcwstr = PCWSTR::from_raw(device_id_wide.as_ptr());
+
+            let (set_default_fn, release_fn) = if use_vista_interface {
+                let vtable = (*(policy_config as *mut IPolicyConfigVista)).vtable;
+                ((*vtable).set_default_endpoint, (*vtable).release)
+            } else {
+                let vtable = (*(policy_config as *mut IPolicyConfig)).vtable;
+                ((*vtable).set_default_endpoint, (*vtable).release)
+            };
+
+            let failed: Vec<String> = roles
+                .iter()
+                .map(|role| {
+                    (
+                        role,
+                        set_default_fn(policy_config, device_id_pcwstr, endpoint_role(*role)),
+                    )
+                })
+                .filter(|(_, hr)| hr.is_err())
+                .map(|(role, hr)| format!("{:?}={:?}", role, hr))
+                .collect();
+
+            release_fn(policy_config);
+
+            if !failed.is_empty() {
+                return Err(format!("設定默認設備失敗: {}", failed.join(", ")));
+            }
+
+            Ok(())
+        }
+    }
+
+    fn get_default_device_volume(&self, direction: DeviceDirection) -> Result<f32, String> {
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            default_endpoint_volume(direction)?
+                .GetMasterVolumeLevelScalar()
+                .map_err(|e| format!("無法取得音量: {:?}", e))
+        }
+    }
+
+    fn set_default_device_volume(
+        &self,
+        direction: DeviceDirection,
+        volume: f32,
+    ) -> Result<(), String> {
+        let volume = volume.max(0.0).min(1.0);
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            default_endpoint_volume(direction)?
+                .SetMasterVolumeLevelScalar(volume, std::ptr::null())
+                .map_err(|e| format!("無法設定音量: {:?}", e))
+        }
+    }
+
+    fn get_default_device_mute(&self, direction: DeviceDirection) -> Result<bool, String> {
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            default_endpoint_volume(direction)?
+                .GetMute()
+                .map(|muted| muted.as_bool())
+                .map_err(|e| format!("無法取得靜音狀態: {:?}", e))
+        }
+    }
+
+    fn set_default_device_mute(
+        &self,
+        direction: DeviceDirection,
+        mute: bool,
+    ) -> Result<(), String> {
+        use windows::Win32::Foundation::BOOL;
+
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            default_endpoint_volume(direction)?
+                .SetMute(BOOL::from(mute), std::ptr::null())
+                .map_err(|e| format!("無法設定靜音狀態: {:?}", e))
+        }
+    }
+
+    fn get_device_volume(&self, device_id: &str) -> Result<f32, String> {
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            device_endpoint_volume(device_id)?
+                .GetMasterVolumeLevelScalar()
+                .map_err(|e| format!("無法取得音量: {:?}", e))
+        }
+    }
+
+    fn set_device_volume(&self, device_id: &str, volume: f32) -> Result<(), String> {
+        let volume = volume.clamp(0.0, 1.0);
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            device_endpoint_volume(device_id)?
+                .SetMasterVolumeLevelScalar(volume, std::ptr::null())
+                .map_err(|e| format!("無法設定音量: {:?}", e))
+        }
+    }
+
+    fn get_device_mute(&self, device_id: &str) -> Result<bool, String> {
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            device_endpoint_volume(device_id)?
+                .GetMute()
+                .map(|muted| muted.as_bool())
+                .map_err(|e| format!("無法取得靜音狀態: {:?}", e))
+        }
+    }
+
+    fn set_device_mute(&self, device_id: &str, mute: bool) -> Result<(), String> {
+        use windows::Win32::Foundation::BOOL;
+
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            device_endpoint_volume(device_id)?
+                .SetMute(BOOL::from(mute), std::ptr::null())
+                .map_err(|e| format!("無法設定靜音狀態: {:?}", e))
+        }
+    }
+
+    fn get_sessions(&self) -> Result<Vec<AudioSession>, String> {
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            Ok(enumerate_sessions()?
+                .into_iter()
+                .map(|(session, _)| session)
+                .collect())
+        }
+    }
+
+    fn set_session_volume(&self, session_id: &str, volume: f32) -> Result<(), String> {
+        let volume = volume.clamp(0.0, 1.0);
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            find_session_volume(session_id)?
+                .SetMasterVolume(volume, std::ptr::null())
+                .map_err(|e| format!("無法設定音量: {:?}", e))
+        }
+    }
+
+    fn set_session_mute(&self, session_id: &str, mute: bool) -> Result<(), String> {
+        use windows::Win32::Foundation::BOOL;
+
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            find_session_volume(session_id)?
+                .SetMute(BOOL::from(mute), std::ptr::null())
+                .map_err(|e| format!("無法設定靜音: {:?}", e))
+        }
+    }
+
+    fn set_session_device(&self, session_id: &str, device_id: Option<&str>) -> Result<(), String> {
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            let (session, _) = enumerate_sessions()?
+                .into_iter()
+                .find(|(session, _)| session.id == session_id)
+                .ok_or(format!("找不到: '{}'", session_id))?;
+
+            set_process_render_endpoint(session.pid, device_id)
+        }
+    }
+
+    fn watch(&self, sender: Sender<AudioEvent>) -> Result<(), String> {
+        crate::audio::wasapi_watch::watch(sender)
+    }
+
+    fn get_peak_levels(&self) -> Result<AudioLevels, String> {
+        PEAK_METERS.with(|meters| {
+            let mut meters = meters.borrow_mut();
+
+            let stale = PEAK_METERS_STALE.swap(false, Ordering::Relaxed);
+            let meters = match meters.as_mut() {
+                Some(m) if !stale && m.refreshed_at.elapsed() < METER_REFRESH => m,
+                _ => meters.insert(unsafe { PeakMeters::new()? }),
+            };
+
+            let peaks = |meters: &[(String, IAudioMeterInformation)]| {
+                meters
+                    .iter()
+                    .map(|(id, meter)| (id.clone(), unsafe { meter.GetPeakValue() }.unwrap_or(0.0)))
+                    .collect()
+            };
+
+            Ok(AudioLevels {
+                devices: peaks(&meters.devices),
+                sessions: peaks(&meters.sessions),
+            })
+        })
+    }
+
+    fn stop_peak_levels(&self) {
+        PEAK_METERS.with(|meters| *meters.borrow_mut() = None);
+    }
+}
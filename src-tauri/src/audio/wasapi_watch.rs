@@ -0,0 +1,382 @@
+use crate::audio::backend::AudioEvent;
+use crate::audio::wasapi::{data_flow, device_role};
+use crate::models::DeviceDirection;
+use crate::utils::{debug_log, ComGuard};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+use windows::core::{implement, PCWSTR};
+use windows::Win32::Foundation::BOOL;
+use windows::Win32::Media::Audio::Endpoints::*;
+use windows::Win32::Media::Audio::*;
+use windows::Win32::System::Com::*;
+use windows::Win32::UI::Shell::PropertiesSystem::PROPERTYKEY;
+
+/// 通知回呼送給監聽執行緒的訊號
+enum Signal {
+    /// 裝置或會話增減，需要重新註冊回呼
+    Rebuild,
+    /// 事件接收端已關閉
+    Stop,
+}
+
+/// 回呼共用的發送端
+#[derive(Clone)]
+struct Notifier {
+    events: Sender<AudioEvent>,
+    signals: Sender<Signal>,
+}
+
+impl Notifier {
+    fn send(&self, event: AudioEvent) {
+        if self.events.send(event).is_err() {
+            let _ = self.signals.send(Signal::Stop);
+        }
+    }
+
+    fn rebuild(&self) {
+        crate::audio::wasapi::invalidate_peak_meters();
+        let _ = self.signals.send(Signal::Rebuild);
+    }
+}
+
+/// 裝置增減與預設裝置變更
+#[implement(IMMNotificationClient)]
+struct DeviceNotifier(Notifier);
+
+impl IMMNotificationClient_Impl for DeviceNotifier_Impl {
+    fn OnDeviceStateChanged(
+        &self,
+        pwstrdeviceid: &PCWSTR,
+        dwnewstate: DEVICE_STATE,
+    ) -> windows::core::Result<()> {
+        let device_id = unsafe { pwstrdeviceid.to_string() }.unwrap_or_default();
+
+        if let Some(direction) = unsafe { device_direction(&device_id) } {
+            self.0.send(if dwnewstate == DEVICE_STATE_ACTIVE {
+                AudioEvent::DeviceAdded {
+                    direction,
+                    device_id,
+                }
+            } else {
+                AudioEvent::DeviceRemoved {
+                    direction,
+                    device_id,
+                }
+            });
+            self.0.rebuild();
+        }
+
+        Ok(())
+    }
+
+    fn OnDeviceAdded(&self, _pwstrdeviceid: &PCWSTR) -> windows::core::Result<()> {
+        // 新裝置啟用時會另外觸發 OnDeviceStateChanged
+        Ok(())
+    }
+
+    fn OnDeviceRemoved(&self, _pwstrdeviceid: &PCWSTR) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn OnDefaultDeviceChanged(
+        &self,
+        flow: EDataFlow,
+        role: ERole,
+        pwstrdefaultdeviceid: &PCWSTR,
+    ) -> windows::core::Result<()> {
+        let direction = match flow {
+            f if f == eRender => DeviceDirection::Render,
+            f if f == eCapture => DeviceDirection::Capture,
+            _ => return Ok(()),
+        };
+        let Some(device_role) = device_role(role) else {
+            return Ok(());
+        };
+
+        let device_id = unsafe { pwstrdefaultdeviceid.to_string() }.unwrap_or_default();
+        self.0.send(AudioEvent::DefaultDeviceChanged {
+            direction,
+            role: device_role,
+            device_id,
+        });
+
+        // 音量回呼只註冊在一般用途的預設裝置上
+        if role == eConsole {
+            self.0.rebuild();
+        }
+
+        Ok(())
+    }
+
+    fn OnPropertyValueChanged(
+        &self,
+        _pwstrdeviceid: &PCWSTR,
+        _key: &PROPERTYKEY,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+}
+
+/// 預設輸出裝置或麥克風的音量與靜音
+#[implement(IAudioEndpointVolumeCallback)]
+struct VolumeNotifier(Notifier, DeviceDirection);
+
+impl IAudioEndpointVolumeCallback_Impl for VolumeNotifier_Impl {
+    fn OnNotify(&self, pnotify: *mut AUDIO_VOLUME_NOTIFICATION_DATA) -> windows::core::Result<()> {
+        if let Some(data) = unsafe { pnotify.as_ref() } {
+            self.0.send(AudioEvent::DeviceVolumeChanged {
+                direction: self.1,
+                volume: data.fMasterVolume,
+                is_muted: data.bMuted.as_bool(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// 裝置上建立了新的應用程式會話
+#[implement(IAudioSessionNotification)]
+struct SessionCreatedNotifier(Notifier);
+
+impl IAudioSessionNotification_Impl for SessionCreatedNotifier_Impl {
+    fn OnSessionCreated(
+        &self,
+        _newsession: Option<&IAudioSessionControl>,
+    ) -> windows::core::Result<()> {
+        self.0.send(AudioEvent::SessionsChanged);
+        self.0.rebuild();
+        Ok(())
+    }
+}
+
+/// 單一會話的音量、靜音與結束
+#[implement(IAudioSessionEvents)]
+struct SessionEventsNotifier(Notifier);
+
+impl IAudioSessionEvents_Impl for SessionEventsNotifier_Impl {
+    fn OnDisplayNameChanged(
+        &self,
+        _newdisplayname: &PCWSTR,
+        _eventcontext: *const windows::core::GUID,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn OnIconPathChanged(
+        &self,
+        _newiconpath: &PCWSTR,
+        _eventcontext: *const windows::core::GUID,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn OnSimpleVolumeChanged(
+        &self,
+        _newvolume: f32,
+        _newmute: BOOL,
+        _eventcontext: *const windows::core::GUID,
+    ) -> windows::core::Result<()> {
+        self.0.send(AudioEvent::SessionsChanged);
+        Ok(())
+    }
+
+    fn OnChannelVolumeChanged(
+        &self,
+        _channelcount: u32,
+        _newchannelvolumearray: *const f32,
+        _changedchannel: u32,
+        _eventcontext: *const windows::core::GUID,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn OnGroupingParamChanged(
+        &self,
+        _newgroupingparam: *const windows::core::GUID,
+        _eventcontext: *const windows::core::GUID,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn OnStateChanged(&self, newstate: AudioSessionState) -> windows::core::Result<()> {
+        // 開始或停止播放也要通知，自動降低音量依賴會話的活動狀態
+        self.0.send(AudioEvent::SessionsChanged);
+        if newstate == AudioSessionStateExpired {
+            self.0.rebuild();
+        }
+        Ok(())
+    }
+
+    fn OnSessionDisconnected(
+        &self,
+        _disconnectreason: AudioSessionDisconnectReason,
+    ) -> windows::core::Result<()> {
+        self.0.send(AudioEvent::SessionsChanged);
+        self.0.rebuild();
+        Ok(())
+    }
+}
+
+/// 判斷端點是輸出裝置還是麥克風
+unsafe fn device_direction(device_id: &str) -> Option<DeviceDirection> {
+    use windows::core::Interface;
+
+    let Ok(enumerator) =
+        CoCreateInstance::<_, IMMDeviceEnumerator>(&MMDeviceEnumerator, None, CLSCTX_ALL)
+    else {
+        return None;
+    };
+
+    let device_id_wide: Vec<u16> = device_id.encode_utf16().chain(std::iter::once(0)).collect();
+
+    enumerator
+        .GetDevice(PCWSTR::from_raw(device_id_wide.as_ptr()))
+        .and_then(|device| device.cast::<IMMEndpoint>())
+        .and_then(|endpoint| endpoint.GetDataFlow())
+        .ok()
+        .and_then(|flow| match flow {
+            f if f == eRender => Some(DeviceDirection::Render),
+            f if f == eCapture => Some(DeviceDirection::Capture),
+            _ => None,
+        })
+}
+
+/// 目前已註冊的回呼，釋放時全部取消註冊
+#[derive(Default)]
+struct Registrations {
+    endpoint_volumes: Vec<(IAudioEndpointVolume, IAudioEndpointVolumeCallback)>,
+    managers: Vec<(IAudioSessionManager2, IAudioSessionNotification)>,
+    sessions: Vec<(IAudioSessionControl, IAudioSessionEvents)>,
+}
+
+impl Drop for Registrations {
+    fn drop(&mut self) {
+        unsafe {
+            for (control, events) in &self.sessions {
+                let _ = control.UnregisterAudioSessionNotification(events);
+            }
+            for (manager, notification) in &self.managers {
+                let _ = manager.UnregisterSessionNotification(notification);
+            }
+            for (volume, callback) in &self.endpoint_volumes {
+                let _ = volume.UnregisterControlChangeNotify(callback);
+            }
+        }
+    }
+}
+
+/// 在預設輸出裝置與麥克風的音量、所有輸出裝置的會話管理器與每個會話上註冊回呼
+unsafe fn register(enumerator: &IMMDeviceEnumerator, notifier: &Notifier) -> Registrations {
+    let mut registrations = Registrations::default();
+
+    for direction in [DeviceDirection::Render, DeviceDirection::Capture] {
+        let Ok(device) = enumerator.GetDefaultAudioEndpoint(data_flow(direction), eConsole) else {
+            continue;
+        };
+        if let Ok(volume) = device.Activate::<IAudioEndpointVolume>(CLSCTX_ALL, None) {
+            let callback: IAudioEndpointVolumeCallback =
+                VolumeNotifier(notifier.clone(), direction).into();
+            if volume.RegisterControlChangeNotify(&callback).is_ok() {
+                registrations.endpoint_volumes.push((volume, callback));
+            }
+        }
+    }
+
+    let Ok(collection) = enumerator.EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE) else {
+        return registrations;
+    };
+
+    for i in 0..collection.GetCount().unwrap_or(0) {
+        let Ok(device) = collection.Item(i) else {
+            continue;
+        };
+        let Ok(manager) = device.Activate::<IAudioSessionManager2>(CLSCTX_ALL, None) else {
+            continue;
+        };
+
+        // 需先列舉過一次，之後才會收到新會話的通知
+        let Ok(session_enumerator) = manager.GetSessionEnumerator() else {
+            continue;
+        };
+
+        let notification: IAudioSessionNotification =
+            SessionCreatedNotifier(notifier.clone()).into();
+        if manager.RegisterSessionNotification(&notification).is_ok() {
+            registrations.managers.push((manager, notification));
+        }
+
+        for j in 0..session_enumerator.GetCount().unwrap_or(0) {
+            let Ok(control) = session_enumerator.GetSession(j) else {
+                continue;
+            };
+
+            let events: IAudioSessionEvents = SessionEventsNotifier(notifier.clone()).into();
+            if control.RegisterAudioSessionNotification(&events).is_ok() {
+                registrations.sessions.push((control, events));
+            }
+        }
+    }
+
+    registrations
+}
+
+/// 在獨立的執行緒上監聽 WASAPI 通知
+/// 回呼不能在通知中取消註冊，所以裝置或會話增減時交由此執行緒重新註冊
+pub fn watch(events: Sender<AudioEvent>) -> Result<(), String> {
+    let (ready_tx, ready_rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let _com_guard = ComGuard::new();
+
+        unsafe {
+            let enumerator: IMMDeviceEnumerator =
+                match CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL) {
+                    Ok(e) => e,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(format!("無法獲取設備資料清單: {:?}", e)));
+                        return;
+                    }
+                };
+
+            let (signal_tx, signal_rx) = mpsc::channel();
+            let notifier = Notifier {
+                events,
+                signals: signal_tx,
+            };
+
+            let client: IMMNotificationClient = DeviceNotifier(notifier.clone()).into();
+            if let Err(e) = enumerator.RegisterEndpointNotificationCallback(&client) {
+                let _ = ready_tx.send(Err(format!("無法註冊裝置通知: {:?}", e)));
+                return;
+            }
+
+            let mut registrations = register(&enumerator, &notifier);
+            let _ = ready_tx.send(Ok(()));
+
+            while let Ok(Signal::Rebuild) = signal_rx.recv() {
+                // 同一次變化常會連續觸發多個通知，稍等後合併成一次重建
+                thread::sleep(Duration::from_millis(200));
+                let mut stop = false;
+                while let Ok(signal) = signal_rx.try_recv() {
+                    stop |= matches!(signal, Signal::Stop);
+                }
+                if stop {
+                    break;
+                }
+
+                drop(registrations);
+                registrations = register(&enumerator, &notifier);
+            }
+
+            drop(registrations);
+            let _ = enumerator.UnregisterEndpointNotificationCallback(&client);
+            debug_log!("已停止監聽音訊變化");
+        }
+    });
+
+    ready_rx
+        .recv()
+        .map_err(|e| format!("無法監聽音訊變化: {:?}", e))?
+}
@@ -1,3 +1,122 @@
+use std::sync::Mutex;
+
+/// 麥克風靜音快捷鍵的儲存鍵
+const MIC_MUTE_HOTKEY_KEY: &str = "mic_mute_hotkey";
+
+lazy_static::lazy_static! {
+    /// 已註冊的設定檔快捷鍵，更新設定檔時需要先取消
+    static ref PROFILE_SHORTCUTS: Mutex<Vec<tauri_plugin_global_shortcut::Shortcut>> =
+        Mutex::new(Vec::new());
+    /// 已註冊的顯示/隱藏視窗快捷鍵
+    static ref WINDOW_SHORTCUT: Mutex<Option<tauri_plugin_global_shortcut::Shortcut>> =
+        Mutex::new(None);
+    /// 已註冊的麥克風靜音快捷鍵，與視窗快捷鍵分開管理，錄製快捷鍵時仍然有效
+    static ref MIC_MUTE_SHORTCUT: Mutex<Option<tauri_plugin_global_shortcut::Shortcut>> =
+        Mutex::new(None);
+}
+
+/// 註冊所有設定檔的快捷鍵，先前註冊的會被取代
+pub fn register_profile_hotkeys(app: &tauri::AppHandle) -> Result<(), String> {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+    let mut registered = PROFILE_SHORTCUTS.lock().unwrap();
+    for shortcut in registered.drain(..) {
+        let _ = app.global_shortcut().unregister(shortcut);
+    }
+
+    for profile in crate::profiles::profiles() {
+        let Some(hotkey) = profile.hotkey.filter(|h| !h.is_empty()) else {
+            continue;
+        };
+
+        let shortcut: Shortcut = hotkey
+            .parse()
+            .map_err(|e| format!("無法解析快捷鍵: {:?}", e))?;
+
+        let name = profile.name;
+        app.global_shortcut()
+            .on_shortcut(shortcut, move |_app, _shortcut, event| {
+                if event.state == ShortcutState::Pressed {
+                    if let Err(_e) = crate::profiles::apply_profile_by_name(&name) {
+                        crate::utils::debug_log!("{}", _e);
+                    }
+                }
+            })
+            .map_err(|e| format!("無法註冊快捷鍵: {:?}", e))?;
+
+        registered.push(shortcut);
+    }
+
+    Ok(())
+}
+
+/// 註冊切換麥克風靜音的快捷鍵，先前註冊的會被取代
+/// 預設不設定，避免佔用其他程式的按鍵
+pub fn register_mic_mute_hotkey(app: &tauri::AppHandle) -> Result<(), String> {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+    let mut registered = MIC_MUTE_SHORTCUT.lock().unwrap();
+    if let Some(shortcut) = registered.take() {
+        let _ = app.global_shortcut().unregister(shortcut);
+    }
+
+    let hotkey = load_mic_mute_hotkey(app.clone())?;
+    if hotkey.is_empty() {
+        return Ok(());
+    }
+
+    let shortcut: Shortcut = hotkey
+        .parse()
+        .map_err(|e| format!("無法解析快捷鍵: {:?}", e))?;
+
+    app.global_shortcut()
+        .on_shortcut(shortcut, |_app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                if let Err(_e) = crate::toggle_microphone_mute() {
+                    crate::utils::debug_log!("{}", _e);
+                }
+            }
+        })
+        .map_err(|e| format!("無法註冊快捷鍵: {:?}", e))?;
+
+    *registered = Some(shortcut);
+
+    Ok(())
+}
+
+/// 儲存麥克風靜音快捷鍵並立即重新註冊，空字串表示不使用
+#[tauri::command]
+pub fn save_mic_mute_hotkey(app: tauri::AppHandle, hotkey: String) -> Result<(), String> {
+    use tauri_plugin_store::StoreExt;
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+    store.set(MIC_MUTE_HOTKEY_KEY, serde_json::json!(hotkey.trim()));
+
+    store.save().map_err(|e| format!("無法保存設定: {:?}", e))?;
+
+    register_mic_mute_hotkey(&app)
+}
+
+/// 載入麥克風靜音快捷鍵設定
+#[tauri::command]
+pub fn load_mic_mute_hotkey(app: tauri::AppHandle) -> Result<String, String> {
+    use tauri_plugin_store::StoreExt;
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+    let hotkey = store
+        .get(MIC_MUTE_HOTKEY_KEY)
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .unwrap_or_default();
+
+    Ok(hotkey)
+}
+
 /// 儲存全域快捷鍵設定到本地
 #[tauri::command]
 pub fn save_hotkey(app: tauri::AppHandle, hotkey: String) -> Result<(), String> {
@@ -31,27 +150,35 @@ pub fn load_hotkey(app: tauri::AppHandle) -> Result<String, String> {
     Ok(hotkey)
 }
 
-/// 取消註冊所有全域快捷鍵
-#[tauri::command]
-pub fn unregister_all_hotkeys(app: tauri::AppHandle) -> Result<(), String> {
+/// 取消註冊視窗與設定檔的快捷鍵，錄製新的快捷鍵時使用
+/// 麥克風靜音快捷鍵不受影響，只在更改設定時重新註冊
+fn unregister_window_hotkeys(app: &tauri::AppHandle) -> Result<(), String> {
     use tauri_plugin_global_shortcut::GlobalShortcutExt;
 
-    // 清除所有快捷鍵
-    app.global_shortcut()
-        .unregister_all()
-        .map_err(|e| format!("無法取消註冊快捷鍵: {:?}", e))?;
+    for shortcut in PROFILE_SHORTCUTS.lock().unwrap().drain(..) {
+        let _ = app.global_shortcut().unregister(shortcut);
+    }
+
+    if let Some(shortcut) = WINDOW_SHORTCUT.lock().unwrap().take() {
+        app.global_shortcut()
+            .unregister(shortcut)
+            .map_err(|e| format!("無法取消註冊快捷鍵: {:?}", e))?;
+    }
 
     Ok(())
 }
 
-/// 註冊全域快捷鍵
+/// 取消註冊視窗與設定檔的快捷鍵 (麥克風靜音快捷鍵保持註冊)
 #[tauri::command]
-pub fn register_hotkey(app: tauri::AppHandle, hotkey: String) -> Result<(), String> {
+pub fn unregister_all_hotkeys(app: tauri::AppHandle) -> Result<(), String> {
+    unregister_window_hotkeys(&app)
+}
+
+/// 註冊顯示/隱藏視窗的快捷鍵
+fn register_window_hotkey(app: &tauri::AppHandle, hotkey: &str) -> Result<(), String> {
     use tauri::Manager;
     use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
-    let _ = app.global_shortcut().unregister_all();
-
     if hotkey.is_empty() {
         return Ok(());
     }
@@ -81,5 +208,23 @@ pub fn register_hotkey(app: tauri::AppHandle, hotkey: String) -> Result<(), Stri
         })
         .map_err(|e| format!("無法註冊快捷鍵: {:?}", e))?;
 
+    *WINDOW_SHORTCUT.lock().unwrap() = Some(shortcut);
+
     Ok(())
 }
+
+/// 註冊全域快捷鍵
+#[tauri::command]
+pub fn register_hotkey(app: tauri::AppHandle, hotkey: String) -> Result<(), String> {
+    let _ = unregister_window_hotkeys(&app);
+
+    // 先註冊視窗快捷鍵，設定檔的快捷鍵無效時不能影響它
+    let result = register_window_hotkey(&app, &hotkey);
+
+    // 錄製快捷鍵時設定檔的快捷鍵也被取消了
+    if let Err(_e) = register_profile_hotkeys(&app) {
+        crate::utils::debug_log!("無法註冊設定檔快捷鍵: {}", _e);
+    }
+
+    result
+}
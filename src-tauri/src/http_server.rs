@@ -2,33 +2,56 @@ use axum::{
     body::Body,
     extract::{
         ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
-        Request, State,
+        Path, Query, Request, State,
     },
-    http::{header, StatusCode},
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
-    routing::get,
-    Router,
+    routing::{get, post},
+    Json, Router,
 };
 use futures_util::{SinkExt, StreamExt};
 use local_ip_address;
-use mdns_sd::{ServiceDaemon, ServiceInfo};
+use mdns_sd::{DaemonEvent, ServiceDaemon, ServiceInfo};
+use serde::Deserialize;
 use serde_json::Value;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
 use std::path::PathBuf;
-use std::sync::Arc;
-use tauri::{AssetResolver, Runtime};
-use tokio::sync::{broadcast, mpsc};
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::{AssetResolver, Emitter, Runtime};
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::JoinHandle;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::services::ServeDir;
 
+use crate::message_handler::{
+    error_reply, Access, ApiError, ErrorCode, Subscriptions, PROTOCOL_VERSION,
+};
+use crate::models::AudioLevels;
+use crate::server_settings::{ServerSettings, ServerStatus};
 use crate::utils::debug_log;
 
 pub type BroadcastSender = broadcast::Sender<String>;
 
+/// 執行中的服務，丟棄 shutdown 即停止
+struct Running {
+    shutdown: watch::Sender<()>,
+    /// 各監聽端口的服務，結束後端口才會釋放
+    serve_tasks: Vec<JoinHandle<()>>,
+    mdns_fullname: Option<String>,
+}
+
 pub struct HttpServer<R: Runtime> {
     mdns: ServiceDaemon,
     broadcast_tx: BroadcastSender,
     asset_resolver: Option<AssetResolver<R>>,
+    app_handle: Option<tauri::AppHandle<R>>,
     static_dir: Option<PathBuf>,
+    running: Mutex<Option<Running>>,
+    status: RwLock<ServerStatus>,
 }
 
 impl<R: Runtime> HttpServer<R> {
@@ -39,7 +62,10 @@ impl<R: Runtime> HttpServer<R> {
             mdns,
             broadcast_tx,
             asset_resolver: None,
+            app_handle: None,
             static_dir: None,
+            running: Mutex::new(None),
+            status: RwLock::new(ServerStatus::default()),
         }
     }
 
@@ -48,40 +74,19 @@ impl<R: Runtime> HttpServer<R> {
         self
     }
 
+    /// 配對成功或服務狀態改變時用來通知桌面介面
+    pub fn with_app_handle(mut self, app_handle: tauri::AppHandle<R>) -> Self {
+        self.app_handle = Some(app_handle);
+        self
+    }
+
     pub fn broadcast(&self, message: String) {
         let _ = self.broadcast_tx.send(message);
     }
 
-    pub async fn start(self: Arc<Self>, port: u16) {
-        let addr = format!("0.0.0.0:{}", port);
-
-        let service_type = "_http._tcp.local.";
-        let instance_name = "eazycontroller";
-        let host_name = "eazycontroller.local.";
-
-        let local_ip = local_ip_address::local_ip().ok();
-
-        match local_ip {
-            Some(ip) => {
-                let ip_addr = match ip {
-                    std::net::IpAddr::V4(ipv4) => ipv4,
-                    std::net::IpAddr::V6(_) => std::net::Ipv4Addr::new(0, 0, 0, 0),
-                };
-
-                if ip_addr != std::net::Ipv4Addr::new(0, 0, 0, 0) {
-                    let service_info =
-                        ServiceInfo::new(service_type, instance_name, host_name, (), port, None);
-
-                    if let Ok(info) = service_info {
-                        let info = info.enable_addr_auto();
-                        let _ = self.mdns.register(info);
-                    }
-                }
-            }
-            None => {}
-        }
-
-        let app = if let Some(ref dir) = self.static_dir {
+    /// 建立 HTTP/WebSocket 路由
+    pub fn router(self: &Arc<Self>) -> Router {
+        if let Some(ref dir) = self.static_dir {
             let serve_dir = ServeDir::new(dir)
                 .append_index_html_on_directories(true)
                 .precompressed_gzip()
@@ -90,6 +95,8 @@ impl<R: Runtime> HttpServer<R> {
             Router::new()
                 .route("/ws", get(ws_handler::<R>))
                 .route("/health", get(health_check))
+                .route("/pair", post(pair_handler::<R>))
+                .route("/media/:session_id/artwork", get(artwork_handler))
                 .fallback_service(serve_dir)
                 .layer(
                     CorsLayer::new()
@@ -97,11 +104,13 @@ impl<R: Runtime> HttpServer<R> {
                         .allow_methods(Any)
                         .allow_headers(Any),
                 )
-                .with_state(Arc::clone(&self))
+                .with_state(Arc::clone(self))
         } else {
             Router::new()
                 .route("/ws", get(ws_handler::<R>))
                 .route("/health", get(health_check))
+                .route("/pair", post(pair_handler::<R>))
+                .route("/media/:session_id/artwork", get(artwork_handler))
                 .fallback(static_file_handler::<R>)
                 .layer(
                     CorsLayer::new()
@@ -109,19 +118,304 @@ impl<R: Runtime> HttpServer<R> {
                         .allow_methods(Any)
                         .allow_headers(Any),
                 )
-                .with_state(Arc::clone(&self))
+                .with_state(Arc::clone(self))
+        }
+    }
+
+    /// 依設定啟動服務，已在執行時會先停止
+    /// 綁定失敗時回傳的狀態會帶有錯誤原因
+    pub async fn start(self: Arc<Self>, settings: &ServerSettings) -> ServerStatus {
+        self.stop().await;
+
+        let status = if !settings.enabled {
+            ServerStatus::default()
+        } else {
+            match bind_listeners(settings) {
+                Ok((listeners, port)) => {
+                    let (shutdown_tx, shutdown_rx) = watch::channel(());
+                    let mut serve_tasks = Vec::new();
+
+                    for listener in listeners {
+                        let app = self.router();
+                        let mut shutdown_rx = shutdown_rx.clone();
+                        serve_tasks.push(tokio::spawn(async move {
+                            let result = axum::serve(listener, app)
+                                .with_graceful_shutdown(async move {
+                                    let _ = shutdown_rx.changed().await;
+                                })
+                                .await;
+                            if let Err(_e) = result {
+                                debug_log!("HTTP 伺服器錯誤: {:?}", _e);
+                            }
+                        }));
+                    }
+
+                    let mdns_fullname = self.register_mdns(port);
+                    tokio::spawn(
+                        Arc::clone(&self).watch_network_changes(port, shutdown_rx.clone()),
+                    );
+                    *self.running.lock().unwrap() = Some(Running {
+                        shutdown: shutdown_tx,
+                        serve_tasks,
+                        mdns_fullname,
+                    });
+
+                    ServerStatus {
+                        running: true,
+                        port: Some(port),
+                        urls: server_urls(settings, port),
+                        error: None,
+                    }
+                }
+                Err(e) => {
+                    debug_log!("{}", e);
+                    ServerStatus {
+                        error: Some(e),
+                        ..Default::default()
+                    }
+                }
+            }
         };
 
-        let listener = match tokio::net::TcpListener::bind(&addr).await {
-            Ok(l) => l,
-            Err(e) => {
-                debug_log!("無法綁定 HTTP 端口 {}: {:?}", port, e);
+        *self.status.write().unwrap() = status.clone();
+        if let Some(app_handle) = &self.app_handle {
+            let _ = app_handle.emit("server-status-changed", &status);
+        }
+
+        status
+    }
+
+    /// 停止服務並中斷所有 WebSocket 連線，等到端口釋放後才返回
+    /// 否則以相同設定重新啟動時會因端口仍被占用而換到下一個端口
+    pub async fn stop(&self) {
+        let running = self.running.lock().unwrap().take();
+
+        if let Some(running) = running {
+            if let Some(fullname) = running.mdns_fullname {
+                let _ = self.mdns.unregister(&fullname);
+            }
+            // 丟棄 shutdown 會通知所有監聽者
+            drop(running.shutdown);
+
+            let serve_tasks = futures_util::future::join_all(running.serve_tasks);
+            if tokio::time::timeout(SHUTDOWN_TIMEOUT, serve_tasks)
+                .await
+                .is_err()
+            {
+                debug_log!("HTTP 伺服器未在時限內停止");
+            }
+        }
+
+        *self.status.write().unwrap() = ServerStatus::default();
+    }
+
+    pub fn status(&self) -> ServerStatus {
+        self.status.read().unwrap().clone()
+    }
+
+    /// 服務停止時完成，未透過 start 啟動時永遠不會完成
+    fn shutdown_signal(&self) -> impl std::future::Future<Output = ()> {
+        let receiver = self
+            .running
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|running| running.shutdown.subscribe());
+
+        async move {
+            match receiver {
+                Some(mut receiver) => {
+                    let _ = receiver.changed().await;
+                }
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    /// 以實際綁定的端口註冊 mDNS，回傳註冊的服務全名
+    /// 重複註冊同一個服務會覆蓋舊的記錄並重新廣播
+    fn register_mdns(&self, port: u16) -> Option<String> {
+        let machine_name = machine_name();
+        let host_name = format!("{}.local.", mdns_host_label(&machine_name));
+
+        let properties = [
+            ("version", PROTOCOL_VERSION.to_string()),
+            ("name", machine_name.clone()),
+            ("auth", "pairing".to_string()),
+            ("ws", "/ws".to_string()),
+        ];
+
+        let info = match ServiceInfo::new(
+            MDNS_SERVICE_TYPE,
+            &machine_name,
+            &host_name,
+            (),
+            port,
+            &properties[..],
+        ) {
+            Ok(info) => info.enable_addr_auto(),
+            Err(_e) => {
+                debug_log!("無法建立 mDNS 服務: {:?}", _e);
+                return None;
+            }
+        };
+        let fullname = info.get_fullname().to_string();
+
+        if let Err(_e) = self.mdns.register(info) {
+            debug_log!("無法註冊 mDNS 服務: {:?}", _e);
+            return None;
+        }
+
+        Some(fullname)
+    }
+
+    /// 網路介面的位址改變時重新註冊，讓手機拿到新的位址
+    async fn watch_network_changes(self: Arc<Self>, port: u16, mut shutdown: watch::Receiver<()>) {
+        let monitor = match self.mdns.monitor() {
+            Ok(monitor) => monitor,
+            Err(_e) => {
+                debug_log!("無法監聽網路介面變化: {:?}", _e);
                 return;
             }
         };
 
-        if let Err(e) = axum::serve(listener, app).await {
-            debug_log!("HTTP 伺服器錯誤: {:?}", e);
+        loop {
+            tokio::select! {
+                _ = shutdown.changed() => break,
+                event = monitor.recv_async() => match event {
+                    Ok(DaemonEvent::IpAdd(_) | DaemonEvent::IpDel(_)) => {
+                        debug_log!("網路介面已變更，重新註冊 mDNS 服務");
+                        self.register_mdns(port);
+                    }
+                    Ok(_) => {}
+                    Err(_) => break,
+                },
+            }
+        }
+    }
+}
+
+/// 專用的服務類型，讓手機只搜尋到 EazyController
+const MDNS_SERVICE_TYPE: &str = "_eazycontroller._tcp.local.";
+
+/// 電腦名稱，用於 mDNS 實例名稱與顯示
+fn machine_name() -> String {
+    hostname::get()
+        .ok()
+        .and_then(|name| name.into_string().ok())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "eazycontroller".to_string())
+}
+
+/// 將電腦名稱轉為合法的 mDNS 主機名稱
+fn mdns_host_label(machine_name: &str) -> String {
+    let label = machine_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    let label = label.trim_matches('-');
+
+    if label.is_empty() {
+        "eazycontroller".to_string()
+    } else {
+        label.to_string()
+    }
+}
+
+/// 設定的端口被占用時，依序嘗試後面幾個端口，最後交由系統分配
+const PORT_FALLBACK_ATTEMPTS: u16 = 10;
+
+/// 停止服務時等待連線結束的上限
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+fn bind_listeners(settings: &ServerSettings) -> Result<(Vec<TcpListener>, u16), String> {
+    let address: IpAddr = settings
+        .bind_address
+        .parse()
+        .map_err(|e| format!("無效的綁定位址 '{}': {:?}", settings.bind_address, e))?;
+
+    let candidates = (0..PORT_FALLBACK_ATTEMPTS)
+        .filter_map(|i| settings.port.checked_add(i))
+        .chain(std::iter::once(0));
+
+    let mut last_error = None;
+
+    for port in candidates {
+        match bind_port(address, port, settings.ipv6) {
+            Ok(listeners) => {
+                let port = listeners[0]
+                    .local_addr()
+                    .map_err(|e| format!("無法取得綁定的端口: {:?}", e))?
+                    .port();
+                return Ok((listeners, port));
+            }
+            Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+                debug_log!("端口 {} 已被占用", port);
+                last_error = Some(e);
+            }
+            Err(e) => return Err(format!("無法綁定 HTTP 端口 {}:{}: {}", address, port, e)),
+        }
+    }
+
+    Err(format!(
+        "無法綁定 HTTP 端口 {}: {:?}",
+        settings.port, last_error
+    ))
+}
+
+/// 綁定單一端口，開啟 IPv6 時在同一端口額外監聽 ::
+fn bind_port(address: IpAddr, port: u16, ipv6: bool) -> io::Result<Vec<TcpListener>> {
+    let mut listeners = vec![listen(SocketAddr::new(address, port))?];
+
+    if ipv6 && address.is_ipv4() {
+        let port = listeners[0].local_addr()?.port();
+        listeners.push(listen(SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), port))?);
+    }
+
+    Ok(listeners)
+}
+
+fn listen(addr: SocketAddr) -> io::Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+
+    // IPv4 與 IPv6 分開監聽，避免雙堆疊造成端口衝突
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+
+    // Windows 的 SO_REUSEADDR 允許搶占使用中的端口，不能開啟
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    socket.set_nonblocking(true)?;
+
+    TcpListener::from_std(socket.into())
+}
+
+/// 顯示給使用者的連線網址
+fn server_urls(settings: &ServerSettings, port: u16) -> Vec<String> {
+    let address: Option<IpAddr> = settings.bind_address.parse().ok();
+
+    match address {
+        Some(ip) if !ip.is_unspecified() => vec![match ip {
+            IpAddr::V4(ip) => format!("http://{}:{}/", ip, port),
+            IpAddr::V6(ip) => format!("http://[{}]:{}/", ip, port),
+        }],
+        _ => {
+            let host = mdns_host_label(&machine_name());
+            let mut urls = vec![format!("http://{}.local:{}/", host, port)];
+            if let Ok(ip) = local_ip_address::local_ip() {
+                urls.push(format!("http://{}:{}/", ip, port));
+            }
+            urls
         }
     }
 }
@@ -130,14 +424,121 @@ async fn health_check() -> impl IntoResponse {
     "OK"
 }
 
+#[derive(Debug, Deserialize)]
+struct PairRequest {
+    code: String,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// 以桌面顯示的配對碼換取 token
+async fn pair_handler<R: Runtime>(
+    State(server): State<Arc<HttpServer<R>>>,
+    headers: HeaderMap,
+    Json(request): Json<PairRequest>,
+) -> Response {
+    let name = request
+        .name
+        .filter(|name| !name.trim().is_empty())
+        .or_else(|| {
+            headers
+                .get(header::USER_AGENT)
+                .and_then(|ua| ua.to_str().ok())
+                .map(|ua| ua.chars().take(64).collect())
+        })
+        .unwrap_or_else(|| "未命名裝置".to_string());
+
+    match crate::pairing::pair(&request.code, &name) {
+        Ok(device) => {
+            if let Some(app_handle) = &server.app_handle {
+                if let Err(_e) = crate::pairing::save_paired_devices(app_handle) {
+                    debug_log!("無法保存配對裝置: {}", _e);
+                }
+                let _ = app_handle.emit("paired-devices-updated", ());
+            }
+
+            Json(serde_json::json!({
+                "device_id": device.id,
+                "token": device.token
+            }))
+            .into_response()
+        }
+        Err(e) => (
+            StatusCode::UNAUTHORIZED,
+            Json(error_reply(ApiError::new(ErrorCode::Unauthorized, e), None)),
+        )
+            .into_response(),
+    }
+}
+
+/// 客戶端帶著上次的 ETag 請求時，封面沒變就不需要重新傳送
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+/// 專輯封面，與 WebSocket 一樣不需要配對就能讀取
+/// 讀取封面可能要等待播放器或下載圖片，所以在阻塞執行緒中處理
+async fn artwork_handler(Path(session_id): Path<String>, headers: HeaderMap) -> Response {
+    tokio::task::spawn_blocking(move || artwork_response(&session_id, &headers))
+        .await
+        .unwrap_or_else(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:?}", e)).into_response())
+}
+
+/// 封面網址在同一個會話中不會改變，客戶端每次都要以 ETag 確認是否換了封面
+fn artwork_response(session_id: &str, headers: &HeaderMap) -> Response {
+    let artwork = match crate::media::get_artwork(session_id) {
+        Ok(Some(artwork)) => artwork,
+        Ok(None) => return (StatusCode::NOT_FOUND, "沒有專輯封面").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+
+    let etag = format!("\"{}\"", artwork.hash);
+    if etag_matches(headers, &etag) {
+        return Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, etag)
+            .header(header::CACHE_CONTROL, "no-cache")
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, artwork.content_type)
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, "no-cache")
+        .body(Body::from(artwork.bytes))
+        .unwrap()
+}
+
+/// 帶 token 的連線必須是有效的配對，沒有 token 的連線只能讀取
 async fn ws_handler<R: Runtime>(
     ws: WebSocketUpgrade,
+    Query(params): Query<HashMap<String, String>>,
     State(server): State<Arc<HttpServer<R>>>,
-) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_websocket(socket, server))
+) -> Response {
+    let token = params.get("token").cloned();
+
+    if let Some(token) = &token {
+        if !crate::pairing::is_token_valid(token) {
+            return (StatusCode::UNAUTHORIZED, "配對已失效").into_response();
+        }
+    }
+
+    ws.on_upgrade(move |socket| handle_websocket(socket, server, token))
 }
 
-async fn handle_websocket<R: Runtime>(socket: WebSocket, server: Arc<HttpServer<R>>) {
+async fn handle_websocket<R: Runtime>(
+    socket: WebSocket,
+    server: Arc<HttpServer<R>>,
+    token: Option<String>,
+) {
     let (mut ws_sender, mut ws_receiver) = socket.split();
     let (tx, mut rx) = mpsc::unbounded_channel();
 
@@ -160,23 +561,61 @@ async fn handle_websocket<R: Runtime>(socket: WebSocket, server: Arc<HttpServer<
         }
     });
 
-    while let Some(msg) = ws_receiver.next().await {
+    let shutdown = server.shutdown_signal();
+    tokio::pin!(shutdown);
+
+    let mut subscriptions = Subscriptions::default();
+    let mut levels_task: Option<tokio::task::JoinHandle<()>> = None;
+
+    loop {
+        let msg = tokio::select! {
+            msg = ws_receiver.next() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = &mut shutdown => break,
+        };
+
         match msg {
             Ok(WsMessage::Text(text)) => match serde_json::from_str::<Value>(&text) {
                 Ok(json) => {
-                    if let Some(response) = crate::message_handler::handle_message(json).await {
-                        let response_text = serde_json::to_string(&response).unwrap_or_default();
-                        if tx.send(WsMessage::Text(response_text)).is_err() {
-                            break;
+                    // 每則訊息重新檢查，撤銷後立即降為唯讀
+                    let access = match &token {
+                        Some(token) if crate::pairing::is_token_valid(token) => Access::Full,
+                        _ => Access::ReadOnly,
+                    };
+                    let previous = subscriptions.clone();
+                    let response = crate::message_handler::handle_message(
+                        json,
+                        access,
+                        &mut subscriptions,
+                        server.app_handle.as_ref(),
+                    )
+                    .await;
+                    let response_text = serde_json::to_string(&response).unwrap_or_default();
+                    if tx.send(WsMessage::Text(response_text)).is_err() {
+                        break;
+                    }
+
+                    if subscriptions.levels != previous.levels {
+                        if let Some(task) = levels_task.take() {
+                            task.abort();
                         }
+                        levels_task = subscriptions.levels.map(|interval| {
+                            tokio::spawn(forward_levels(
+                                crate::audio::subscribe_levels(),
+                                interval,
+                                tx.clone(),
+                            ))
+                        });
                     }
                 }
                 Err(e) => {
                     debug_log!("無法解析 JSON: {:?}", e);
-                    let error_msg = serde_json::json!({
-                        "type": "error",
-                        "message": format!("無法解析訊息: {:?}", e)
-                    });
+                    let error_msg = error_reply(
+                        ApiError::new(ErrorCode::InvalidJson, format!("無法解析訊息: {:?}", e)),
+                        None,
+                    );
                     let _ = tx.send(WsMessage::Text(error_msg.to_string()));
                 }
             },
@@ -192,9 +631,38 @@ async fn handle_websocket<R: Runtime>(socket: WebSocket, server: Arc<HttpServer<
         }
     }
 
+    if let Some(task) = levels_task {
+        task.abort();
+    }
     send_task.abort();
 }
 
+/// 依訂閱的間隔送出最新的音量峰值，沒有新的取樣時不送
+async fn forward_levels(
+    mut levels: watch::Receiver<AudioLevels>,
+    interval: std::time::Duration,
+    tx: mpsc::UnboundedSender<WsMessage>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    loop {
+        ticker.tick().await;
+
+        if !levels.has_changed().unwrap_or(false) {
+            continue;
+        }
+
+        let message = serde_json::json!({
+            "type": "levels",
+            "data": *levels.borrow_and_update()
+        });
+        if tx.send(WsMessage::Text(message.to_string())).is_err() {
+            break;
+        }
+    }
+}
+
 async fn static_file_handler<R: Runtime>(
     State(server): State<Arc<HttpServer<R>>>,
     request: Request,
@@ -234,3 +702,277 @@ async fn static_file_handler<R: Runtime>(
         .body(Body::from("404 Not Found"))
         .unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::HttpServer;
+    use crate::audio::mock::MockAudioBackend;
+    use crate::media::mock::MockMediaBackend;
+    use crate::server_settings::ServerSettings;
+    use crate::utils::backend_test_lock;
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tokio_tungstenite::tungstenite::Message;
+    use tower::ServiceExt;
+
+    async fn serve() -> (Arc<HttpServer<tauri::Wry>>, String) {
+        let server = Arc::new(HttpServer::<tauri::Wry>::new());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = server.router();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        (server, format!("ws://{}/ws", addr))
+    }
+
+    /// 直接配對一台裝置，回傳帶 token 的連線網址
+    fn paired_url(url: &str) -> String {
+        let code = crate::pairing::new_pairing_code().code;
+        let device = crate::pairing::pair(&code, "test").unwrap();
+        format!("{}?token={}", url, device.token)
+    }
+
+    async fn next_json<S>(stream: &mut S) -> Value
+    where
+        S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        loop {
+            if let Message::Text(text) = stream.next().await.unwrap().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn websocket_round_trip() {
+        let _lock = backend_test_lock().await;
+        let audio = Arc::new(MockAudioBackend::fixture());
+        crate::set_audio_backend(audio.clone());
+        crate::set_media_backend(Arc::new(MockMediaBackend::fixture()));
+
+        let (_server, url) = serve().await;
+        let (mut ws, _) = tokio_tungstenite::connect_async(paired_url(&url)).await.unwrap();
+
+        let request = json!({ "type": "set_default_device_volume", "data": { "volume": 0.3 } });
+        ws.send(Message::Text(request.to_string())).await.unwrap();
+        assert_eq!(next_json(&mut ws).await["type"], "success");
+        assert!((audio.state().default_volume - 0.3).abs() < 1e-6);
+
+        ws.send(Message::Text("not json".to_string())).await.unwrap();
+        let reply = next_json(&mut ws).await;
+        assert_eq!(reply["type"], "error");
+        assert_eq!(reply["error"]["code"], "invalid_json");
+    }
+
+    #[tokio::test]
+    async fn broadcasts_reach_connected_clients() {
+        let (server, url) = serve().await;
+        let (mut ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+
+        // 等待連線完成訂閱後再廣播
+        ws.send(Message::Ping(Vec::new())).await.unwrap();
+        while !matches!(ws.next().await.unwrap().unwrap(), Message::Pong(_)) {}
+
+        server.broadcast(json!({ "type": "media_info_cleared" }).to_string());
+        assert_eq!(next_json(&mut ws).await["type"], "media_info_cleared");
+    }
+
+    #[tokio::test]
+    async fn levels_are_only_sent_to_subscribers() {
+        let _lock = backend_test_lock().await;
+        let audio = Arc::new(MockAudioBackend::fixture());
+        audio.update(|state| {
+            state.levels.sessions.insert("spotify-1".to_string(), 0.5);
+        });
+        crate::set_audio_backend(audio.clone());
+
+        let (_server, url) = serve().await;
+        let (mut idle, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        let (mut ws, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+
+        let request = json!({ "type": "subscribe_levels", "data": { "interval_ms": 50 } });
+        ws.send(Message::Text(request.to_string())).await.unwrap();
+        assert_eq!(next_json(&mut ws).await["data"]["interval_ms"], 50);
+
+        let levels = next_json(&mut ws).await;
+        assert_eq!(levels["type"], "levels");
+        assert_eq!(levels["data"]["sessions"]["spotify-1"], 0.5);
+
+        // 沒有訂閱的連線只會收到自己請求的回覆
+        let request = json!({ "type": "get_audio_devices" });
+        idle.send(Message::Text(request.to_string())).await.unwrap();
+        assert_eq!(next_json(&mut idle).await["type"], "audio_devices");
+    }
+
+    #[tokio::test]
+    async fn pairing_code_grants_revocable_write_access() {
+        let _lock = backend_test_lock().await;
+        let audio = Arc::new(MockAudioBackend::fixture());
+        crate::set_audio_backend(audio.clone());
+
+        let (server, url) = serve().await;
+        let mute = json!({ "type": "set_default_device_mute", "data": { "mute": true } }).to_string();
+
+        assert!(tokio_tungstenite::connect_async(format!("{}?token=bogus", url))
+            .await
+            .is_err());
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        ws.send(Message::Text(mute.clone())).await.unwrap();
+        assert_eq!(next_json(&mut ws).await["error"]["code"], "unauthorized");
+        assert!(!audio.state().default_mute);
+
+        let code = crate::pairing::new_pairing_code().code;
+        let pair = |code: String| {
+            Request::post("/pair")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json!({ "code": code, "name": "Phone" }).to_string()))
+                .unwrap()
+        };
+        let response = server.router().oneshot(pair(code.clone())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let paired: Value = serde_json::from_slice(&body).unwrap();
+
+        let response = server.router().oneshot(pair(code)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let token = paired["token"].as_str().unwrap();
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("{}?token={}", url, token))
+            .await
+            .unwrap();
+        ws.send(Message::Text(mute.clone())).await.unwrap();
+        assert_eq!(next_json(&mut ws).await["type"], "success");
+        assert!(audio.state().default_mute);
+
+        crate::pairing::revoke(paired["device_id"].as_str().unwrap()).unwrap();
+        ws.send(Message::Text(mute)).await.unwrap();
+        assert_eq!(next_json(&mut ws).await["error"]["code"], "unauthorized");
+    }
+
+    #[tokio::test]
+    async fn artwork_is_served_with_etags() {
+        let _lock = backend_test_lock().await;
+        let media = Arc::new(MockMediaBackend::fixture());
+        crate::set_media_backend(media.clone());
+        media.update(|state| {
+            state
+                .thumbnails
+                .insert("spotify".to_string(), "iVBORw0KGgo=".to_string());
+        });
+
+        let server = Arc::new(HttpServer::<tauri::Wry>::new());
+        let info = crate::get_all_media_sessions().unwrap().remove(0);
+        let url = info.artwork_url.unwrap();
+        let get = |if_none_match: Option<&str>| {
+            let mut request = Request::get(&url);
+            if let Some(tag) = if_none_match {
+                request = request.header(header::IF_NONE_MATCH, tag);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let response = server.router().oneshot(get(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        let etag = response.headers()[header::ETAG]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-cache");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"\x89PNG\r\n\x1a\n");
+
+        let response = server.router().oneshot(get(Some(&etag))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        // 曲目資訊沒變但封面換了，舊的 ETag 也會失效
+        media.update(|state| {
+            state
+                .thumbnails
+                .insert("spotify".to_string(), "/9j/4A==".to_string());
+        });
+        let response = server.router().oneshot(get(Some(&etag))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/jpeg");
+        assert_ne!(response.headers()[header::ETAG], etag.as_str());
+
+        let request = Request::get("/media/closed/artwork")
+            .body(Body::empty())
+            .unwrap();
+        let response = server.router().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn start_falls_back_when_port_is_taken() {
+        let occupied = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = occupied.local_addr().unwrap().port();
+
+        let server = Arc::new(HttpServer::<tauri::Wry>::new());
+        let settings = ServerSettings {
+            bind_address: "127.0.0.1".to_string(),
+            port,
+            ..Default::default()
+        };
+
+        let status = Arc::clone(&server).start(&settings).await;
+        assert!(status.running);
+        assert_ne!(status.port, Some(port));
+        assert_eq!(status.urls, vec![format!("http://127.0.0.1:{}/", status.port.unwrap())]);
+
+        server.stop().await;
+        assert!(!server.status().running);
+
+        let settings = ServerSettings {
+            bind_address: "not an address".to_string(),
+            ..settings
+        };
+        let status = Arc::clone(&server).start(&settings).await;
+        assert!(!status.running);
+        assert!(status.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn restart_keeps_the_same_port() {
+        let free = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = free.local_addr().unwrap().port();
+        drop(free);
+
+        let server = Arc::new(HttpServer::<tauri::Wry>::new());
+        let settings = ServerSettings {
+            bind_address: "127.0.0.1".to_string(),
+            port,
+            ..Default::default()
+        };
+
+        let (_ws, url) = {
+            let status = Arc::clone(&server).start(&settings).await;
+            assert_eq!(status.port, Some(port));
+            let url = format!("ws://127.0.0.1:{}/ws", port);
+            (tokio_tungstenite::connect_async(&url).await.unwrap(), url)
+        };
+
+        // 儲存未變更的設定會重新啟動服務，有連線中的客戶端也不能換端口
+        let status = Arc::clone(&server).start(&settings).await;
+        assert_eq!(status.port, Some(port));
+        assert!(tokio_tungstenite::connect_async(&url).await.is_ok());
+
+        server.stop().await;
+    }
+
+    #[test]
+    fn mdns_host_label_is_a_valid_dns_label() {
+        assert_eq!(super::mdns_host_label("DESKTOP-AB12"), "desktop-ab12");
+        assert_eq!(super::mdns_host_label("Amy's PC"), "amy-s-pc");
+        assert_eq!(super::mdns_host_label("客廳電腦"), "eazycontroller");
+    }
+}
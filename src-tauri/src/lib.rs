@@ -4,14 +4,25 @@ mod http_server;
 mod media;
 mod message_handler;
 mod models;
+mod pairing;
+mod profiles;
+mod rules;
+mod server_settings;
 mod utils;
 
 use audio::*;
 use hotkey::*;
 use media::*;
+use pairing::*;
+use profiles::*;
+use rules::*;
+use server_settings::*;
 use std::sync::Arc;
+#[cfg(windows)]
 use tauri_plugin_updater::UpdaterExt;
 
+/// 自動更新只在 Windows 上提供 (更新檔只有 NSIS 安裝程式)
+#[cfg(windows)]
 async fn update(app: tauri::AppHandle) -> tauri_plugin_updater::Result<()> {
     if let Some(update) = app.updater()?.check().await? {
         let mut downloaded = 0;
@@ -35,13 +46,66 @@ async fn update(app: tauri::AppHandle) -> tauri_plugin_updater::Result<()> {
     Ok(())
 }
 
+/// 系統匣選單，設定檔改變時需要重新建立
+fn build_tray_menu(app: &tauri::AppHandle) -> tauri::Result<tauri::menu::Menu<tauri::Wry>> {
+    use tauri::menu::{IsMenuItem, Menu, MenuItem, Submenu};
+
+    let show_item = MenuItem::with_id(app, "show", "開啟", true, None::<&str>)?;
+    let hide_item = MenuItem::with_id(app, "hide", "關閉", true, None::<&str>)?;
+    let mic_item = MenuItem::with_id(app, "toggle_mic", "麥克風靜音", true, None::<&str>)?;
+    let quit_item = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
+
+    let profile_items = profiles::profiles()
+        .into_iter()
+        .map(|profile| {
+            let id = format!("profile:{}", profile.name);
+            MenuItem::with_id(app, id, profile.name, true, None::<&str>)
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let profile_refs: Vec<&dyn IsMenuItem<tauri::Wry>> = profile_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<tauri::Wry>)
+        .collect();
+    let profiles_menu =
+        Submenu::with_items(app, "套用設定檔", !profile_refs.is_empty(), &profile_refs)?;
+
+    Menu::with_items(
+        app,
+        &[
+            &show_item,
+            &hide_item,
+            &mic_item,
+            &profiles_menu,
+            &quit_item,
+        ],
+    )
+}
+
+/// 以目前的設定檔重新建立系統匣選單
+pub(crate) fn refresh_tray_menu(app: &tauri::AppHandle) {
+    if let Some(tray) = app.tray_by_id("main-tray") {
+        match build_tray_menu(app) {
+            Ok(menu) => {
+                let _ = tray.set_menu(Some(menu));
+            }
+            Err(_e) => {
+                utils::debug_log!("無法建立系統匣選單: {:?}", _e);
+            }
+        }
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    use tauri::menu::{Menu, MenuItem};
     use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder};
     use tauri::Manager;
 
-    tauri::Builder::default()
+    let builder = tauri::Builder::default();
+
+    #[cfg(windows)]
+    let builder = builder.plugin(tauri_plugin_updater::Builder::new().build());
+
+    builder
         .plugin({
             use tauri_plugin_prevent_default::{Builder, KeyboardShortcut, ModifierKey};
             Builder::new()
@@ -55,7 +119,6 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .plugin(tauri_plugin_store::Builder::new().build())
-        .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.show();
@@ -72,6 +135,33 @@ pub fn run() {
                 )?;
             }
 
+            set_audio_backend(default_audio_backend());
+            set_media_backend(default_media_backend());
+
+            if let Err(_e) = load_paired_devices(app.handle()) {
+                utils::debug_log!("無法載入配對裝置: {}", _e);
+            }
+
+            if let Err(_e) = load_profiles(app.handle()) {
+                utils::debug_log!("無法載入設定檔: {}", _e);
+            }
+
+            if let Err(_e) = load_volume_memory(app.handle()) {
+                utils::debug_log!("無法載入記住的音量: {}", _e);
+            }
+
+            if let Err(_e) = load_app_routes(app.handle()) {
+                utils::debug_log!("無法載入程式的輸出裝置: {}", _e);
+            }
+
+            if let Err(_e) = load_rules(app.handle()) {
+                utils::debug_log!("無法載入規則: {}", _e);
+            }
+
+            if let Err(_e) = load_ducking_settings(app.handle()) {
+                utils::debug_log!("無法載入自動降低音量設定: {}", _e);
+            }
+
             if let Some(window) = app.get_webview_window("main") {
                 let window_clone = window.clone();
                 window.on_window_event(move |event| {
@@ -82,11 +172,7 @@ pub fn run() {
                 });
             }
 
-            let show_item = MenuItem::with_id(app, "show", "開啟", true, None::<&str>)?;
-            let hide_item = MenuItem::with_id(app, "hide", "關閉", true, None::<&str>)?;
-            let quit_item = MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?;
-
-            let menu = Menu::with_items(app, &[&show_item, &hide_item, &quit_item])?;
+            let menu = build_tray_menu(app.handle())?;
 
             let _tray = TrayIconBuilder::with_id("main-tray")
                 .tooltip("eazy-controller")
@@ -105,10 +191,21 @@ pub fn run() {
                             let _ = window.hide();
                         }
                     }
+                    "toggle_mic" => {
+                        if let Err(_e) = toggle_microphone_mute() {
+                            utils::debug_log!("{}", _e);
+                        }
+                    }
                     "quit" => {
                         app.exit(0);
                     }
-                    _ => {}
+                    id => {
+                        if let Some(name) = id.strip_prefix("profile:") {
+                            if let Err(_e) = apply_profile_by_name(name) {
+                                utils::debug_log!("{}", _e);
+                            }
+                        }
+                    }
                 })
                 .on_tray_icon_event(|tray, event| {
                     if let tauri::tray::TrayIconEvent::Click {
@@ -133,12 +230,17 @@ pub fn run() {
                 .build(app)?;
 
             let asset_resolver = app.asset_resolver();
-            let http_server =
-                Arc::new(http_server::HttpServer::new().with_asset_resolver(asset_resolver));
-            let http_server_clone = Arc::clone(&http_server);
+            let http_server = Arc::new(
+                http_server::HttpServer::new()
+                    .with_asset_resolver(asset_resolver)
+                    .with_app_handle(app.handle().clone()),
+            );
+            app.manage(Arc::clone(&http_server));
 
+            let http_server_clone = Arc::clone(&http_server);
+            let server_settings = load_server_settings(app.handle());
             tauri::async_runtime::spawn(async move {
-                http_server_clone.start(8800).await;
+                http_server_clone.start(&server_settings).await;
             });
 
             let app_handle = app.handle().clone();
@@ -147,13 +249,26 @@ pub fn run() {
                 media_monitor_loop(app_handle, http_server_monitor);
             });
 
+            let app_handle = app.handle().clone();
+            let http_server_monitor = Arc::clone(&http_server);
+            std::thread::spawn(move || {
+                audio_monitor_loop(app_handle, http_server_monitor);
+            });
+
             let app_handle = app.handle().clone();
             let _ = register_hotkey(app_handle, "Alt+Z".to_string());
 
-            let handle = app.handle().clone();
-            tauri::async_runtime::spawn(async move {
-                let _ = update(handle).await;
-            });
+            if let Err(_e) = register_mic_mute_hotkey(app.handle()) {
+                utils::debug_log!("無法註冊麥克風靜音快捷鍵: {}", _e);
+            }
+
+            #[cfg(windows)]
+            {
+                let handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    let _ = update(handle).await;
+                });
+            }
 
             Ok(())
         })
@@ -161,22 +276,61 @@ pub fn run() {
             get_audio_sessions,
             set_session_volume,
             set_session_mute,
+            set_session_device,
+            get_app_routes,
+            clear_app_route,
             get_audio_devices,
             set_default_device,
             get_default_device_volume,
             set_default_device_volume,
             get_default_device_mute,
             set_default_device_mute,
+            toggle_microphone_mute,
+            get_device_volume,
+            set_device_volume,
+            get_device_mute,
+            set_device_mute,
             get_media_info,
             get_all_media_sessions,
             get_media_thumbnail,
             media_play_pause,
+            media_play,
+            media_pause,
+            media_stop,
             media_next,
             media_previous,
+            media_seek,
+            media_set_shuffle,
+            media_set_repeat,
+            media_set_rate,
             register_hotkey,
             unregister_all_hotkeys,
             save_hotkey,
-            load_hotkey
+            load_hotkey,
+            save_mic_mute_hotkey,
+            load_mic_mute_hotkey,
+            create_pairing_code,
+            get_paired_devices,
+            revoke_paired_device,
+            get_server_settings,
+            save_server_settings,
+            get_server_status,
+            get_audio_profiles,
+            save_audio_profile,
+            delete_audio_profile,
+            apply_audio_profile,
+            get_volume_memory,
+            clear_volume_memory,
+            set_volume_memory_excluded,
+            get_rules,
+            save_rule,
+            delete_rule,
+            get_rule_log,
+            get_ducking_settings,
+            save_ducking_settings,
+            get_ducking_active,
+            subscribe_audio_levels,
+            unsubscribe_audio_levels
         ])
         .run(tauri::generate_context!())
         .expect("無法運行 Tauri");
@@ -0,0 +1,137 @@
+use crate::media::backend::media_backend;
+use crate::models::MediaInfo;
+use base64::{engine::general_purpose, Engine as _};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// 專輯封面圖片
+#[derive(Debug, Clone)]
+pub struct Artwork {
+    /// 圖片內容的雜湊，作為 HTTP 的 ETag
+    pub hash: String,
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+fn hash_of<T: Hash>(value: T) -> String {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// 以曲目資訊計算的雜湊，只有換歌時才會改變，客戶端據此決定何時重新下載封面
+/// 封面可能比曲目資訊晚到 (SMTC 常短暫回傳上一首的封面)，所以不能當作 ETag
+fn artwork_hash(info: &MediaInfo) -> String {
+    hash_of((&info.title, &info.artist, &info.album))
+}
+
+/// 封面在 HTTP 服務上的路徑，會話 ID 中的特殊字元會被編碼
+pub fn artwork_path(session_id: &str) -> String {
+    let mut encoded = String::with_capacity(session_id.len());
+    for byte in session_id.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    format!("/media/{}/artwork", encoded)
+}
+
+/// 為有封面的會話填入雜湊，客戶端在雜湊改變時才需要重新下載封面
+/// 封面網址由後端在播放器提供封面時填入
+pub fn with_artwork(info: MediaInfo) -> MediaInfo {
+    MediaInfo {
+        artwork_hash: info.artwork_url.as_ref().map(|_| artwork_hash(&info)),
+        ..info
+    }
+}
+
+/// 依圖片開頭的檔案簽章判斷格式
+fn content_type(bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        "image/png"
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        "image/jpeg"
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        "image/gif"
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        "image/webp"
+    } else if bytes.starts_with(b"BM") {
+        "image/bmp"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+/// 讀取會話目前的封面，每次都向播放器讀取，ETag 才會跟著實際的圖片改變
+/// 會話不存在或播放器沒有提供封面時回傳 None
+pub fn get_artwork(session_id: &str) -> Result<Option<Artwork>, String> {
+    let backend = media_backend();
+
+    if !backend
+        .get_sessions()?
+        .iter()
+        .any(|s| s.session_id == session_id)
+    {
+        return Ok(None);
+    }
+
+    let Some(encoded) = backend.get_thumbnail(Some(session_id))? else {
+        return Ok(None);
+    };
+    let bytes = general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("無法解碼專輯封面: {:?}", e))?;
+
+    Ok(Some(Artwork {
+        hash: hash_of(&bytes),
+        content_type: content_type(&bytes),
+        bytes,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::backend::set_media_backend;
+    use crate::media::mock::MockMediaBackend;
+    use crate::utils::backend_test_lock;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn artwork_hash_follows_the_image_not_the_track() {
+        let _lock = backend_test_lock().await;
+        let media = Arc::new(MockMediaBackend::fixture());
+        set_media_backend(media.clone());
+
+        // 換歌後播放器還是回傳上一首的封面
+        let png = general_purpose::STANDARD.encode(b"\x89PNG\r\n\x1a\nprevious");
+        media.update(|state| {
+            state.thumbnails.insert("spotify".to_string(), png);
+            state.players[0].title = "Next Song".to_string();
+        });
+        let stale = get_artwork("spotify").unwrap().unwrap();
+        assert_eq!(stale.content_type, "image/png");
+        assert_eq!(stale.bytes, b"\x89PNG\r\n\x1a\nprevious");
+
+        // 正確的封面到了之後，曲目資訊沒變但 ETag 改變
+        let jpeg = general_purpose::STANDARD.encode(b"\xff\xd8\xffnext");
+        media.update(|state| {
+            state.thumbnails.insert("spotify".to_string(), jpeg);
+        });
+        let current = get_artwork("spotify").unwrap().unwrap();
+        assert_ne!(current.hash, stale.hash);
+        assert_eq!(current.content_type, "image/jpeg");
+
+        assert!(get_artwork("missing").unwrap().is_none());
+
+        // 沒有封面的播放器不提供封面網址
+        media.update(|state| state.thumbnails.clear());
+        let info = crate::get_all_media_sessions().unwrap().remove(0);
+        assert_eq!(info.artwork_url, None);
+        assert_eq!(info.artwork_hash, None);
+        assert!(get_artwork("spotify").unwrap().is_none());
+        assert_eq!(artwork_path("a b/c"), "/media/a%20b%2Fc/artwork");
+    }
+}
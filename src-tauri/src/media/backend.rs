@@ -0,0 +1,144 @@
+use crate::models::{MediaInfo, RepeatMode};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, RwLock};
+
+const UNSUPPORTED_MESSAGE: &str = "媒體控制 只支持 Windows 與 Linux :(((";
+
+/// 指定的會話已經結束 (播放器關閉或停止回報) 時的錯誤訊息
+pub fn session_gone(session_id: &str) -> String {
+    format!("媒體會話已結束: {}", session_id)
+}
+
+/// 媒體後端
+/// 封裝播放器列舉、專輯封面與播放控制等平台相關操作
+pub trait MediaBackend: Send + Sync {
+    /// 獲取所有媒體會話(播放器)的資訊列表
+    fn get_sessions(&self) -> Result<Vec<MediaInfo>, String>;
+
+    /// 開始在背景監聽播放器的開啟、關閉與資訊變化，有變化時送出通知
+    /// sender 的接收端關閉後停止通知；回傳錯誤時監聽器會改用輪詢
+    fn watch(&self, sender: Sender<()>) -> Result<(), String>;
+
+    /// 獲取專輯封面，返回 Base64 編碼的圖片字串
+    /// 未指定 session_id 時使用目前的會話
+    fn get_thumbnail(&self, session_id: Option<&str>) -> Result<Option<String>, String>;
+
+    /// 切換播放/暫停狀態
+    fn play_pause(&self, session_id: Option<&str>) -> Result<(), String>;
+
+    /// 開始播放
+    fn play(&self, session_id: Option<&str>) -> Result<(), String>;
+
+    /// 暫停播放
+    fn pause(&self, session_id: Option<&str>) -> Result<(), String>;
+
+    /// 停止播放
+    fn stop(&self, session_id: Option<&str>) -> Result<(), String>;
+
+    /// 跳到下一首
+    fn next(&self, session_id: Option<&str>) -> Result<(), String>;
+
+    /// 回到上一首
+    fn previous(&self, session_id: Option<&str>) -> Result<(), String>;
+
+    /// 跳到指定的播放位置 (毫秒)
+    fn seek(&self, session_id: Option<&str>, position_ms: u64) -> Result<(), String>;
+
+    /// 開啟或關閉隨機播放
+    fn set_shuffle(&self, session_id: Option<&str>, shuffle: bool) -> Result<(), String>;
+
+    /// 設定重複播放模式
+    fn set_repeat(&self, session_id: Option<&str>, repeat: RepeatMode) -> Result<(), String>;
+
+    /// 設定播放速度，1.0 為正常速度
+    fn set_rate(&self, session_id: Option<&str>, rate: f64) -> Result<(), String>;
+}
+
+/// 不支援的平台，所有操作都回傳錯誤
+pub struct UnsupportedMediaBackend;
+
+impl MediaBackend for UnsupportedMediaBackend {
+    fn get_sessions(&self) -> Result<Vec<MediaInfo>, String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn watch(&self, _sender: Sender<()>) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn get_thumbnail(&self, _session_id: Option<&str>) -> Result<Option<String>, String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn play_pause(&self, _session_id: Option<&str>) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn play(&self, _session_id: Option<&str>) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn pause(&self, _session_id: Option<&str>) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn stop(&self, _session_id: Option<&str>) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn next(&self, _session_id: Option<&str>) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn previous(&self, _session_id: Option<&str>) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn seek(&self, _session_id: Option<&str>, _position_ms: u64) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn set_shuffle(&self, _session_id: Option<&str>, _shuffle: bool) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn set_repeat(&self, _session_id: Option<&str>, _repeat: RepeatMode) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+
+    fn set_rate(&self, _session_id: Option<&str>, _rate: f64) -> Result<(), String> {
+        Err(UNSUPPORTED_MESSAGE.to_string())
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref MEDIA_BACKEND: RwLock<Arc<dyn MediaBackend>> = RwLock::new(Arc::new(UnsupportedMediaBackend));
+}
+
+/// 依照目前平台選擇預設的媒體後端
+pub fn default_media_backend() -> Arc<dyn MediaBackend> {
+    #[cfg(target_os = "windows")]
+    {
+        Arc::new(crate::media::smtc::SmtcBackend)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        Arc::new(crate::media::mpris::MprisBackend::new())
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        Arc::new(UnsupportedMediaBackend)
+    }
+}
+
+/// 設定目前使用的媒體後端，應在啟動時呼叫
+pub fn set_media_backend(backend: Arc<dyn MediaBackend>) {
+    *MEDIA_BACKEND.write().unwrap() = backend;
+}
+
+/// 取得目前使用的媒體後端
+pub fn media_backend() -> Arc<dyn MediaBackend> {
+    MEDIA_BACKEND.read().unwrap().clone()
+}
@@ -7,6 +7,8 @@ pub struct MediaSessionCache {
     data: RwLock<Vec<MediaInfo>>,
     last_update: RwLock<Option<Instant>>,
     is_updating: AtomicBool,
+    /// 讀取期間收到變化通知，讀到的資料可能已經過期
+    invalidated: AtomicBool,
 }
 
 impl MediaSessionCache {
@@ -15,6 +17,7 @@ impl MediaSessionCache {
             data: RwLock::new(Vec::new()),
             last_update: RwLock::new(None),
             is_updating: AtomicBool::new(false),
+            invalidated: AtomicBool::new(false),
         }
     }
 
@@ -27,13 +30,28 @@ impl MediaSessionCache {
     }
 
     pub fn start_update(&self) -> bool {
-        self.is_updating.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_ok()
+        let started = self.is_updating.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).is_ok();
+        if started {
+            self.invalidated.store(false, Ordering::SeqCst);
+        }
+        started
     }
 
-    pub fn finish_update(&self, data: Vec<MediaInfo>) {
+    /// 回傳 false 表示讀取期間資料又有變化，仍在更新中，需要重新讀取
+    pub fn finish_update(&self, data: Vec<MediaInfo>) -> bool {
         *self.data.write().unwrap() = data;
+        if self.invalidated.swap(false, Ordering::SeqCst) {
+            return false;
+        }
         *self.last_update.write().unwrap() = Some(Instant::now());
         self.is_updating.store(false, Ordering::SeqCst);
+        true
+    }
+
+    /// 讓快取過期，下次讀取時重新整理
+    pub fn invalidate(&self) {
+        self.invalidated.store(true, Ordering::SeqCst);
+        *self.last_update.write().unwrap() = None;
     }
 
     pub fn get(&self) -> Vec<MediaInfo> {
@@ -47,10 +65,11 @@ impl MediaSessionCache {
     }
 }
 
+/// 會話 ID 是以管理器回傳的會話物件辨識，重新建立管理器會得到不同的物件，
+/// 所以只在第一次使用 (或先前建立失敗) 時建立
 #[cfg(target_os = "windows")]
 pub struct SessionManagerCache {
     manager: RwLock<Option<windows::Media::Control::GlobalSystemMediaTransportControlsSessionManager>>,
-    last_update: RwLock<Option<Instant>>,
 }
 
 #[cfg(target_os = "windows")]
@@ -58,35 +77,22 @@ impl SessionManagerCache {
     pub fn new() -> Self {
         Self {
             manager: RwLock::new(None),
-            last_update: RwLock::new(None),
         }
     }
 
-    pub fn get_or_refresh(&self, max_age: Duration) -> Option<windows::Media::Control::GlobalSystemMediaTransportControlsSessionManager> {
-        let should_refresh = {
-            let last = self.last_update.read().unwrap();
-            match *last {
-                None => true,
-                Some(time) => time.elapsed() > max_age,
-            }
-        };
-
-        if should_refresh {
-            use windows::Media::Control::*;
-            match GlobalSystemMediaTransportControlsSessionManager::RequestAsync() {
-                Ok(async_op) => match async_op.get() {
-                    Ok(new_manager) => {
-                        *self.manager.write().unwrap() = Some(new_manager.clone());
-                        *self.last_update.write().unwrap() = Some(Instant::now());
-                        return Some(new_manager);
-                    },
-                    Err(_) => {}
-                }
-                Err(_) => {}
-            }
+    pub fn get(&self) -> Option<windows::Media::Control::GlobalSystemMediaTransportControlsSessionManager> {
+        if let Some(manager) = self.manager.read().unwrap().clone() {
+            return Some(manager);
         }
 
-        self.manager.read().unwrap().clone()
+        use windows::Media::Control::*;
+        let new_manager = GlobalSystemMediaTransportControlsSessionManager::RequestAsync()
+            .ok()?
+            .get()
+            .ok()?;
+
+        let mut manager = self.manager.write().unwrap();
+        Some(manager.get_or_insert(new_manager).clone())
     }
 }
 
@@ -0,0 +1,237 @@
+use crate::media::artwork::artwork_path;
+use crate::media::backend::{session_gone, MediaBackend};
+use crate::models::{MediaInfo, RepeatMode};
+use std::collections::HashMap;
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+
+/// 模擬的播放器狀態，測試可直接讀寫
+#[derive(Debug, Clone, Default)]
+pub struct MockMediaState {
+    pub players: Vec<MediaInfo>,
+    /// session_id -> Base64 封面
+    pub thumbnails: HashMap<String, String>,
+    /// 依序記錄收到的控制指令，例如 "next:spotify"
+    pub commands: Vec<String>,
+}
+
+/// 記憶體內的媒體後端，供測試使用
+pub struct MockMediaBackend {
+    state: Mutex<MockMediaState>,
+    watchers: Mutex<Vec<Sender<()>>>,
+}
+
+impl MockMediaBackend {
+    pub fn new(state: MockMediaState) -> Self {
+        Self {
+            state: Mutex::new(state),
+            watchers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 一個正在播放的播放器
+    pub fn fixture() -> Self {
+        let mut thumbnails = HashMap::new();
+        thumbnails.insert("spotify".to_string(), "UE5HREFUQQ==".to_string());
+
+        Self::new(MockMediaState {
+            players: vec![MediaInfo {
+                session_id: "spotify".to_string(),
+                app_name: "Spotify.exe".to_string(),
+                title: "Song".to_string(),
+                artist: "Artist".to_string(),
+                album: "Album".to_string(),
+                is_playing: true,
+                can_go_next: true,
+                can_go_previous: false,
+                position_ms: Some(30_000),
+                start_time_ms: Some(0),
+                end_time_ms: Some(180_000),
+                timeline_updated_at: Some(0),
+                can_seek: true,
+                shuffle: Some(false),
+                repeat: Some(RepeatMode::None),
+                playback_rate: Some(1.0),
+                can_play: true,
+                can_pause: true,
+                can_stop: true,
+                can_play_pause_toggle: true,
+                can_shuffle: true,
+                can_repeat: true,
+                can_change_rate: false,
+                ..Default::default()
+            }],
+            thumbnails,
+            commands: Vec::new(),
+        })
+    }
+
+    /// 取得目前狀態的快照
+    pub fn state(&self) -> MockMediaState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// 修改狀態，模擬播放器自行切換歌曲等變化，並通知監聽器
+    pub fn update<F: FnOnce(&mut MockMediaState)>(&self, f: F) {
+        f(&mut self.state.lock().unwrap());
+
+        self.watchers
+            .lock()
+            .unwrap()
+            .retain(|sender| sender.send(()).is_ok());
+    }
+
+    /// 關閉所有監聽者的 sender，讓監聽循環結束
+    pub fn stop_watching(&self) {
+        self.watchers.lock().unwrap().clear();
+    }
+
+    /// 依照 session_id 取得播放器索引，未指定時使用第一個
+    fn resolve(state: &MockMediaState, session_id: Option<&str>) -> Result<usize, String> {
+        match session_id {
+            Some(id) => state
+                .players
+                .iter()
+                .position(|p| p.session_id == id)
+                .ok_or_else(|| session_gone(id)),
+            None if !state.players.is_empty() => Ok(0),
+            None => Err("找不到任何播放器".to_string()),
+        }
+    }
+
+    fn command(&self, name: &str, session_id: Option<&str>) -> Result<usize, String> {
+        let mut state = self.state.lock().unwrap();
+        let index = Self::resolve(&state, session_id)?;
+        let target = state.players[index].session_id.clone();
+        state.commands.push(format!("{}:{}", name, target));
+        Ok(index)
+    }
+
+    /// 記錄指令後，在播放器支援時修改它的狀態
+    fn control<F: FnOnce(&mut MediaInfo)>(
+        &self,
+        name: &str,
+        session_id: Option<&str>,
+        supported: fn(&MediaInfo) -> bool,
+        f: F,
+    ) -> Result<(), String> {
+        let index = self.command(name, session_id)?;
+        let mut state = self.state.lock().unwrap();
+        let player = &mut state.players[index];
+        if !supported(player) {
+            return Err(format!("播放器不支援此操作: {}", name));
+        }
+        f(player);
+        Ok(())
+    }
+}
+
+impl MediaBackend for MockMediaBackend {
+    fn get_sessions(&self) -> Result<Vec<MediaInfo>, String> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .players
+            .iter()
+            .map(|player| MediaInfo {
+                artwork_url: state
+                    .thumbnails
+                    .contains_key(&player.session_id)
+                    .then(|| artwork_path(&player.session_id)),
+                ..player.clone()
+            })
+            .collect())
+    }
+
+    fn watch(&self, sender: Sender<()>) -> Result<(), String> {
+        self.watchers.lock().unwrap().push(sender);
+        Ok(())
+    }
+
+    fn get_thumbnail(&self, session_id: Option<&str>) -> Result<Option<String>, String> {
+        let state = self.state.lock().unwrap();
+        let index = Self::resolve(&state, session_id)?;
+        Ok(state
+            .thumbnails
+            .get(&state.players[index].session_id)
+            .cloned())
+    }
+
+    fn play_pause(&self, session_id: Option<&str>) -> Result<(), String> {
+        let index = self.command("play_pause", session_id)?;
+        let mut state = self.state.lock().unwrap();
+        state.players[index].is_playing = !state.players[index].is_playing;
+        Ok(())
+    }
+
+    fn play(&self, session_id: Option<&str>) -> Result<(), String> {
+        self.control("play", session_id, |p| p.can_play, |p| p.is_playing = true)
+    }
+
+    fn pause(&self, session_id: Option<&str>) -> Result<(), String> {
+        self.control(
+            "pause",
+            session_id,
+            |p| p.can_pause,
+            |p| p.is_playing = false,
+        )
+    }
+
+    fn stop(&self, session_id: Option<&str>) -> Result<(), String> {
+        self.control(
+            "stop",
+            session_id,
+            |p| p.can_stop,
+            |p| {
+                p.is_playing = false;
+                p.position_ms = p.start_time_ms;
+            },
+        )
+    }
+
+    fn next(&self, session_id: Option<&str>) -> Result<(), String> {
+        self.command("next", session_id).map(|_| ())
+    }
+
+    fn previous(&self, session_id: Option<&str>) -> Result<(), String> {
+        self.command("previous", session_id).map(|_| ())
+    }
+
+    fn seek(&self, session_id: Option<&str>, position_ms: u64) -> Result<(), String> {
+        let index = self.command("seek", session_id)?;
+        let mut state = self.state.lock().unwrap();
+        let player = &mut state.players[index];
+        if !player.can_seek {
+            return Err("播放器不支援調整播放位置".to_string());
+        }
+        player.position_ms = Some(position_ms);
+        player.timeline_updated_at = Some(crate::utils::now_millis());
+        Ok(())
+    }
+
+    fn set_shuffle(&self, session_id: Option<&str>, shuffle: bool) -> Result<(), String> {
+        self.control(
+            "shuffle",
+            session_id,
+            |p| p.can_shuffle,
+            |p| p.shuffle = Some(shuffle),
+        )
+    }
+
+    fn set_repeat(&self, session_id: Option<&str>, repeat: RepeatMode) -> Result<(), String> {
+        self.control(
+            "repeat",
+            session_id,
+            |p| p.can_repeat,
+            |p| p.repeat = Some(repeat),
+        )
+    }
+
+    fn set_rate(&self, session_id: Option<&str>, rate: f64) -> Result<(), String> {
+        self.control(
+            "rate",
+            session_id,
+            |p| p.can_change_rate,
+            |p| p.playback_rate = Some(rate),
+        )
+    }
+}
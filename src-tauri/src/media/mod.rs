@@ -1,7 +1,20 @@
+mod artwork;
+mod backend;
+#[cfg(target_os = "windows")]
 mod cache;
+#[cfg(target_os = "windows")]
 mod thumbnail;
 mod controls;
+#[cfg(test)]
+pub mod mock;
 mod monitor;
+#[cfg(target_os = "linux")]
+mod mpris;
+mod session_ids;
+#[cfg(target_os = "windows")]
+mod smtc;
 
+pub use artwork::*;
+pub use backend::*;
 pub use controls::*;
 pub use monitor::*;
@@ -1,82 +1,262 @@
-use crate::media::controls::{get_all_media_sessions, get_media_thumbnail};
+use crate::media::backend::media_backend;
+use crate::media::controls::get_all_media_sessions;
+use crate::models::MediaInfo;
 use crate::utils::debug_log;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tauri::Emitter;
 
+/// 播放進度與推算的位置相差超過這個值時視為跳轉
+const TIMELINE_JUMP_THRESHOLD_MS: i64 = 2000;
+
+/// 後端不支援變化通知時的輪詢間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 收到通知後等待這段時間，合併同一次切歌產生的多個通知
+const COALESCE_WINDOW: Duration = Duration::from_millis(100);
+
+/// 沒有任何變化時檢查「媒體閒置」規則的間隔
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 比較是否有變化時忽略播放進度，否則播放中每次讀取都會不同
+fn without_position(info: &MediaInfo) -> MediaInfo {
+    MediaInfo {
+        position_ms: None,
+        timeline_updated_at: None,
+        ..info.clone()
+    }
+}
+
+/// 以上一次回報的進度推算現在的位置，與實際回報的位置相差太多就是跳轉 (例如拖動進度條)
+fn timeline_jumped(previous: &MediaInfo, current: &MediaInfo) -> bool {
+    let (Some(previous_position), Some(previous_at), Some(position), Some(updated_at)) = (
+        previous.position_ms,
+        previous.timeline_updated_at,
+        current.position_ms,
+        current.timeline_updated_at,
+    ) else {
+        return false;
+    };
+
+    let elapsed = if previous.is_playing {
+        updated_at as i64 - previous_at as i64
+    } else {
+        0
+    };
+    let expected = previous_position as i64 + elapsed;
+
+    (position as i64 - expected).abs() > TIMELINE_JUMP_THRESHOLD_MS
+}
+
+/// 兩次讀取之間單一播放器的變化
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaChange {
+    Added(MediaInfo),
+    Updated(MediaInfo),
+    Removed(String),
+    /// 其他資訊沒變，只有播放進度跳轉
+    TimelineJumped(MediaInfo),
+}
+
+/// 比較兩次讀取的播放器，只回傳有變化的會話
+fn diff_sessions(previous: &[MediaInfo], current: &[MediaInfo]) -> Vec<MediaChange> {
+    let mut changes: Vec<MediaChange> = previous
+        .iter()
+        .filter(|p| !current.iter().any(|c| c.session_id == p.session_id))
+        .map(|p| MediaChange::Removed(p.session_id.clone()))
+        .collect();
+
+    for info in current {
+        let change = match previous.iter().find(|p| p.session_id == info.session_id) {
+            None => MediaChange::Added(info.clone()),
+            Some(p) if without_position(p) != without_position(info) => {
+                MediaChange::Updated(info.clone())
+            }
+            Some(p) if timeline_jumped(p, info) => MediaChange::TimelineJumped(info.clone()),
+            Some(_) => continue,
+        };
+        changes.push(change);
+    }
+
+    changes
+}
+
 /// 媒體監聽循環
+/// 由後端通知變化後才重新讀取，只發布有變化的會話
 pub fn media_monitor_loop<R: tauri::Runtime>(
     app_handle: tauri::AppHandle<R>,
     http_server: Arc<crate::http_server::HttpServer<R>>,
 ) {
-    let mut last_all_sessions: Option<String> = None;
+    let (sender, receiver) = mpsc::channel();
 
-    loop {
-        thread::sleep(Duration::from_millis(500));
+    let receiver = match media_backend().watch(sender) {
+        Ok(()) => Some(receiver),
+        Err(_e) => {
+            debug_log!("無法監聽播放器變化，改用輪詢: {}", _e);
+            None
+        }
+    };
 
-        match get_all_media_sessions() {
-            Ok(all_sessions) => {
-                if let Ok(current_json) = serde_json::to_string(&all_sessions) {
-                    let has_changed = last_all_sessions.as_ref() != Some(&current_json);
+    run_media_monitor(receiver, |event_type, data| {
+        let _ = app_handle.emit(&event_type.replace('_', "-"), &data);
 
-                    if has_changed {
-                        last_all_sessions = Some(current_json);
+        let ws_message = serde_json::json!({
+            "type": event_type,
+            "data": data
+        });
+        http_server.broadcast(ws_message.to_string());
+    });
+}
 
-                        for media_info in &all_sessions {
-                            let _ = app_handle.emit("media-info-updated", media_info);
+/// 讀取播放器並發布變化，receiver 為 None 時改用輪詢
+/// 所有 sender 都關閉後結束
+pub fn run_media_monitor<F>(receiver: Option<Receiver<()>>, mut publish: F)
+where
+    F: FnMut(&str, serde_json::Value),
+{
+    let mut last_sessions: Vec<MediaInfo> = Vec::new();
 
-                            let ws_message = serde_json::json!({
-                                "type": "media_info_updated",
-                                "data": media_info
-                            });
-                            http_server.broadcast(ws_message.to_string());
-                        }
+    loop {
+        match get_all_media_sessions() {
+            Ok(sessions) => {
+                crate::rules::observe_media(&sessions);
 
-                        if let Some(first_session) = all_sessions.first() {
-                            let is_browser =
-                                first_session.app_name.to_lowercase().contains("chrome")
-                                    || first_session.app_name.to_lowercase().contains("edge")
-                                    || first_session.app_name.to_lowercase().contains("firefox")
-                                    || first_session.app_name.to_lowercase().contains("opera")
-                                    || first_session.app_name.to_lowercase().contains("brave");
-
-                            if is_browser {
-                                let app_handle_clone = app_handle.clone();
-                                let http_server_clone = Arc::clone(&http_server);
-                                let session_id = first_session.session_id.clone();
-                                thread::spawn(move || {
-                                    if let Ok(Some(thumbnail)) =
-                                        get_media_thumbnail(Some(session_id))
-                                    {
-                                        let _ = app_handle_clone
-                                            .emit("media-thumbnail-updated", &thumbnail);
-
-                                        let ws_message = serde_json::json!({
-                                            "type": "media_thumbnail_updated",
-                                            "data": thumbnail
-                                        });
-                                        http_server_clone.broadcast(ws_message.to_string());
-                                    }
-                                });
-                            }
+                for change in diff_sessions(&last_sessions, &sessions) {
+                    match change {
+                        MediaChange::Added(info) => {
+                            publish("media_session_added", serde_json::json!(info));
+                            // 舊版客戶端只認得 media_info_updated
+                            publish("media_info_updated", serde_json::json!(info));
+                        }
+                        MediaChange::Updated(info) => {
+                            publish("media_session_updated", serde_json::json!(info));
+                            publish("media_info_updated", serde_json::json!(info));
+                        }
+                        MediaChange::Removed(session_id) => {
+                            publish(
+                                "media_session_removed",
+                                serde_json::json!({ "session_id": session_id }),
+                            );
+                        }
+                        MediaChange::TimelineJumped(info) => {
+                            publish(
+                                "media_timeline_changed",
+                                serde_json::json!({
+                                    "session_id": info.session_id,
+                                    "position_ms": info.position_ms,
+                                    "start_time_ms": info.start_time_ms,
+                                    "end_time_ms": info.end_time_ms,
+                                    "timeline_updated_at": info.timeline_updated_at,
+                                    "is_playing": info.is_playing,
+                                }),
+                            );
                         }
                     }
                 }
 
-                if all_sessions.is_empty() && last_all_sessions.is_some() {
-                    last_all_sessions = None;
-                    let _ = app_handle.emit("media-info-cleared", ());
-
-                    let ws_message = serde_json::json!({
-                        "type": "media_info_cleared"
-                    });
-                    http_server.broadcast(ws_message.to_string());
+                if sessions.is_empty() && !last_sessions.is_empty() {
+                    publish("media_info_cleared", serde_json::Value::Null);
                 }
+
+                last_sessions = sessions;
             }
             Err(_e) => {
                 debug_log!("監聽器錯誤: {}", _e);
             }
         }
+
+        let Some(receiver) = receiver.as_ref() else {
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        };
+
+        // 沒有變化時只定期檢查「媒體閒置」規則
+        loop {
+            match receiver.recv_timeout(IDLE_CHECK_INTERVAL) {
+                Ok(()) => break,
+                Err(RecvTimeoutError::Timeout) => crate::rules::observe_media(&last_sessions),
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        // 切歌時標題、封面與進度會分別通知，合併後再讀取
+        let deadline = Instant::now() + COALESCE_WINDOW;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            if receiver.recv_timeout(remaining).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::backend::set_media_backend;
+    use crate::media::mock::MockMediaBackend;
+    use crate::utils::backend_test_lock;
+
+    #[test]
+    fn only_unexpected_position_changes_are_jumps() {
+        let previous = MockMediaBackend::fixture().state().players[0].clone();
+        let at = |position_ms: u64, updated_at: u64| MediaInfo {
+            position_ms: Some(position_ms),
+            timeline_updated_at: Some(updated_at),
+            ..previous.clone()
+        };
+
+        // 播放中經過 5 秒，進度也前進 5 秒
+        assert!(!timeline_jumped(&previous, &at(35_000, 5_000)));
+        assert!(timeline_jumped(&previous, &at(90_000, 5_000)));
+
+        // 暫停時進度不應該前進
+        let paused = MediaInfo {
+            is_playing: false,
+            ..previous.clone()
+        };
+        assert!(timeline_jumped(&paused, &at(35_000, 5_000)));
+        assert!(!timeline_jumped(&paused, &at(30_000, 5_000)));
+    }
+
+    #[tokio::test]
+    async fn only_changed_sessions_are_published() {
+        let _guard = backend_test_lock().await;
+        let mock = Arc::new(MockMediaBackend::fixture());
+        set_media_backend(mock.clone());
+
+        let (sender, receiver) = mpsc::channel();
+        media_backend().watch(sender).unwrap();
+
+        let mut published = Vec::new();
+        run_media_monitor(Some(receiver), |event_type, data| {
+            // 第一次讀取後模擬另一個播放器開啟、原本的播放器關閉
+            if event_type == "media_session_added" && data["session_id"] == "spotify" {
+                mock.update(|state| {
+                    let mut vlc = state.players[0].clone();
+                    vlc.session_id = "vlc".to_string();
+                    vlc.title = "Video".to_string();
+                    state.players = vec![vlc];
+                });
+                mock.stop_watching();
+            }
+            published.push((event_type.to_string(), data));
+        });
+
+        let events: Vec<(&str, &str)> = published
+            .iter()
+            .map(|(t, data)| (t.as_str(), data["session_id"].as_str().unwrap_or("")))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                ("media_session_added", "spotify"),
+                ("media_info_updated", "spotify"),
+                ("media_session_removed", "spotify"),
+                ("media_session_added", "vlc"),
+                ("media_info_updated", "vlc"),
+            ]
+        );
     }
 }
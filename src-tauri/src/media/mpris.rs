@@ -0,0 +1,478 @@
+use crate::media::artwork::artwork_path;
+use crate::media::backend::{session_gone, MediaBackend};
+use crate::media::session_ids::SessionIds;
+use crate::models::{MediaInfo, RepeatMode};
+use crate::utils::now_millis;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+use std::thread;
+use zbus::blocking::{fdo::DBusProxy, Connection, MessageIterator, Proxy};
+use zbus::message::Type as MessageType;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_NAMESPACE: &str = "org.mpris.MediaPlayer2";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const MPRIS_PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// 封面大小上限，與 Windows 後端一致
+const MAX_THUMBNAIL_SIZE: u64 = 5_000_000;
+
+/// Linux D-Bus MPRIS2 後端
+pub struct MprisBackend {
+    connection: Mutex<Option<Connection>>,
+    /// 以播放器的 unique name 配發 ID，格式與 Windows 相同
+    session_ids: Mutex<SessionIds<String>>,
+}
+
+/// 一個 MPRIS 播放器
+struct Player {
+    session_id: String,
+    /// bus name 去掉 MPRIS 前綴，例如 "vlc" 或 "firefox.instance_1_84"
+    app_name: String,
+    /// 播放器連線的 unique name，程式重新啟動後會不同
+    unique_name: String,
+}
+
+impl MprisBackend {
+    pub fn new() -> Self {
+        Self {
+            connection: Mutex::new(None),
+            session_ids: Mutex::new(SessionIds::new()),
+        }
+    }
+
+    /// 取得 session bus 連線，失敗時下次呼叫會重新連線
+    fn connection(&self) -> Result<Connection, String> {
+        let mut connection = self.connection.lock().unwrap();
+
+        if let Some(conn) = connection.as_ref() {
+            return Ok(conn.clone());
+        }
+
+        let conn = Connection::session().map_err(|e| format!("無法連接 D-Bus: {:?}", e))?;
+        *connection = Some(conn.clone());
+        Ok(conn)
+    }
+
+    fn reset_connection(&self) {
+        *self.connection.lock().unwrap() = None;
+    }
+
+    /// 列出所有 MPRIS 播放器並配發 ID
+    /// 同一個 bus name 可能在程式重新啟動後再次出現，所以以 unique name 識別播放器
+    fn players(&self, conn: &Connection) -> Result<Vec<Player>, String> {
+        let dbus = DBusProxy::new(conn).map_err(|e| format!("無法連接 D-Bus: {:?}", e))?;
+
+        let names = dbus.list_names().map_err(|e| {
+            self.reset_connection();
+            format!("無法取得播放器列表: {:?}", e)
+        })?;
+
+        let mut names: Vec<_> = names
+            .into_iter()
+            .filter(|name| name.starts_with(MPRIS_PREFIX))
+            .collect();
+        names.sort();
+
+        // 在列舉與查詢之間關閉的播放器沒有擁有者，直接略過
+        let current: Vec<(String, String)> = names
+            .into_iter()
+            .filter_map(|name| {
+                let owner = dbus.get_name_owner((*name).clone()).ok()?;
+                let app_name = name.trim_start_matches(MPRIS_PREFIX).to_string();
+                Some((owner.to_string(), app_name))
+            })
+            .collect();
+        let ids = self.session_ids.lock().unwrap().assign(current.clone());
+
+        Ok(current
+            .into_iter()
+            .zip(ids)
+            .map(|((unique_name, app_name), session_id)| Player {
+                session_id,
+                app_name,
+                unique_name,
+            })
+            .collect())
+    }
+
+    /// 依照 session_id 取得播放器，未指定時優先使用正在播放的播放器
+    fn resolve_player<'a>(
+        &self,
+        conn: &'a Connection,
+        session_id: Option<&str>,
+    ) -> Result<Proxy<'a>, String> {
+        if let Some(id) = session_id {
+            // 先重新列舉，已結束的播放器才不會留著舊的 ID
+            self.players(conn)?;
+            let unique_name = self
+                .session_ids
+                .lock()
+                .unwrap()
+                .get(id)
+                .cloned()
+                .ok_or_else(|| session_gone(id))?;
+            return player_proxy(conn, unique_name);
+        }
+
+        let mut first = None;
+        for Player { unique_name, .. } in self.players(conn)? {
+            let player = player_proxy(conn, unique_name)?;
+            if playback_status(&player) == "Playing" {
+                return Ok(player);
+            }
+            first.get_or_insert(player);
+        }
+
+        first.ok_or("找不到任何播放器".to_string())
+    }
+
+    fn call_player(&self, session_id: Option<&str>, method: &str) -> Result<(), String> {
+        let conn = self.connection()?;
+        let player = self.resolve_player(&conn, session_id)?;
+
+        player
+            .call_method(method, &())
+            .map_err(|e| format!("無法執行 {}: {:?}", method, e))?;
+
+        Ok(())
+    }
+
+    fn set_player_property<'a, T>(
+        &self,
+        session_id: Option<&str>,
+        property: &'a str,
+        value: T,
+    ) -> Result<(), String>
+    where
+        T: 'a + Into<zbus::zvariant::Value<'a>>,
+    {
+        let conn = self.connection()?;
+        let player = self.resolve_player(&conn, session_id)?;
+
+        player
+            .set_property(property, value)
+            .map_err(|e| format!("無法設定 {}: {:?}", property, e))?;
+
+        Ok(())
+    }
+}
+
+fn player_proxy(conn: &Connection, bus_name: String) -> Result<Proxy<'_>, String> {
+    zbus::blocking::proxy::Builder::new(conn)
+        .destination(bus_name)
+        .and_then(|b| b.path(MPRIS_PATH))
+        .and_then(|b| b.interface(MPRIS_PLAYER_INTERFACE))
+        .map(|b| b.cache_properties(CacheProperties::No))
+        .and_then(|b| b.build())
+        .map_err(|e| format!("無法連接播放器: {:?}", e))
+}
+
+fn playback_status(player: &Proxy) -> String {
+    player
+        .get_property::<String>("PlaybackStatus")
+        .unwrap_or_default()
+}
+
+fn metadata(player: &Proxy) -> HashMap<String, OwnedValue> {
+    player
+        .get_property::<HashMap<String, OwnedValue>>("Metadata")
+        .unwrap_or_default()
+}
+
+fn metadata_string(metadata: &HashMap<String, OwnedValue>, key: &str) -> String {
+    metadata
+        .get(key)
+        .and_then(|v| v.try_clone().ok())
+        .and_then(|v| String::try_from(v).ok())
+        .unwrap_or_default()
+}
+
+fn metadata_string_list(metadata: &HashMap<String, OwnedValue>, key: &str) -> String {
+    metadata
+        .get(key)
+        .and_then(|v| v.try_clone().ok())
+        .and_then(|v| Vec::<String>::try_from(v).ok())
+        .map(|list| list.join(", "))
+        .unwrap_or_default()
+}
+
+/// `mpris:length` 規格上為 x (i64)，部分播放器使用 t (u64)
+fn metadata_i64(metadata: &HashMap<String, OwnedValue>, key: &str) -> Option<i64> {
+    let value = metadata.get(key)?;
+
+    value
+        .try_clone()
+        .ok()
+        .and_then(|v| i64::try_from(v).ok())
+        .or_else(|| {
+            value
+                .try_clone()
+                .ok()
+                .and_then(|v| u64::try_from(v).ok())
+                .map(|v| v as i64)
+        })
+}
+
+/// LoopStatus 為 "None"、"Track" 或 "Playlist"
+fn repeat_mode(loop_status: &str) -> Option<RepeatMode> {
+    match loop_status {
+        "None" => Some(RepeatMode::None),
+        "Track" => Some(RepeatMode::Track),
+        "Playlist" => Some(RepeatMode::List),
+        _ => None,
+    }
+}
+
+fn loop_status(repeat: RepeatMode) -> &'static str {
+    match repeat {
+        RepeatMode::None => "None",
+        RepeatMode::Track => "Track",
+        RepeatMode::List => "Playlist",
+    }
+}
+
+/// MPRIS 的時間單位為微秒
+fn micros_to_ms(micros: i64) -> u64 {
+    (micros / 1000).max(0) as u64
+}
+
+/// 讀取 `mpris:artUrl` 指向的圖片，支援 file:// 與 http(s)://
+fn read_art_url(url: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    if let Some(path) = url.strip_prefix("file://") {
+        let path = percent_decode(path);
+        let file = std::fs::File::open(path).ok()?;
+        file.take(MAX_THUMBNAIL_SIZE + 1)
+            .read_to_end(&mut bytes)
+            .ok()?;
+    } else if url.starts_with("http://") || url.starts_with("https://") {
+        let response = ureq::get(url)
+            .timeout(std::time::Duration::from_secs(2))
+            .call()
+            .ok()?;
+        response
+            .into_reader()
+            .take(MAX_THUMBNAIL_SIZE + 1)
+            .read_to_end(&mut bytes)
+            .ok()?;
+    } else {
+        return None;
+    }
+
+    if bytes.is_empty() || bytes.len() as u64 > MAX_THUMBNAIL_SIZE {
+        return None;
+    }
+
+    Some(bytes)
+}
+
+/// 解碼 file:// 路徑中的 %XX
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+impl MediaBackend for MprisBackend {
+    fn get_sessions(&self) -> Result<Vec<MediaInfo>, String> {
+        let conn = self.connection()?;
+        let mut media_infos = Vec::new();
+
+        for Player {
+            session_id,
+            app_name,
+            unique_name,
+        } in self.players(&conn)?
+        {
+            let player = match player_proxy(&conn, unique_name) {
+                Ok(p) => p,
+                Err(_) => continue,
+            };
+
+            let metadata = metadata(&player);
+            let length = metadata_i64(&metadata, "mpris:length").filter(|l| *l > 0);
+
+            // Position 是即時的值，回報時間就是現在
+            let position_ms = length
+                .and_then(|_| player.get_property::<i64>("Position").ok())
+                .map(micros_to_ms);
+
+            // Shuffle、LoopStatus 與 Rate 都是選用的屬性，CanControl 為 false 時不能修改任何狀態
+            let can_control = player.get_property::<bool>("CanControl").unwrap_or(false);
+            let shuffle = player.get_property::<bool>("Shuffle").ok();
+            let repeat = player
+                .get_property::<String>("LoopStatus")
+                .ok()
+                .and_then(|status| repeat_mode(&status));
+            let playback_rate = player.get_property::<f64>("Rate").ok();
+            let minimum_rate = player.get_property::<f64>("MinimumRate").unwrap_or(1.0);
+            let maximum_rate = player.get_property::<f64>("MaximumRate").unwrap_or(1.0);
+            let can_pause = player.get_property::<bool>("CanPause").unwrap_or(false);
+            let artwork_url = (!metadata_string(&metadata, "mpris:artUrl").is_empty())
+                .then(|| artwork_path(&session_id));
+
+            media_infos.push(MediaInfo {
+                app_name,
+                session_id,
+                title: metadata_string(&metadata, "xesam:title"),
+                artist: metadata_string_list(&metadata, "xesam:artist"),
+                album: metadata_string(&metadata, "xesam:album"),
+                is_playing: playback_status(&player) == "Playing",
+                artwork_url,
+                can_go_next: player.get_property::<bool>("CanGoNext").unwrap_or(true),
+                can_go_previous: player.get_property::<bool>("CanGoPrevious").unwrap_or(true),
+                position_ms,
+                start_time_ms: length.map(|_| 0),
+                end_time_ms: length.map(micros_to_ms),
+                timeline_updated_at: position_ms.map(|_| now_millis()),
+                can_seek: player.get_property::<bool>("CanSeek").unwrap_or(false),
+                can_play: player.get_property::<bool>("CanPlay").unwrap_or(false),
+                can_pause,
+                can_stop: can_control,
+                can_play_pause_toggle: can_pause,
+                can_shuffle: can_control && shuffle.is_some(),
+                can_repeat: can_control && repeat.is_some(),
+                can_change_rate: can_control
+                    && playback_rate.is_some()
+                    && minimum_rate < maximum_rate,
+                shuffle,
+                repeat,
+                playback_rate,
+                ..Default::default()
+            });
+        }
+
+        Ok(media_infos)
+    }
+
+    fn watch(&self, sender: Sender<()>) -> Result<(), String> {
+        let conn = self.connection()?;
+
+        // PropertiesChanged 與 Seeked 都從播放器的物件路徑送出
+        let player_rule = zbus::MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .path(MPRIS_PATH)
+            .map_err(|e| format!("無法監聽播放器變化: {:?}", e))?
+            .build();
+
+        // 播放器開啟或關閉時 bus name 的擁有者會改變
+        let owner_rule = zbus::MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .interface("org.freedesktop.DBus")
+            .and_then(|b| b.member("NameOwnerChanged"))
+            .and_then(|b| b.arg0ns(MPRIS_NAMESPACE))
+            .map_err(|e| format!("無法監聽播放器變化: {:?}", e))?
+            .build();
+
+        for rule in [player_rule, owner_rule] {
+            let messages = MessageIterator::for_match_rule(rule, &conn, None)
+                .map_err(|e| format!("無法監聽播放器變化: {:?}", e))?;
+            let sender = sender.clone();
+
+            thread::spawn(move || {
+                for _ in messages {
+                    if sender.send(()).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    fn get_thumbnail(&self, session_id: Option<&str>) -> Result<Option<String>, String> {
+        use base64::{engine::general_purpose, Engine as _};
+
+        let conn = self.connection()?;
+        let player = self.resolve_player(&conn, session_id)?;
+
+        let art_url = metadata_string(&metadata(&player), "mpris:artUrl");
+        if art_url.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(read_art_url(&art_url).map(|bytes| general_purpose::STANDARD.encode(&bytes)))
+    }
+
+    fn play_pause(&self, session_id: Option<&str>) -> Result<(), String> {
+        self.call_player(session_id, "PlayPause")
+    }
+
+    fn play(&self, session_id: Option<&str>) -> Result<(), String> {
+        self.call_player(session_id, "Play")
+    }
+
+    fn pause(&self, session_id: Option<&str>) -> Result<(), String> {
+        self.call_player(session_id, "Pause")
+    }
+
+    fn stop(&self, session_id: Option<&str>) -> Result<(), String> {
+        self.call_player(session_id, "Stop")
+    }
+
+    fn next(&self, session_id: Option<&str>) -> Result<(), String> {
+        self.call_player(session_id, "Next")
+    }
+
+    fn previous(&self, session_id: Option<&str>) -> Result<(), String> {
+        self.call_player(session_id, "Previous")
+    }
+
+    fn seek(&self, session_id: Option<&str>, position_ms: u64) -> Result<(), String> {
+        let conn = self.connection()?;
+        let player = self.resolve_player(&conn, session_id)?;
+        let position = position_ms as i64 * 1000;
+
+        // SetPosition 需要目前曲目的 trackid，沒有時改用相對位移的 Seek
+        let track_id = metadata(&player)
+            .get("mpris:trackid")
+            .and_then(|v| v.try_clone().ok())
+            .and_then(|v| OwnedObjectPath::try_from(v).ok());
+
+        let result = match track_id {
+            Some(track_id) => player.call_method("SetPosition", &(track_id, position)),
+            None => {
+                let current = player.get_property::<i64>("Position").unwrap_or(0);
+                player.call_method("Seek", &(position - current))
+            }
+        };
+
+        result.map_err(|e| format!("無法調整播放位置: {:?}", e))?;
+
+        Ok(())
+    }
+
+    fn set_shuffle(&self, session_id: Option<&str>, shuffle: bool) -> Result<(), String> {
+        self.set_player_property(session_id, "Shuffle", shuffle)
+    }
+
+    fn set_repeat(&self, session_id: Option<&str>, repeat: RepeatMode) -> Result<(), String> {
+        self.set_player_property(session_id, "LoopStatus", loop_status(repeat))
+    }
+
+    fn set_rate(&self, session_id: Option<&str>, rate: f64) -> Result<(), String> {
+        self.set_player_property(session_id, "Rate", rate)
+    }
+}
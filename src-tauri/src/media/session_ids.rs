@@ -0,0 +1,65 @@
+/// 為媒體會話配發在整個生命週期內不變的 ID
+/// 系統列舉會話的順序會在其他播放器開啟或關閉時改變，所以不能用索引當作 ID
+pub struct SessionIds<K> {
+    sessions: Vec<(String, K)>,
+    next_id: u64,
+}
+
+impl<K: PartialEq> SessionIds<K> {
+    pub fn new() -> Self {
+        Self {
+            sessions: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// 依照這一輪列舉到的會話 (key, 程式名稱) 依序回傳 ID
+    /// 新的會話會配發新的 ID，沒有再出現的會話會被移除，它們的 ID 不會再被使用
+    pub fn assign(&mut self, current: Vec<(K, String)>) -> Vec<String> {
+        let mut previous = std::mem::take(&mut self.sessions);
+        let mut ids = Vec::with_capacity(current.len());
+
+        for (key, app_name) in current {
+            let id = match previous.iter().position(|(_, k)| *k == key) {
+                Some(index) => previous.swap_remove(index).0,
+                None => {
+                    self.next_id += 1;
+                    format!("{}_{}", app_name, self.next_id)
+                }
+            };
+
+            ids.push(id.clone());
+            self.sessions.push((id, key));
+        }
+
+        ids
+    }
+
+    pub fn get(&self, id: &str) -> Option<&K> {
+        self.sessions.iter().find(|(i, _)| i == id).map(|(_, k)| k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_follow_sessions_when_the_order_changes() {
+        let mut ids = SessionIds::new();
+        let session = |key: u32, app: &str| (key, app.to_string());
+
+        let first = ids.assign(vec![session(1, "Spotify.exe"), session(2, "chrome")]);
+        assert_eq!(first, ["Spotify.exe_1", "chrome_2"]);
+
+        // Spotify 關閉後 chrome 排到第一個，ID 不變
+        let second = ids.assign(vec![session(2, "chrome"), session(3, "vlc")]);
+        assert_eq!(second, ["chrome_2", "vlc_3"]);
+        assert_eq!(ids.get("chrome_2"), Some(&2));
+        assert_eq!(ids.get("Spotify.exe_1"), None);
+
+        // 重新開啟的 Spotify 是新的會話
+        let third = ids.assign(vec![session(4, "Spotify.exe"), session(2, "chrome")]);
+        assert_eq!(third, ["Spotify.exe_4", "chrome_2"]);
+    }
+}
@@ -0,0 +1,543 @@
+use crate::media::artwork::artwork_path;
+use crate::media::backend::{session_gone, MediaBackend};
+use crate::media::cache::{MEDIA_SESSION_CACHE, SESSION_MANAGER_CACHE};
+use crate::media::session_ids::SessionIds;
+use crate::media::thumbnail::get_thumbnail_safe;
+use crate::models::{MediaInfo, RepeatMode};
+use crate::utils::{debug_log, ComGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+use std::time::Duration;
+use windows::Foundation::TypedEventHandler;
+use windows::Media::Control::*;
+use windows::Media::MediaPlaybackAutoRepeatMode;
+
+/// Windows GlobalSystemMediaTransportControls 後端
+pub struct SmtcBackend;
+
+/// 以 COM 物件身分比較的會話，同一個會話每次列舉都會是同一個物件
+#[derive(Clone)]
+struct SessionKey(GlobalSystemMediaTransportControlsSession);
+
+impl PartialEq for SessionKey {
+    fn eq(&self, other: &Self) -> bool {
+        use windows::core::{IUnknown, Interface};
+
+        match (self.0.cast::<IUnknown>(), other.0.cast::<IUnknown>()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref SESSION_IDS: Mutex<SessionIds<SessionKey>> = Mutex::new(SessionIds::new());
+    /// 監聽器的 sender，會話資訊更新後通知
+    static ref WATCHERS: Mutex<Vec<Sender<()>>> = Mutex::new(Vec::new());
+    /// 已經註冊變化通知的會話
+    static ref WATCHED_SESSIONS: Mutex<Vec<SessionKey>> = Mutex::new(Vec::new());
+}
+
+/// 是否已經向管理器註冊變化通知
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// 依照 ID 取得會話，不依賴列舉的順序；會話已經結束時回傳錯誤
+pub fn get_session_by_id(
+    session_id: &str,
+) -> Result<GlobalSystemMediaTransportControlsSession, String> {
+    let key = SESSION_IDS
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .cloned()
+        .ok_or_else(|| session_gone(session_id))?;
+
+    // ID 只在列舉時更新，確認會話仍然存在
+    let session_manager = SESSION_MANAGER_CACHE
+        .get()
+        .ok_or("無法取得訊息管理器".to_string())?;
+
+    let sessions = session_manager
+        .GetSessions()
+        .map_err(|e| format!("無法取得會話列表: {:?}", e))?;
+
+    let session_count = sessions.Size().unwrap_or(0);
+
+    for i in 0..session_count {
+        if let Ok(session) = sessions.GetAt(i) {
+            if SessionKey(session) == key {
+                return Ok(key.0);
+            }
+        }
+    }
+
+    Err(session_gone(session_id))
+}
+
+/// SMTC 的時間單位為 100 奈秒
+const TICKS_PER_MILLISECOND: i64 = 10_000;
+
+/// DateTime 以 1601-01-01 起算，與 Unix 時間相差的 100 奈秒數
+const UNIX_EPOCH_TICKS: i64 = 116_444_736_000_000_000;
+
+fn timespan_ms(timespan: windows::Foundation::TimeSpan) -> u64 {
+    (timespan.Duration / TICKS_PER_MILLISECOND).max(0) as u64
+}
+
+fn datetime_unix_ms(datetime: windows::Foundation::DateTime) -> u64 {
+    ((datetime.UniversalTime - UNIX_EPOCH_TICKS) / TICKS_PER_MILLISECOND).max(0) as u64
+}
+
+/// 依照 session_id 取得會話，未指定時使用目前的會話
+fn resolve_session(session_id: Option<&str>) -> Option<GlobalSystemMediaTransportControlsSession> {
+    let session = if let Some(id) = session_id {
+        get_session_by_id(id).ok()?
+    } else {
+        SESSION_MANAGER_CACHE.get()?.GetCurrentSession().ok()?
+    };
+
+    if session.SourceAppUserModelId().is_err() {
+        return None;
+    }
+
+    Some(session)
+}
+
+type ControlQuery =
+    fn(&GlobalSystemMediaTransportControlsSessionPlaybackControls) -> windows::core::Result<bool>;
+
+/// 播放器沒有回報控制項時視為不支援
+fn control_enabled(
+    controls: Option<&GlobalSystemMediaTransportControlsSessionPlaybackControls>,
+    query: ControlQuery,
+) -> bool {
+    controls.and_then(|c| query(c).ok()).unwrap_or(false)
+}
+
+fn repeat_mode(mode: MediaPlaybackAutoRepeatMode) -> Option<RepeatMode> {
+    match mode {
+        MediaPlaybackAutoRepeatMode::None => Some(RepeatMode::None),
+        MediaPlaybackAutoRepeatMode::Track => Some(RepeatMode::Track),
+        MediaPlaybackAutoRepeatMode::List => Some(RepeatMode::List),
+        _ => None,
+    }
+}
+
+fn auto_repeat_mode(repeat: RepeatMode) -> MediaPlaybackAutoRepeatMode {
+    match repeat {
+        RepeatMode::None => MediaPlaybackAutoRepeatMode::None,
+        RepeatMode::Track => MediaPlaybackAutoRepeatMode::Track,
+        RepeatMode::List => MediaPlaybackAutoRepeatMode::List,
+    }
+}
+
+/// 在背景執行緒對會話送出控制指令
+/// 指定的會話已經結束時直接回傳錯誤，避免指令送到其他播放器
+fn spawn_session_command<F>(session_id: Option<&str>, command: F) -> Result<(), String>
+where
+    F: FnOnce(&GlobalSystemMediaTransportControlsSession) + Send + 'static,
+{
+    if let Some(id) = session_id {
+        if SESSION_IDS.lock().unwrap().get(id).is_none() {
+            return Err(session_gone(id));
+        }
+    }
+
+    let session_id = session_id.map(|s| s.to_string());
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let _com_guard = ComGuard::new();
+
+        if let Some(session) = resolve_session(session_id.as_deref()) {
+            command(&session);
+        }
+    });
+
+    Ok(())
+}
+
+fn notify_watchers() {
+    WATCHERS
+        .lock()
+        .unwrap()
+        .retain(|sender| sender.send(()).is_ok());
+}
+
+/// 會話有變化時讓快取過期並在背景重新讀取，讀取完成後才通知監聽器
+fn session_changed() {
+    MEDIA_SESSION_CACHE.invalidate();
+    let _ = SmtcBackend.get_sessions();
+}
+
+/// 替新出現的會話註冊資訊、播放狀態與進度的變化通知
+fn watch_sessions(current: &[(SessionKey, String)]) {
+    if !WATCHING.load(Ordering::SeqCst) {
+        return;
+    }
+
+    let mut watched = WATCHED_SESSIONS.lock().unwrap();
+    watched.retain(|key| current.iter().any(|(k, _)| k == key));
+
+    for (key, _) in current {
+        if watched.contains(key) {
+            continue;
+        }
+
+        match watch_session(&key.0) {
+            Ok(()) => watched.push(key.clone()),
+            Err(_e) => {
+                debug_log!("無法監聽會話變化: {:?}", _e);
+            }
+        }
+    }
+}
+
+fn watch_session(session: &GlobalSystemMediaTransportControlsSession) -> windows::core::Result<()> {
+    session.MediaPropertiesChanged(&TypedEventHandler::<
+        GlobalSystemMediaTransportControlsSession,
+        MediaPropertiesChangedEventArgs,
+    >::new(|_, _| {
+        session_changed();
+        Ok(())
+    }))?;
+
+    session.PlaybackInfoChanged(&TypedEventHandler::<
+        GlobalSystemMediaTransportControlsSession,
+        PlaybackInfoChangedEventArgs,
+    >::new(|_, _| {
+        session_changed();
+        Ok(())
+    }))?;
+
+    session.TimelinePropertiesChanged(&TypedEventHandler::<
+        GlobalSystemMediaTransportControlsSession,
+        TimelinePropertiesChangedEventArgs,
+    >::new(|_, _| {
+        session_changed();
+        Ok(())
+    }))?;
+
+    Ok(())
+}
+
+/// 讀取所有會話的資訊，呼叫端需要先初始化 COM
+fn read_sessions() -> Vec<MediaInfo> {
+    let Some(session_manager) = SESSION_MANAGER_CACHE.get() else {
+        return Vec::new();
+    };
+
+    let Ok(sessions) = session_manager.GetSessions() else {
+        return Vec::new();
+    };
+
+    // 會話的順序會在其他播放器開啟或關閉時改變，ID 要依照會話物件配發
+    let session_count = sessions.Size().unwrap_or(0);
+    let current: Vec<(SessionKey, String)> = (0..session_count)
+        .filter_map(|i| sessions.GetAt(i).ok())
+        .filter_map(|session| {
+            let app_name = session.SourceAppUserModelId().ok()?.to_string();
+            Some((SessionKey(session), app_name))
+        })
+        .collect();
+    let ids = SESSION_IDS.lock().unwrap().assign(current.clone());
+    watch_sessions(&current);
+
+    let mut media_infos = Vec::new();
+
+    for ((SessionKey(session), app_name), session_id) in current.into_iter().zip(ids) {
+        if let Ok(props_async) = session.TryGetMediaPropertiesAsync() {
+            if let Ok(props) = props_async.get() {
+                let title = props.Title().unwrap_or_default().to_string();
+                let artist = props.Artist().unwrap_or_default().to_string();
+                let album = props.AlbumTitle().unwrap_or_default().to_string();
+                let artwork_url = props.Thumbnail().ok().map(|_| artwork_path(&session_id));
+
+                let playback_info = session.GetPlaybackInfo().ok();
+                let is_playing = playback_info
+                    .as_ref()
+                    .and_then(|info| info.PlaybackStatus().ok())
+                    .map(|status| {
+                        status == GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing
+                    })
+                    .unwrap_or(false);
+
+                let controls = playback_info.as_ref().and_then(|info| info.Controls().ok());
+
+                let next_result = controls.as_ref().and_then(|c| c.IsNextEnabled().ok());
+                let prev_result = controls.as_ref().and_then(|c| c.IsPreviousEnabled().ok());
+
+                let can_go_next = next_result.unwrap_or(true);
+                let can_go_previous = prev_result.unwrap_or(true);
+
+                let enabled = |f: ControlQuery| control_enabled(controls.as_ref(), f);
+
+                let shuffle = playback_info
+                    .as_ref()
+                    .and_then(|info| info.IsShuffleActive().ok())
+                    .and_then(|v| v.Value().ok());
+                let repeat = playback_info
+                    .as_ref()
+                    .and_then(|info| info.AutoRepeatMode().ok())
+                    .and_then(|v| v.Value().ok())
+                    .and_then(repeat_mode);
+                let playback_rate = playback_info
+                    .as_ref()
+                    .and_then(|info| info.PlaybackRate().ok())
+                    .and_then(|v| v.Value().ok());
+
+                let can_seek = controls
+                    .as_ref()
+                    .and_then(|c| c.IsPlaybackPositionEnabled().ok())
+                    .unwrap_or(false);
+
+                // 沒有提供進度的播放器結束時間為 0
+                let timeline = session
+                    .GetTimelineProperties()
+                    .ok()
+                    .filter(|t| t.EndTime().map(|e| e.Duration > 0).unwrap_or(false));
+                let position_ms = timeline
+                    .as_ref()
+                    .and_then(|t| t.Position().ok())
+                    .map(timespan_ms);
+                let start_time_ms = timeline
+                    .as_ref()
+                    .and_then(|t| t.StartTime().ok())
+                    .map(timespan_ms);
+                let end_time_ms = timeline
+                    .as_ref()
+                    .and_then(|t| t.EndTime().ok())
+                    .map(timespan_ms);
+                let timeline_updated_at = timeline
+                    .as_ref()
+                    .and_then(|t| t.LastUpdatedTime().ok())
+                    .map(datetime_unix_ms);
+
+                media_infos.push(MediaInfo {
+                    session_id,
+                    app_name,
+                    title,
+                    artist,
+                    album,
+                    is_playing,
+                    artwork_url,
+                    artwork_hash: None,
+                    can_go_next,
+                    can_go_previous,
+                    position_ms,
+                    start_time_ms,
+                    end_time_ms,
+                    timeline_updated_at,
+                    can_seek,
+                    shuffle,
+                    repeat,
+                    playback_rate,
+                    can_play: enabled(|c| c.IsPlayEnabled()),
+                    can_pause: enabled(|c| c.IsPauseEnabled()),
+                    can_stop: enabled(|c| c.IsStopEnabled()),
+                    can_play_pause_toggle: enabled(|c| c.IsPlayPauseToggleEnabled()),
+                    can_record: enabled(|c| c.IsRecordEnabled()),
+                    can_fast_forward: enabled(|c| c.IsFastForwardEnabled()),
+                    can_rewind: enabled(|c| c.IsRewindEnabled()),
+                    can_channel_up: enabled(|c| c.IsChannelUpEnabled()),
+                    can_channel_down: enabled(|c| c.IsChannelDownEnabled()),
+                    can_shuffle: enabled(|c| c.IsShuffleEnabled()),
+                    can_repeat: enabled(|c| c.IsRepeatEnabled()),
+                    can_change_rate: enabled(|c| c.IsPlaybackRateEnabled()),
+                });
+            }
+        }
+    }
+
+    media_infos
+}
+
+impl MediaBackend for SmtcBackend {
+    fn get_sessions(&self) -> Result<Vec<MediaInfo>, String> {
+        use std::thread;
+
+        let cache_max_age = Duration::from_millis(500);
+
+        if !MEDIA_SESSION_CACHE.should_update(cache_max_age) {
+            return Ok(MEDIA_SESSION_CACHE.get());
+        }
+
+        if !MEDIA_SESSION_CACHE.start_update() {
+            return Ok(MEDIA_SESSION_CACHE.get());
+        }
+
+        thread::spawn(move || {
+            let _com_guard = ComGuard::new();
+
+            // 讀取期間又收到變化通知時重新讀取一次
+            while !MEDIA_SESSION_CACHE.finish_update(read_sessions()) {}
+            notify_watchers();
+        });
+
+        Ok(MEDIA_SESSION_CACHE.get())
+    }
+
+    fn watch(&self, sender: Sender<()>) -> Result<(), String> {
+        WATCHERS.lock().unwrap().push(sender);
+
+        if WATCHING.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let _com_guard = ComGuard::new();
+
+        let registered = SESSION_MANAGER_CACHE
+            .get()
+            .ok_or("無法取得訊息管理器".to_string())
+            .and_then(|manager| {
+                manager
+                    .SessionsChanged(&TypedEventHandler::<
+                        GlobalSystemMediaTransportControlsSessionManager,
+                        SessionsChangedEventArgs,
+                    >::new(|_, _| {
+                        session_changed();
+                        Ok(())
+                    }))
+                    .map_err(|e| format!("無法監聽播放器變化: {:?}", e))
+            });
+
+        if let Err(e) = registered {
+            WATCHING.store(false, Ordering::SeqCst);
+            WATCHERS.lock().unwrap().clear();
+            return Err(e);
+        }
+
+        // 替已經存在的會話註冊通知
+        session_changed();
+
+        Ok(())
+    }
+
+    fn get_thumbnail(&self, session_id: Option<&str>) -> Result<Option<String>, String> {
+        let session = if let Some(id) = session_id {
+            get_session_by_id(id)?
+        } else {
+            let session_manager = GlobalSystemMediaTransportControlsSessionManager::RequestAsync()
+                .map_err(|e| format!("無法取得訊息管理器: {:?}", e))?
+                .get()
+                .map_err(|e| format!("無法取得: {:?}", e))?;
+
+            session_manager
+                .GetCurrentSession()
+                .map_err(|e| format!("無法取得: {:?}", e))?
+        };
+
+        if session.SourceAppUserModelId().is_err() {
+            return Ok(None);
+        }
+
+        let props = session
+            .TryGetMediaPropertiesAsync()
+            .map_err(|e| format!("無法取得媒體屬性: {:?}", e))?
+            .get()
+            .map_err(|e| format!("無法取得媒體屬性: {:?}", e))?;
+
+        Ok(get_thumbnail_safe(&props))
+    }
+
+    fn play_pause(&self, session_id: Option<&str>) -> Result<(), String> {
+        spawn_session_command(session_id, |session| {
+            let playback_info = match session.GetPlaybackInfo() {
+                Ok(info) => info,
+                Err(_) => {
+                    return;
+                }
+            };
+
+            let status = match playback_info.PlaybackStatus() {
+                Ok(s) => s,
+                Err(_) => {
+                    return;
+                }
+            };
+
+            if status == GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing {
+                if let Ok(async_op) = session.TryPauseAsync() {
+                    let _ = async_op.get();
+                }
+            } else {
+                if let Ok(async_op) = session.TryPlayAsync() {
+                    let _ = async_op.get();
+                }
+            }
+        })
+    }
+
+    fn play(&self, session_id: Option<&str>) -> Result<(), String> {
+        spawn_session_command(session_id, |session| {
+            if let Ok(async_op) = session.TryPlayAsync() {
+                let _ = async_op.get();
+            }
+        })
+    }
+
+    fn pause(&self, session_id: Option<&str>) -> Result<(), String> {
+        spawn_session_command(session_id, |session| {
+            if let Ok(async_op) = session.TryPauseAsync() {
+                let _ = async_op.get();
+            }
+        })
+    }
+
+    fn stop(&self, session_id: Option<&str>) -> Result<(), String> {
+        spawn_session_command(session_id, |session| {
+            if let Ok(async_op) = session.TryStopAsync() {
+                let _ = async_op.get();
+            }
+        })
+    }
+
+    fn next(&self, session_id: Option<&str>) -> Result<(), String> {
+        spawn_session_command(session_id, |session| {
+            if let Ok(async_op) = session.TrySkipNextAsync() {
+                let _ = async_op.get();
+            }
+        })
+    }
+
+    fn previous(&self, session_id: Option<&str>) -> Result<(), String> {
+        spawn_session_command(session_id, |session| {
+            if let Ok(async_op) = session.TrySkipPreviousAsync() {
+                let _ = async_op.get();
+            }
+        })
+    }
+
+    fn seek(&self, session_id: Option<&str>, position_ms: u64) -> Result<(), String> {
+        spawn_session_command(session_id, move |session| {
+            let position = position_ms as i64 * TICKS_PER_MILLISECOND;
+            if let Ok(async_op) = session.TryChangePlaybackPositionAsync(position) {
+                let _ = async_op.get();
+            }
+        })
+    }
+
+    fn set_shuffle(&self, session_id: Option<&str>, shuffle: bool) -> Result<(), String> {
+        spawn_session_command(session_id, move |session| {
+            if let Ok(async_op) = session.TryChangeShuffleActiveAsync(shuffle) {
+                let _ = async_op.get();
+            }
+        })
+    }
+
+    fn set_repeat(&self, session_id: Option<&str>, repeat: RepeatMode) -> Result<(), String> {
+        spawn_session_command(session_id, move |session| {
+            if let Ok(async_op) = session.TryChangeAutoRepeatModeAsync(auto_repeat_mode(repeat)) {
+                let _ = async_op.get();
+            }
+        })
+    }
+
+    fn set_rate(&self, session_id: Option<&str>, rate: f64) -> Result<(), String> {
+        spawn_session_command(session_id, move |session| {
+            if let Ok(async_op) = session.TryChangePlaybackRateAsync(rate) {
+                let _ = async_op.get();
+            }
+        })
+    }
+}
@@ -1,257 +1,968 @@
+use crate::models::{DeviceDirection, DeviceRole, RepeatMode};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::time::Duration;
 
-pub async fn handle_message(msg: Value) -> Option<Value> {
-    let msg_type = msg.get("type")?.as_str()?;
-
-    match msg_type {
-        "get_audio_sessions" => handle_get_audio_sessions().await,
-        "set_session_volume" => handle_set_session_volume(msg).await,
-        "set_session_mute" => handle_set_session_mute(msg).await,
-        "get_audio_devices" => handle_get_audio_devices().await,
-        "set_default_device" => handle_set_default_device(msg).await,
-        "get_default_device_volume" => handle_get_default_device_volume().await,
-        "set_default_device_volume" => handle_set_default_device_volume(msg).await,
-        "get_default_device_mute" => handle_get_default_device_mute().await,
-        "set_default_device_mute" => handle_set_default_device_mute(msg).await,
-        "get_all_media_sessions" => handle_get_all_media_sessions().await,
-        "get_media_info" => handle_get_media_info().await,
-        "get_media_thumbnail" => handle_get_media_thumbnail(msg).await,
-        "media_play_pause" => handle_media_play_pause(msg),
-        "media_next" => handle_media_next(msg),
-        "media_previous" => handle_media_previous(msg),
-        _ => Some(json!({
-            "type": "error",
-            "message": format!("未知的消息類型: {}", msg_type)
-        })),
+/// WebSocket 協定版本，透過 mDNS TXT 記錄公告，不相容的變更時遞增
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// 客戶端請求，`type` 對應變體名稱，參數放在 `data`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum Request {
+    GetAudioSessions {
+        #[serde(default)]
+        grouped: Option<bool>,
+    },
+    SetSessionVolume {
+        // 舊版客戶端使用 session_name
+        #[serde(alias = "session_name")]
+        session_id: String,
+        volume: f32,
+    },
+    SetSessionMute {
+        #[serde(alias = "session_name")]
+        session_id: String,
+        mute: bool,
+    },
+    SetSessionDevice {
+        #[serde(alias = "session_name")]
+        session_id: String,
+        /// 未指定時改回跟隨系統預設裝置
+        #[serde(default)]
+        device_id: Option<String>,
+    },
+    GetAppRoutes {},
+    ClearAppRoute {
+        group: String,
+    },
+    GetAudioDevices {
+        /// 未指定時為輸出裝置
+        #[serde(default)]
+        direction: Option<DeviceDirection>,
+        /// 一併列出停用、未接上與已移除的裝置
+        #[serde(default)]
+        include_inactive: Option<bool>,
+    },
+    SetDefaultDevice {
+        device_id: String,
+        /// 未指定時為一般與多媒體
+        #[serde(default)]
+        roles: Option<Vec<DeviceRole>>,
+    },
+    GetDefaultDeviceVolume {
+        #[serde(default)]
+        direction: Option<DeviceDirection>,
+    },
+    SetDefaultDeviceVolume {
+        volume: f32,
+        #[serde(default)]
+        direction: Option<DeviceDirection>,
+    },
+    GetDefaultDeviceMute {
+        #[serde(default)]
+        direction: Option<DeviceDirection>,
+    },
+    SetDefaultDeviceMute {
+        mute: bool,
+        #[serde(default)]
+        direction: Option<DeviceDirection>,
+    },
+    ToggleMicrophoneMute {},
+    GetDeviceVolume {
+        device_id: String,
+    },
+    SetDeviceVolume {
+        device_id: String,
+        volume: f32,
+    },
+    GetDeviceMute {
+        device_id: String,
+    },
+    SetDeviceMute {
+        device_id: String,
+        mute: bool,
+    },
+    GetAllMediaSessions {},
+    GetMediaInfo {},
+    GetMediaThumbnail {
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    MediaPlayPause {
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    MediaPlay {
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    MediaPause {
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    MediaStop {
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    MediaNext {
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    MediaPrevious {
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    MediaSeek {
+        #[serde(default)]
+        session_id: Option<String>,
+        /// 播放位置 (毫秒)
+        position_ms: u64,
+    },
+    MediaSetShuffle {
+        #[serde(default)]
+        session_id: Option<String>,
+        shuffle: bool,
+    },
+    MediaSetRepeat {
+        #[serde(default)]
+        session_id: Option<String>,
+        repeat: RepeatMode,
+    },
+    MediaSetRate {
+        #[serde(default)]
+        session_id: Option<String>,
+        rate: f64,
+    },
+    GetProfiles {},
+    ApplyProfile {
+        name: String,
+    },
+    GetRules {},
+    GetRuleLog {},
+    GetVolumeMemory {},
+    ClearVolumeMemory {
+        /// 未指定時清除全部
+        #[serde(default)]
+        group: Option<String>,
+    },
+    SubscribeLevels {
+        /// 傳送間隔 (毫秒)，預設 100
+        #[serde(default)]
+        interval_ms: Option<u64>,
+    },
+    UnsubscribeLevels {},
+}
+
+impl Request {
+    /// 只讀取狀態的請求，未配對的客戶端也可以使用
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Request::GetAudioSessions { .. }
+                | Request::GetAppRoutes {}
+                | Request::GetAudioDevices { .. }
+                | Request::GetDefaultDeviceVolume { .. }
+                | Request::GetDefaultDeviceMute { .. }
+                | Request::GetDeviceVolume { .. }
+                | Request::GetDeviceMute { .. }
+                | Request::GetAllMediaSessions {}
+                | Request::GetMediaInfo {}
+                | Request::GetMediaThumbnail { .. }
+                | Request::GetProfiles {}
+                | Request::GetRules {}
+                | Request::GetRuleLog {}
+                | Request::GetVolumeMemory {}
+                | Request::SubscribeLevels { .. }
+                | Request::UnsubscribeLevels {}
+        )
     }
 }
 
-// === Audio Sessions ===
+/// 連線的權限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// 未配對，只能讀取狀態
+    ReadOnly,
+    /// 已配對或本機
+    Full,
+}
 
-async fn handle_get_audio_sessions() -> Option<Value> {
-    match crate::get_audio_sessions() {
-        Ok(sessions) => Some(json!({
-            "type": "audio_sessions",
-            "data": sessions
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
-    }
+/// 只對單一連線有效的訂閱，由 WebSocket 連線保存
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Subscriptions {
+    /// 訂閱音量峰值時的傳送間隔
+    pub levels: Option<Duration>,
 }
 
-async fn handle_set_session_volume(msg: Value) -> Option<Value> {
-    let data = msg.get("data")?;
-    let session_name = data.get("session_name")?.as_str()?.to_string();
-    let volume = data.get("volume")?.as_f64()? as f32;
-
-    match crate::set_session_volume(session_name, volume) {
-        Ok(_) => Some(json!({
-            "type": "success",
-            "message": "音量設定成功"
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
-    }
+/// 外層信封，`id` 由客戶端自訂，會原樣附在回覆上
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    id: Option<Value>,
+    #[serde(rename = "type")]
+    msg_type: String,
+    #[serde(default)]
+    data: Value,
 }
 
-async fn handle_set_session_mute(msg: Value) -> Option<Value> {
-    let data = msg.get("data")?;
-    let session_name = data.get("session_name")?.as_str()?.to_string();
-    let mute = data.get("mute")?.as_bool()?;
-
-    match crate::set_session_mute(session_name, mute) {
-        Ok(_) => Some(json!({
-            "type": "success",
-            "message": "靜音設定成功"
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
-    }
+/// 錯誤代碼，供客戶端判斷錯誤種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// 訊息不是合法的 JSON
+    InvalidJson,
+    /// 缺少欄位或欄位型別錯誤
+    InvalidRequest,
+    /// 未知的消息類型
+    UnknownType,
+    /// 未配對或配對已撤銷
+    Unauthorized,
+    /// 請求格式正確，但執行失敗
+    OperationFailed,
 }
 
-// === Audio Devices ===
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+}
 
-async fn handle_get_audio_devices() -> Option<Value> {
-    match crate::get_audio_devices() {
-        Ok(devices) => Some(json!({
-            "type": "audio_devices",
-            "data": devices
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
     }
 }
 
-async fn handle_set_default_device(msg: Value) -> Option<Value> {
-    let data = msg.get("data")?;
-    let device_id = data.get("device_id")?.as_str()?.to_string();
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorCode::OperationFailed, message)
+    }
+}
 
-    match crate::set_default_device(device_id) {
-        Ok(_) => Some(json!({
-            "type": "success",
-            "message": "預設裝置設定成功"
-        })),
-        Err(e) => Some(json!({
+/// 錯誤回覆，`message` 保留給舊版客戶端
+pub fn error_reply(error: ApiError, id: Option<Value>) -> Value {
+    with_id(
+        json!({
             "type": "error",
-            "message": e
-        })),
+            "message": error.message,
+            "error": error
+        }),
+        id,
+    )
+}
+
+fn with_id(mut reply: Value, id: Option<Value>) -> Value {
+    if let (Some(id), Some(reply)) = (id, reply.as_object_mut()) {
+        reply.insert("id".to_string(), id);
     }
+    reply
 }
 
-async fn handle_get_default_device_volume() -> Option<Value> {
-    match crate::get_default_device_volume() {
-        Ok(volume) => Some(json!({
-            "type": "default_device_volume",
-            "data": volume
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
+/// 以空的參數解析，參數裡沒有任何值，出現 unknown variant 就只能是類型本身不存在
+/// 不能直接看解析參數時的錯誤，參數中無效的列舉值也會產生同樣的訊息
+fn is_known_type(msg_type: &str) -> bool {
+    match serde_json::from_value::<Request>(json!({ "type": msg_type, "data": {} })) {
+        Ok(_) => true,
+        Err(e) => !e.to_string().starts_with("unknown variant"),
     }
 }
 
-async fn handle_set_default_device_volume(msg: Value) -> Option<Value> {
-    let data = msg.get("data")?;
-    let volume = data.get("volume")?.as_f64()? as f32;
+fn parse_request(msg: Value) -> Result<(Request, Option<Value>), (ApiError, Option<Value>)> {
+    let id = msg.get("id").cloned();
+    let envelope: Envelope = serde_json::from_value(msg).map_err(|e| {
+        (
+            ApiError::new(ErrorCode::InvalidRequest, format!("消息格式錯誤: {}", e)),
+            id.clone(),
+        )
+    })?;
 
-    match crate::set_default_device_volume(volume) {
-        Ok(_) => Some(json!({
-            "type": "success",
-            "message": "預設裝置音量設定成功"
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
+    let data = if envelope.data.is_null() {
+        json!({})
+    } else {
+        envelope.data
+    };
+
+    match serde_json::from_value(json!({ "type": envelope.msg_type, "data": data })) {
+        Ok(request) => Ok((request, envelope.id)),
+        Err(_) if !is_known_type(&envelope.msg_type) => Err((
+            ApiError::new(
+                ErrorCode::UnknownType,
+                format!("未知的消息類型: {}", envelope.msg_type),
+            ),
+            envelope.id,
+        )),
+        Err(e) => Err((
+            ApiError::new(
+                ErrorCode::InvalidRequest,
+                format!("{} 參數錯誤: {}", envelope.msg_type, e),
+            ),
+            envelope.id,
+        )),
     }
 }
 
-async fn handle_get_default_device_mute() -> Option<Value> {
-    match crate::get_default_device_mute() {
-        Ok(muted) => Some(json!({
-            "type": "default_device_mute",
-            "data": muted
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
+/// 處理一則客戶端訊息，每則訊息都會有一則回覆
+/// app_handle 用來把設定的變動寫入本地儲存
+pub async fn handle_message<R: tauri::Runtime>(
+    msg: Value,
+    access: Access,
+    subscriptions: &mut Subscriptions,
+    app_handle: Option<&tauri::AppHandle<R>>,
+) -> Value {
+    match parse_request(msg) {
+        Ok((request, id)) if access == Access::ReadOnly && !request.is_read_only() => {
+            error_reply(
+                ApiError::new(ErrorCode::Unauthorized, "尚未配對，請先在電腦上取得配對碼"),
+                id,
+            )
+        }
+        Ok((request, id)) => match dispatch(request, subscriptions, app_handle).await {
+            Ok(reply) => with_id(reply, id),
+            Err(error) => error_reply(error, id),
+        },
+        Err((error, id)) => error_reply(error, id),
     }
 }
 
-async fn handle_set_default_device_mute(msg: Value) -> Option<Value> {
-    let data = msg.get("data")?;
-    let mute = data.get("mute")?.as_bool()?;
+fn data<T: Serialize>(reply_type: &str, data: T) -> Result<Value, ApiError> {
+    Ok(json!({
+        "type": reply_type,
+        "data": data
+    }))
+}
 
-    match crate::set_default_device_mute(mute) {
-        Ok(_) => Some(json!({
-            "type": "success",
-            "message": "預設裝置靜音設定成功"
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
-    }
+fn success(message: &str) -> Result<Value, ApiError> {
+    Ok(json!({
+        "type": "success",
+        "message": message
+    }))
 }
 
-// === Media Control ===
+async fn dispatch<R: tauri::Runtime>(
+    request: Request,
+    subscriptions: &mut Subscriptions,
+    app_handle: Option<&tauri::AppHandle<R>>,
+) -> Result<Value, ApiError> {
+    match request {
+        // === Audio Sessions ===
+        Request::GetAudioSessions { grouped } => {
+            data("audio_sessions", crate::get_audio_sessions(grouped)?)
+        }
+        Request::SetSessionVolume { session_id, volume } => {
+            crate::set_session_volume(session_id, volume)?;
+            success("音量設定成功")
+        }
+        Request::SetSessionMute { session_id, mute } => {
+            crate::set_session_mute(session_id, mute)?;
+            success("靜音設定成功")
+        }
+        Request::SetSessionDevice {
+            session_id,
+            device_id,
+        } => {
+            crate::set_session_device(session_id, device_id)?;
+            success("輸出裝置設定成功")
+        }
+        Request::GetAppRoutes {} => data("app_routes", crate::get_app_routes()),
+        Request::ClearAppRoute { group } => {
+            crate::forget_route(&group)?;
+            if let Some(app_handle) = app_handle {
+                crate::save_app_routes(app_handle)?;
+            }
+            success("已清除程式的輸出裝置")
+        }
 
-async fn handle_get_all_media_sessions() -> Option<Value> {
-    match crate::get_all_media_sessions() {
-        Ok(sessions) => Some(json!({
-            "type": "all_media_sessions",
-            "data": sessions
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
+        // === Audio Devices ===
+        Request::GetAudioDevices {
+            direction,
+            include_inactive,
+        } => data(
+            "audio_devices",
+            crate::get_audio_devices(direction, include_inactive)?,
+        ),
+        Request::SetDefaultDevice { device_id, roles } => {
+            crate::set_default_device(device_id, roles)?;
+            success("預設裝置設定成功")
+        }
+        Request::GetDefaultDeviceVolume { direction } => data(
+            "default_device_volume",
+            crate::get_default_device_volume(direction)?,
+        ),
+        Request::SetDefaultDeviceVolume { volume, direction } => {
+            crate::set_default_device_volume(volume, direction)?;
+            success("預設裝置音量設定成功")
+        }
+        Request::GetDefaultDeviceMute { direction } => data(
+            "default_device_mute",
+            crate::get_default_device_mute(direction)?,
+        ),
+        Request::SetDefaultDeviceMute { mute, direction } => {
+            crate::set_default_device_mute(mute, direction)?;
+            success("預設裝置靜音設定成功")
+        }
+        Request::ToggleMicrophoneMute {} => {
+            data("microphone_mute", crate::toggle_microphone_mute()?)
+        }
+        Request::GetDeviceVolume { device_id } => {
+            data("device_volume", crate::get_device_volume(device_id)?)
+        }
+        Request::SetDeviceVolume { device_id, volume } => {
+            crate::set_device_volume(device_id, volume)?;
+            success("裝置音量設定成功")
+        }
+        Request::GetDeviceMute { device_id } => {
+            data("device_mute", crate::get_device_mute(device_id)?)
+        }
+        Request::SetDeviceMute { device_id, mute } => {
+            crate::set_device_mute(device_id, mute)?;
+            success("裝置靜音設定成功")
+        }
+
+        // === Media Control ===
+        Request::GetAllMediaSessions {} => {
+            data("all_media_sessions", crate::get_all_media_sessions()?)
+        }
+        Request::GetMediaInfo {} => data("media_info", crate::get_media_info()?),
+        Request::GetMediaThumbnail { session_id } => {
+            data("media_thumbnail", crate::get_media_thumbnail(session_id)?)
+        }
+        Request::MediaPlayPause { session_id } => {
+            crate::media_play_pause(session_id)?;
+            success("播放/暫停成功")
+        }
+        Request::MediaPlay { session_id } => {
+            crate::media_play(session_id)?;
+            success("播放成功")
+        }
+        Request::MediaPause { session_id } => {
+            crate::media_pause(session_id)?;
+            success("暫停成功")
+        }
+        Request::MediaStop { session_id } => {
+            crate::media_stop(session_id)?;
+            success("停止成功")
+        }
+        Request::MediaNext { session_id } => {
+            crate::media_next(session_id)?;
+            success("下一首成功")
+        }
+        Request::MediaPrevious { session_id } => {
+            crate::media_previous(session_id)?;
+            success("上一首成功")
+        }
+        Request::MediaSeek {
+            session_id,
+            position_ms,
+        } => {
+            crate::media_seek(session_id, position_ms)?;
+            success("已調整播放位置")
+        }
+        Request::MediaSetShuffle {
+            session_id,
+            shuffle,
+        } => {
+            crate::media_set_shuffle(session_id, shuffle)?;
+            success("隨機播放設定成功")
+        }
+        Request::MediaSetRepeat { session_id, repeat } => {
+            crate::media_set_repeat(session_id, repeat)?;
+            success("重複播放設定成功")
+        }
+        Request::MediaSetRate { session_id, rate } => {
+            crate::media_set_rate(session_id, rate)?;
+            success("播放速度設定成功")
+        }
+
+        // === Profiles ===
+        Request::GetProfiles {} => data("profiles", crate::get_audio_profiles()),
+        Request::ApplyProfile { name } => {
+            data("profile_applied", crate::apply_audio_profile(name)?)
+        }
+
+        // === Rules ===
+        Request::GetRules {} => data("rules", crate::get_rules()),
+        Request::GetRuleLog {} => data("rule_log", crate::get_rule_log()),
+
+        // === Volume Memory ===
+        Request::GetVolumeMemory {} => data("volume_memory", crate::get_volume_memory()),
+        Request::ClearVolumeMemory { group } => {
+            // 沒有 AppHandle，由音訊監聽循環寫入儲存
+            crate::forget(group.as_deref())?;
+            success("已清除記住的音量")
+        }
+
+        // === Levels ===
+        Request::SubscribeLevels { interval_ms } => {
+            let interval = crate::levels_interval(interval_ms);
+            subscriptions.levels = Some(interval);
+            data(
+                "levels_subscribed",
+                json!({ "interval_ms": interval.as_millis() as u64 }),
+            )
+        }
+        Request::UnsubscribeLevels {} => {
+            subscriptions.levels = None;
+            success("已取消訂閱音量峰值")
+        }
     }
 }
 
-async fn handle_get_media_info() -> Option<Value> {
-    match crate::get_media_info() {
-        Ok(info) => Some(json!({
-            "type": "media_info",
-            "data": info
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
+#[cfg(test)]
+mod tests {
+    use super::{Access, Subscriptions};
+    use crate::audio::mock::MockAudioBackend;
+    use crate::media::mock::MockMediaBackend;
+    use crate::models::RepeatMode;
+    use crate::utils::backend_test_lock;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    async fn handle_message(msg: Value) -> Value {
+        super::handle_message(
+            msg,
+            Access::Full,
+            &mut Subscriptions::default(),
+            None::<&tauri::AppHandle>,
+        )
+        .await
+    }
+
+    fn install() -> (Arc<MockAudioBackend>, Arc<MockMediaBackend>) {
+        let audio = Arc::new(MockAudioBackend::fixture());
+        let media = Arc::new(MockMediaBackend::fixture());
+        crate::set_audio_backend(audio.clone());
+        crate::set_media_backend(media.clone());
+        (audio, media)
     }
-}
 
-async fn handle_get_media_thumbnail(msg: Value) -> Option<Value> {
-    let data = msg.get("data");
-    let session_id = data.and_then(|d| d.get("session_id")).and_then(|s| s.as_str()).map(|s| s.to_string());
+    #[tokio::test]
+    async fn returns_devices_and_sessions() {
+        let _lock = backend_test_lock().await;
+        install();
 
-    match crate::get_media_thumbnail(session_id) {
-        Ok(thumbnail) => Some(json!({
-            "type": "media_thumbnail",
-            "data": thumbnail
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
+        let reply = handle_message(json!({ "type": "get_audio_devices" }))
+            .await;
+        assert_eq!(reply["type"], "audio_devices");
+        assert_eq!(reply["data"][0]["id"], "speakers");
+        assert_eq!(reply["data"][0]["is_default"], true);
+
+        let reply = handle_message(json!({ "type": "get_audio_sessions" }))
+            .await;
+        assert_eq!(reply["type"], "audio_sessions");
+        assert_eq!(reply["data"].as_array().unwrap().len(), 3);
+        assert_eq!(reply["data"][1]["pid"], 200);
+        assert_eq!(reply["data"][1]["group"], reply["data"][2]["group"]);
     }
-}
 
-fn handle_media_play_pause(msg: Value) -> Option<Value> {
-    let data = msg.get("data");
-    let session_id = data.and_then(|d| d.get("session_id")).and_then(|s| s.as_str()).map(|s| s.to_string());
+    #[tokio::test]
+    async fn unplugged_devices_are_listed_only_when_requested() {
+        let _lock = backend_test_lock().await;
+        install();
 
-    match crate::media_play_pause(session_id) {
-        Ok(_) => Some(json!({
-            "type": "success",
-            "message": "播放/暫停成功"
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
+        let reply = handle_message(json!({ "type": "get_audio_devices" })).await;
+        let devices = reply["data"].as_array().unwrap();
+        assert_eq!(devices.len(), 2);
+        assert!(devices.iter().all(|d| d["state"] == "active"));
+        assert_eq!(devices[1]["form_factor"], "headset");
+
+        let reply = handle_message(json!({
+            "type": "get_audio_devices",
+            "data": { "include_inactive": true }
+        }))
+        .await;
+        let devices = reply["data"].as_array().unwrap();
+        assert_eq!(devices.len(), 3);
+        assert_eq!(devices[2]["id"], "headphones");
+        assert_eq!(devices[2]["state"], "unplugged");
+        assert_eq!(devices[2]["jack_connected"], false);
     }
-}
 
-fn handle_media_next(msg: Value) -> Option<Value> {
-    let data = msg.get("data");
-    let session_id = data.and_then(|d| d.get("session_id")).and_then(|s| s.as_str()).map(|s| s.to_string());
+    #[tokio::test]
+    async fn grouped_view_merges_instances() {
+        let _lock = backend_test_lock().await;
+        install();
 
-    match crate::media_next(session_id) {
-        Ok(_) => Some(json!({
-            "type": "success",
-            "message": "下一首成功"
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
+        let reply = handle_message(json!({ "type": "get_audio_sessions", "data": { "grouped": true } }))
+            .await;
+        let sessions = reply["data"].as_array().unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[1]["name"], "chrome.exe");
+        assert_eq!(sessions[1]["id"], sessions[1]["group"]);
     }
-}
 
-fn handle_media_previous(msg: Value) -> Option<Value> {
-    let data = msg.get("data");
-    let session_id = data.and_then(|d| d.get("session_id")).and_then(|s| s.as_str()).map(|s| s.to_string());
+    #[tokio::test]
+    async fn session_commands_target_one_instance_or_a_group() {
+        let _lock = backend_test_lock().await;
+        let (audio, _) = install();
 
-    match crate::media_previous(session_id) {
-        Ok(_) => Some(json!({
-            "type": "success",
-            "message": "上一首成功"
-        })),
-        Err(e) => Some(json!({
-            "type": "error",
-            "message": e
-        })),
+        handle_message(json!({
+            "type": "set_session_volume",
+            "data": { "session_id": "chrome-2", "volume": 0.1 }
+        }))
+        .await;
+        let state = audio.state();
+        assert_eq!(state.sessions[1].volume, 1.0);
+        assert_eq!(state.sessions[2].volume, 0.1);
+
+        let group = state.sessions[1].group.clone();
+        handle_message(json!({
+            "type": "set_session_mute",
+            "data": { "session_id": group, "mute": true }
+        }))
+        .await;
+        let state = audio.state();
+        assert!(!state.sessions[0].is_muted);
+        assert!(state.sessions[1].is_muted && state.sessions[2].is_muted);
+    }
+
+    #[tokio::test]
+    async fn set_commands_reach_the_backend() {
+        let _lock = backend_test_lock().await;
+        let (audio, _) = install();
+
+        let reply = handle_message(json!({
+            "type": "set_session_volume",
+            "data": { "session_name": "Spotify.exe", "volume": 0.25 }
+        }))
+        .await;
+        assert_eq!(reply["type"], "success");
+
+        handle_message(json!({ "type": "set_default_device", "data": { "device_id": "headset" } }))
+            .await;
+        handle_message(json!({ "type": "set_default_device_mute", "data": { "mute": true } }))
+            .await;
+
+        let state = audio.state();
+        assert_eq!(state.sessions[0].volume, 0.25);
+        assert!(state.devices[1].is_default);
+        assert!(!state.devices[0].is_default);
+        assert!(state.default_mute);
+    }
+
+    #[tokio::test]
+    async fn default_device_roles_are_set_independently() {
+        let _lock = backend_test_lock().await;
+        install();
+
+        handle_message(json!({
+            "type": "set_default_device",
+            "data": { "device_id": "headset", "roles": ["communications"] }
+        }))
+        .await;
+
+        let reply = handle_message(json!({ "type": "get_audio_devices" })).await;
+        assert_eq!(reply["data"][0]["is_default"], true);
+        assert_eq!(reply["data"][0]["default_roles"], json!(["console", "multimedia"]));
+        assert_eq!(reply["data"][1]["is_default"], false);
+        assert_eq!(reply["data"][1]["default_roles"], json!(["communications"]));
+
+        // 未指定角色時與舊版相同，設定一般與通訊
+        handle_message(json!({ "type": "set_default_device", "data": { "device_id": "headset" } }))
+            .await;
+
+        let reply = handle_message(json!({ "type": "get_audio_devices" })).await;
+        assert_eq!(reply["data"][0]["default_roles"], json!(["multimedia"]));
+        assert_eq!(reply["data"][1]["default_roles"], json!(["console", "communications"]));
+
+        let reply = handle_message(json!({
+            "type": "set_default_device",
+            "data": { "device_id": "headset", "roles": [] }
+        }))
+        .await;
+        assert_eq!(reply["type"], "error");
+    }
+
+    #[tokio::test]
+    async fn any_device_volume_can_be_changed_without_making_it_default() {
+        let _lock = backend_test_lock().await;
+        let (audio, _) = install();
+
+        handle_message(json!({
+            "type": "set_device_volume",
+            "data": { "device_id": "headset", "volume": 0.3 }
+        }))
+        .await;
+        handle_message(json!({
+            "type": "set_device_mute",
+            "data": { "device_id": "speakers", "mute": true }
+        }))
+        .await;
+
+        let reply = handle_message(json!({
+            "type": "get_device_volume",
+            "data": { "device_id": "headset" }
+        }))
+        .await;
+        assert_eq!(reply["type"], "device_volume");
+        assert!((reply["data"].as_f64().unwrap() - 0.3).abs() < 1e-6);
+
+        let state = audio.state();
+        assert!(!state.devices[1].is_default);
+        assert_eq!(state.default_volume, 0.5);
+        assert!(state.default_mute);
+
+        let reply = handle_message(json!({
+            "type": "get_device_mute",
+            "data": { "device_id": "gone" }
+        }))
+        .await;
+        assert_eq!(reply["type"], "error");
+    }
+
+    #[tokio::test]
+    async fn microphone_is_controlled_separately_from_output() {
+        let _lock = backend_test_lock().await;
+        let (audio, _) = install();
+
+        let reply = handle_message(json!({
+            "type": "get_audio_devices",
+            "data": { "direction": "capture" }
+        }))
+        .await;
+        let devices = reply["data"].as_array().unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0]["id"], "microphone");
+        assert_eq!(devices[0]["direction"], "capture");
+
+        handle_message(json!({
+            "type": "set_default_device_volume",
+            "data": { "volume": 0.2, "direction": "capture" }
+        }))
+        .await;
+        let reply = handle_message(json!({ "type": "toggle_microphone_mute" })).await;
+        assert_eq!(reply["type"], "microphone_mute");
+        assert_eq!(reply["data"], true);
+
+        let state = audio.state();
+        assert!((state.capture_volume - 0.2).abs() < 1e-6);
+        assert!(state.capture_mute);
+        assert_eq!(state.default_volume, 0.5);
+        assert!(!state.default_mute);
+    }
+
+    #[tokio::test]
+    async fn reflects_external_state_changes() {
+        let _lock = backend_test_lock().await;
+        let (audio, _) = install();
+
+        audio.update(|state| state.default_volume = 0.9);
+
+        let reply = handle_message(json!({ "type": "get_default_device_volume" }))
+            .await;
+        assert_eq!(reply["type"], "default_device_volume");
+        assert!((reply["data"].as_f64().unwrap() - 0.9).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn backend_errors_become_error_replies() {
+        let _lock = backend_test_lock().await;
+        let (audio, _) = install();
+
+        let reply = handle_message(json!({
+            "type": "set_session_mute",
+            "data": { "session_name": "missing.exe", "mute": true }
+        }))
+        .await;
+        assert_eq!(reply["type"], "error");
+
+        audio.update(|state| state.fail_next = Some("裝置已移除".to_string()));
+        let reply = handle_message(json!({ "type": "get_audio_devices" }))
+            .await;
+        assert_eq!(reply["type"], "error");
+        assert_eq!(reply["message"], "裝置已移除");
+
+        let reply = handle_message(json!({ "type": "unknown" })).await;
+        assert_eq!(reply["type"], "error");
+    }
+
+    #[tokio::test]
+    async fn media_controls_target_the_requested_player() {
+        let _lock = backend_test_lock().await;
+        let (_, media) = install();
+
+        let reply = handle_message(json!({ "type": "get_all_media_sessions" }))
+            .await;
+        assert_eq!(reply["data"][0]["title"], "Song");
+
+        handle_message(json!({ "type": "media_play_pause", "data": { "session_id": "spotify" } }))
+            .await;
+        handle_message(json!({ "type": "media_next" })).await;
+
+        let reply = handle_message(json!({ "type": "media_previous", "data": { "session_id": "gone" } }))
+            .await;
+        assert_eq!(reply["type"], "error");
+
+        let state = media.state();
+        assert!(!state.players[0].is_playing);
+        assert_eq!(state.commands, vec!["play_pause:spotify", "next:spotify"]);
+
+        let reply = handle_message(json!({ "type": "get_media_thumbnail", "data": { "session_id": "spotify" } }))
+            .await;
+        assert_eq!(reply["data"], "UE5HREFUQQ==");
+    }
+
+    #[tokio::test]
+    async fn commands_for_a_closed_player_are_not_redirected() {
+        let _lock = backend_test_lock().await;
+        let (_, media) = install();
+
+        media.update(|state| {
+            let mut vlc = state.players[0].clone();
+            vlc.session_id = "vlc".to_string();
+            state.players.insert(0, vlc);
+        });
+        handle_message(json!({ "type": "media_next", "data": { "session_id": "vlc" } })).await;
+
+        // vlc 關閉後 spotify 排到第一個，原本的 ID 不會指到 spotify
+        media.update(|state| state.players.retain(|p| p.session_id != "vlc"));
+        let reply =
+            handle_message(json!({ "type": "media_next", "data": { "session_id": "vlc" } })).await;
+        assert_eq!(reply["message"], "媒體會話已結束: vlc");
+        assert_eq!(media.state().commands, ["next:vlc"]);
+    }
+
+    #[tokio::test]
+    async fn seeking_moves_the_reported_position() {
+        let _lock = backend_test_lock().await;
+        let (_, media) = install();
+
+        let reply = handle_message(json!({ "type": "get_all_media_sessions" })).await;
+        assert_eq!(reply["data"][0]["end_time_ms"], 180_000);
+        assert_eq!(reply["data"][0]["can_seek"], true);
+
+        handle_message(json!({
+            "type": "media_seek",
+            "data": { "session_id": "spotify", "position_ms": 95_000 }
+        }))
+        .await;
+        assert_eq!(media.state().players[0].position_ms, Some(95_000));
+
+        media.update(|state| state.players[0].can_seek = false);
+        let reply =
+            handle_message(json!({ "type": "media_seek", "data": { "position_ms": 0 } })).await;
+        assert_eq!(reply["type"], "error");
+    }
+
+    #[tokio::test]
+    async fn playback_modes_are_reported_and_changed() {
+        let _lock = backend_test_lock().await;
+        let (_, media) = install();
+
+        let reply = handle_message(json!({ "type": "get_media_info" })).await;
+        assert_eq!(reply["data"]["repeat"], "none");
+        assert_eq!(reply["data"]["can_stop"], true);
+
+        handle_message(json!({ "type": "media_pause" })).await;
+        handle_message(json!({ "type": "media_set_shuffle", "data": { "shuffle": true } })).await;
+        handle_message(json!({ "type": "media_set_repeat", "data": { "repeat": "track" } })).await;
+
+        let player = media.state().players[0].clone();
+        assert!(!player.is_playing);
+        assert_eq!(player.shuffle, Some(true));
+        assert_eq!(player.repeat, Some(RepeatMode::Track));
+
+        // 播放器不支援調整速度，無效的速度不會送到播放器
+        let reply =
+            handle_message(json!({ "type": "media_set_rate", "data": { "rate": 1.5 } })).await;
+        assert_eq!(reply["type"], "error");
+        let reply =
+            handle_message(json!({ "type": "media_set_rate", "data": { "rate": 0 } })).await;
+        assert_eq!(reply["type"], "error");
+
+        assert_eq!(
+            media.state().commands,
+            [
+                "pause:spotify",
+                "shuffle:spotify",
+                "repeat:spotify",
+                "rate:spotify"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn replies_echo_the_request_id() {
+        let _lock = backend_test_lock().await;
+        install();
+
+        let reply = handle_message(json!({ "id": 7, "type": "get_default_device_mute" })).await;
+        assert_eq!(reply["type"], "default_device_mute");
+        assert_eq!(reply["id"], 7);
+
+        let reply = handle_message(json!({
+            "id": "a1",
+            "type": "set_session_volume",
+            "data": { "session_id": "spotify-1", "volume": 0.5 }
+        }))
+        .await;
+        assert_eq!(reply["type"], "success");
+        assert_eq!(reply["id"], "a1");
+
+        let reply = handle_message(json!({ "type": "get_audio_devices" })).await;
+        assert!(reply.get("id").is_none());
+    }
+
+    #[tokio::test]
+    async fn malformed_requests_get_coded_errors() {
+        let _lock = backend_test_lock().await;
+        install();
+
+        let reply = handle_message(json!({
+            "id": 1,
+            "type": "set_session_volume",
+            "data": { "session_id": "spotify-1", "volume": "loud" }
+        }))
+        .await;
+        assert_eq!(reply["id"], 1);
+        assert_eq!(reply["error"]["code"], "invalid_request");
+
+        let reply = handle_message(json!({ "id": 2, "type": "set_default_device_mute" })).await;
+        assert_eq!(reply["error"]["code"], "invalid_request");
+
+        let repeat = json!({ "type": "media_set_repeat", "data": { "repeat": "bogus" } });
+        let reply = handle_message(repeat).await;
+        assert_eq!(reply["error"]["code"], "invalid_request");
+
+        let reply = handle_message(json!({ "id": 3, "type": "unknown" })).await;
+        assert_eq!(reply["error"]["code"], "unknown_type");
+
+        let reply = handle_message(json!({ "id": 4 })).await;
+        assert_eq!(reply["id"], 4);
+        assert_eq!(reply["error"]["code"], "invalid_request");
+
+        let reply = handle_message(json!({ "id": 5, "type": "set_default_device", "data": { "device_id": "gone" } })).await;
+        assert_eq!(reply["error"]["code"], "operation_failed");
+        assert_eq!(reply["message"], reply["error"]["message"]);
+    }
+
+    #[tokio::test]
+    async fn read_only_clients_cannot_change_state() {
+        let _lock = backend_test_lock().await;
+        let (audio, _) = install();
+        let mut subscriptions = Subscriptions::default();
+
+        let reply = super::handle_message(
+            json!({ "type": "get_audio_devices" }),
+            Access::ReadOnly,
+            &mut subscriptions,
+            None::<&tauri::AppHandle>,
+        )
+        .await;
+        assert_eq!(reply["type"], "audio_devices");
+
+        let reply = super::handle_message(
+            json!({ "id": 9, "type": "set_default_device_mute", "data": { "mute": true } }),
+            Access::ReadOnly,
+            &mut subscriptions,
+            None::<&tauri::AppHandle>,
+        )
+        .await;
+        assert_eq!(reply["id"], 9);
+        assert_eq!(reply["error"]["code"], "unauthorized");
+        assert!(!audio.state().default_mute);
     }
 }
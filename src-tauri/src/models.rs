@@ -1,20 +1,146 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AudioSession {
+    /// 會話的唯一識別碼 (WASAPI session instance identifier)，分組檢視時為分組鍵
+    pub id: String,
+    /// 顯示名稱，通常為執行檔名稱
     pub name: String,
+    pub pid: u32,
+    /// 執行檔完整路徑，無法取得時為空字串
+    pub path: String,
+    /// 分組鍵，同一個程式的多個實例會有相同的值
+    pub group: String,
     pub volume: f32,
     pub is_muted: bool,
+    /// 會話目前是否正在輸出聲音
+    #[serde(default)]
+    pub is_active: bool,
+    /// 會話目前使用的輸出裝置 ID，無法取得時為 None
+    #[serde(default)]
+    pub device_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// 裝置的資料流方向
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceDirection {
+    /// 輸出裝置，例如喇叭與耳機
+    #[default]
+    Render,
+    /// 輸入裝置，例如麥克風
+    Capture,
+}
+
+/// 預設裝置的角色，Windows 可以為每個角色指定不同的裝置
+/// Linux 只有一個預設裝置，同時擔任所有角色
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceRole {
+    /// 一般用途與系統音效
+    Console,
+    /// 音樂與影片
+    Multimedia,
+    /// 語音通話
+    Communications,
+}
+
+impl DeviceRole {
+    pub const ALL: [DeviceRole; 3] = [
+        DeviceRole::Console,
+        DeviceRole::Multimedia,
+        DeviceRole::Communications,
+    ];
+}
+
+/// 裝置的狀態
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceState {
+    #[default]
+    Active,
+    /// 在系統設定中被停用
+    Disabled,
+    /// 裝置已移除，例如拔掉的 USB 耳機
+    NotPresent,
+    /// 插孔沒有接上
+    Unplugged,
+}
+
+/// 裝置的外型，供介面顯示對應的圖示
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FormFactor {
+    Speakers,
+    Headphones,
+    /// 附麥克風的耳機
+    Headset,
+    /// 電話聽筒
+    Handset,
+    Microphone,
+    /// 類比訊號線輸出入
+    LineLevel,
+    /// HDMI 或 DisplayPort 螢幕
+    Hdmi,
+    /// S/PDIF 數位輸出入
+    Spdif,
+    /// 網路或遠端桌面的裝置
+    Network,
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct AudioDevice {
     pub id: String,
     pub name: String,
+    /// 是否為一般用途 (console) 的預設裝置
     pub is_default: bool,
+    #[serde(default)]
+    pub direction: DeviceDirection,
+    /// 此裝置是哪些角色的預設裝置
+    #[serde(default)]
+    pub default_roles: Vec<DeviceRole>,
+    /// 只有要求列出非啟用中的裝置時才會出現 active 以外的狀態
+    #[serde(default)]
+    pub state: DeviceState,
+    /// 音效卡或介面的名稱，例如 "Realtek High Definition Audio"
+    #[serde(default)]
+    pub interface_name: Option<String>,
+    #[serde(default)]
+    pub form_factor: FormFactor,
+    /// 插孔是否接上，裝置不支援插孔偵測時為 None
+    #[serde(default)]
+    pub jack_connected: Option<bool>,
+    /// 混音格式的聲道數，非啟用中的裝置可能無法取得
+    #[serde(default)]
+    pub channels: Option<u16>,
+    /// 混音格式的取樣率 (Hz)
+    #[serde(default)]
+    pub sample_rate: Option<u32>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// 音量峰值 (0.0 ~ 1.0)，分別以裝置 ID 與會話 ID 為索引
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct AudioLevels {
+    pub devices: HashMap<String, f32>,
+    pub sessions: HashMap<String, f32>,
+}
+
+/// 重複播放模式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatMode {
+    #[default]
+    None,
+    /// 重複目前的曲目
+    Track,
+    /// 重複整個播放清單
+    List,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct MediaInfo {
     pub session_id: String,
     pub app_name: String,
@@ -22,7 +148,59 @@ pub struct MediaInfo {
     pub artist: String,
     pub album: String,
     pub is_playing: bool,
-    pub thumbnail: Option<String>,
+    /// 專輯封面在 HTTP 服務上的路徑，播放器沒有提供封面時為 None
+    #[serde(default)]
+    pub artwork_url: Option<String>,
+    /// 以曲目資訊計算的雜湊，換歌時才會改變，與 HTTP 的 ETag 無關
+    #[serde(default)]
+    pub artwork_hash: Option<String>,
     pub can_go_next: bool,
     pub can_go_previous: bool,
+    /// 播放進度 (毫秒)，為播放器在 timeline_updated_at 回報的位置
+    /// 播放中時實際位置需加上之後經過的時間
+    #[serde(default)]
+    pub position_ms: Option<u64>,
+    #[serde(default)]
+    pub start_time_ms: Option<u64>,
+    /// 結束時間 (毫秒)，通常等於曲目長度
+    #[serde(default)]
+    pub end_time_ms: Option<u64>,
+    /// 播放器回報進度的時間 (Unix 毫秒)
+    #[serde(default)]
+    pub timeline_updated_at: Option<u64>,
+    /// 是否可以跳到指定位置
+    #[serde(default)]
+    pub can_seek: bool,
+    /// 是否隨機播放，播放器沒有提供時為 None
+    #[serde(default)]
+    pub shuffle: Option<bool>,
+    #[serde(default)]
+    pub repeat: Option<RepeatMode>,
+    /// 播放速度，1.0 為正常速度
+    #[serde(default)]
+    pub playback_rate: Option<f64>,
+    #[serde(default)]
+    pub can_play: bool,
+    #[serde(default)]
+    pub can_pause: bool,
+    #[serde(default)]
+    pub can_stop: bool,
+    #[serde(default)]
+    pub can_play_pause_toggle: bool,
+    #[serde(default)]
+    pub can_record: bool,
+    #[serde(default)]
+    pub can_fast_forward: bool,
+    #[serde(default)]
+    pub can_rewind: bool,
+    #[serde(default)]
+    pub can_channel_up: bool,
+    #[serde(default)]
+    pub can_channel_down: bool,
+    #[serde(default)]
+    pub can_shuffle: bool,
+    #[serde(default)]
+    pub can_repeat: bool,
+    #[serde(default)]
+    pub can_change_rate: bool,
 }
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 配對碼有效時間
+const CODE_TTL: Duration = Duration::from_secs(300);
+
+/// 配對碼允許輸入錯誤的次數，超過後需要重新產生
+const MAX_ATTEMPTS: u32 = 5;
+
+const STORE_KEY: &str = "paired_devices";
+
+/// 已配對的裝置，token 只保存在本機
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairedDevice {
+    pub id: String,
+    pub name: String,
+    pub token: String,
+    /// 配對時間 (Unix 秒)
+    pub paired_at: u64,
+}
+
+/// 顯示在桌面介面的配對裝置資訊
+#[derive(Debug, Clone, Serialize)]
+pub struct PairedDeviceInfo {
+    pub id: String,
+    pub name: String,
+    pub paired_at: u64,
+}
+
+/// 桌面介面顯示給使用者輸入的配對碼
+#[derive(Debug, Clone, Serialize)]
+pub struct PairingCode {
+    pub code: String,
+    /// 剩餘有效秒數
+    pub expires_in: u64,
+}
+
+struct PendingCode {
+    code: String,
+    expires_at: Instant,
+    attempts: u32,
+}
+
+lazy_static::lazy_static! {
+    static ref PAIRED_DEVICES: RwLock<Vec<PairedDevice>> = RwLock::new(Vec::new());
+    static ref PENDING_CODE: Mutex<Option<PendingCode>> = Mutex::new(None);
+}
+
+/// 產生新的 6 位數配對碼，舊的配對碼立即失效
+pub fn new_pairing_code() -> PairingCode {
+    let code = format!("{:06}", uuid::Uuid::new_v4().as_u128() % 1_000_000);
+
+    *PENDING_CODE.lock().unwrap() = Some(PendingCode {
+        code: code.clone(),
+        expires_at: Instant::now() + CODE_TTL,
+        attempts: 0,
+    });
+
+    PairingCode {
+        code,
+        expires_in: CODE_TTL.as_secs(),
+    }
+}
+
+/// 以配對碼換取 token，成功後配對碼失效
+pub fn pair(code: &str, name: &str) -> Result<PairedDevice, String> {
+    let mut pending = PENDING_CODE.lock().unwrap();
+
+    let current = match pending.as_mut() {
+        Some(current) if current.expires_at > Instant::now() => current,
+        _ => {
+            *pending = None;
+            return Err("配對碼已過期，請在電腦上重新產生".to_string());
+        }
+    };
+
+    if current.code != code.trim() {
+        current.attempts += 1;
+        if current.attempts >= MAX_ATTEMPTS {
+            *pending = None;
+            return Err("配對碼錯誤次數過多，請在電腦上重新產生".to_string());
+        }
+        return Err("配對碼錯誤".to_string());
+    }
+
+    *pending = None;
+
+    let device = PairedDevice {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        token: format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        ),
+        paired_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+
+    PAIRED_DEVICES.write().unwrap().push(device.clone());
+
+    Ok(device)
+}
+
+/// 檢查 token 是否屬於尚未撤銷的配對裝置
+pub fn is_token_valid(token: &str) -> bool {
+    !token.is_empty()
+        && PAIRED_DEVICES
+            .read()
+            .unwrap()
+            .iter()
+            .any(|device| device.token == token)
+}
+
+pub fn paired_devices() -> Vec<PairedDeviceInfo> {
+    PAIRED_DEVICES
+        .read()
+        .unwrap()
+        .iter()
+        .map(|device| PairedDeviceInfo {
+            id: device.id.clone(),
+            name: device.name.clone(),
+            paired_at: device.paired_at,
+        })
+        .collect()
+}
+
+/// 撤銷配對，該裝置之後只能唯讀
+pub fn revoke(device_id: &str) -> Result<(), String> {
+    let mut devices = PAIRED_DEVICES.write().unwrap();
+    let before = devices.len();
+    devices.retain(|device| device.id != device_id);
+
+    if devices.len() == before {
+        return Err(format!("找不到配對裝置: '{}'", device_id));
+    }
+
+    Ok(())
+}
+
+/// 從本地儲存載入已配對的裝置，應在啟動時呼叫
+pub fn load_paired_devices<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    use tauri_plugin_store::StoreExt;
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+    let devices = store
+        .get(STORE_KEY)
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+
+    *PAIRED_DEVICES.write().unwrap() = devices;
+
+    Ok(())
+}
+
+/// 儲存已配對的裝置到本地
+pub fn save_paired_devices<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    use tauri_plugin_store::StoreExt;
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+    store.set(
+        STORE_KEY,
+        serde_json::json!(*PAIRED_DEVICES.read().unwrap()),
+    );
+
+    store.save().map_err(|e| format!("無法保存設定: {:?}", e))?;
+
+    Ok(())
+}
+
+/// 產生配對碼，顯示在桌面介面讓手機輸入
+#[tauri::command]
+pub fn create_pairing_code() -> PairingCode {
+    new_pairing_code()
+}
+
+/// 獲取所有已配對的裝置
+#[tauri::command]
+pub fn get_paired_devices() -> Vec<PairedDeviceInfo> {
+    paired_devices()
+}
+
+/// 撤銷已配對的裝置
+#[tauri::command]
+pub fn revoke_paired_device(app: tauri::AppHandle, device_id: String) -> Result<(), String> {
+    revoke(&device_id)?;
+    save_paired_devices(&app)
+}
@@ -0,0 +1,330 @@
+use crate::audio::{audio_backend, DEFAULT_DEVICE_ROLES};
+use crate::models::{AudioSession, DeviceDirection};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, RwLock};
+
+const STORE_KEY: &str = "profiles";
+
+/// 設定檔中單一程式的音量，以分組鍵比對，重新啟動程式後仍然有效
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileSession {
+    pub group: String,
+    pub name: String,
+    pub volume: f32,
+    pub is_muted: bool,
+}
+
+/// 整個混音器的快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioProfile {
+    pub name: String,
+    /// 預設輸出裝置，沒有裝置時為 None
+    pub default_device: Option<String>,
+    pub volume: f32,
+    pub is_muted: bool,
+    pub sessions: Vec<ProfileSession>,
+    /// 套用此設定檔的全域快捷鍵
+    #[serde(default)]
+    pub hotkey: Option<String>,
+}
+
+/// 套用設定檔的結果
+#[derive(Debug, Clone, Serialize)]
+pub struct ApplyProfileResult {
+    pub profile: String,
+    /// 目前沒有在執行、無法套用的程式名稱
+    pub unmatched_sessions: Vec<String>,
+}
+
+lazy_static::lazy_static! {
+    static ref PROFILES: RwLock<Vec<AudioProfile>> = RwLock::new(Vec::new());
+    /// 避免兩個設定檔同時套用造成混合的狀態
+    static ref APPLY_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// 擷取目前的混音器狀態，同一個程式的多個會話只記錄一筆
+pub fn snapshot_profile(name: &str) -> Result<AudioProfile, String> {
+    let backend = audio_backend();
+
+    let default_device = backend
+        .get_devices(DeviceDirection::Render, false)?
+        .into_iter()
+        .find(|device| device.is_default)
+        .map(|device| device.id);
+
+    let mut sessions: Vec<ProfileSession> = Vec::new();
+    for session in backend.get_sessions()? {
+        if sessions.iter().any(|s| s.group == session.group) {
+            continue;
+        }
+        sessions.push(ProfileSession {
+            group: session.group,
+            name: session.name,
+            volume: session.volume,
+            is_muted: session.is_muted,
+        });
+    }
+
+    Ok(AudioProfile {
+        name: name.to_string(),
+        default_device,
+        volume: backend.get_default_device_volume(DeviceDirection::Render)?,
+        is_muted: backend.get_default_device_mute(DeviceDirection::Render)?,
+        sessions,
+        hotkey: None,
+    })
+}
+
+/// 找出設定檔中每個程式目前對應的會話 ID
+/// 先以分組鍵比對，找不到時以名稱比對
+fn match_sessions<'a>(
+    profile: &'a AudioProfile,
+    current: &[AudioSession],
+) -> (Vec<(&'a ProfileSession, Vec<String>)>, Vec<String>) {
+    let mut matched = Vec::new();
+    let mut unmatched = Vec::new();
+
+    for saved in &profile.sessions {
+        let mut ids: Vec<String> = current
+            .iter()
+            .filter(|s| s.group == saved.group)
+            .map(|s| s.id.clone())
+            .collect();
+
+        if ids.is_empty() {
+            ids = current
+                .iter()
+                .filter(|s| s.name.eq_ignore_ascii_case(&saved.name))
+                .map(|s| s.id.clone())
+                .collect();
+        }
+
+        if ids.is_empty() {
+            unmatched.push(saved.name.clone());
+        } else {
+            matched.push((saved, ids));
+        }
+    }
+
+    (matched, unmatched)
+}
+
+fn apply_matched(
+    profile: &AudioProfile,
+    matched: &[(&ProfileSession, Vec<String>)],
+) -> Result<(), String> {
+    let backend = audio_backend();
+
+    if let Some(device_id) = &profile.default_device {
+        backend.set_default_device(device_id, &DEFAULT_DEVICE_ROLES)?;
+    }
+    backend.set_default_device_volume(DeviceDirection::Render, profile.volume.clamp(0.0, 1.0))?;
+    backend.set_default_device_mute(DeviceDirection::Render, profile.is_muted)?;
+
+    for (saved, ids) in matched {
+        for id in ids {
+            backend.set_session_volume(id, saved.volume.clamp(0.0, 1.0))?;
+            backend.set_session_mute(id, saved.is_muted)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 套用設定檔，任一步驟失敗時還原到套用前的狀態
+pub fn apply_profile(profile: &AudioProfile) -> Result<ApplyProfileResult, String> {
+    let _guard = APPLY_LOCK.lock().unwrap();
+    let backend = audio_backend();
+
+    // 先確認裝置存在，避免套用到一半才失敗
+    if let Some(device_id) = &profile.default_device {
+        if !backend
+            .get_devices(DeviceDirection::Render, false)?
+            .iter()
+            .any(|d| &d.id == device_id)
+        {
+            return Err(format!("找不到裝置: '{}'", device_id));
+        }
+    }
+
+    let current = backend.get_sessions()?;
+    let (matched, unmatched_sessions) = match_sessions(profile, &current);
+
+    let previous = snapshot_profile(&profile.name)?;
+
+    if let Err(e) = apply_matched(profile, &matched) {
+        let (restore, _) = match_sessions(&previous, &current);
+        if let Err(_restore_error) = apply_matched(&previous, &restore) {
+            crate::utils::debug_log!("無法還原混音器狀態: {}", _restore_error);
+        }
+        return Err(format!("無法套用設定檔 '{}': {}", profile.name, e));
+    }
+
+    Ok(ApplyProfileResult {
+        profile: profile.name.clone(),
+        unmatched_sessions,
+    })
+}
+
+/// 依名稱套用已儲存的設定檔
+pub fn apply_profile_by_name(name: &str) -> Result<ApplyProfileResult, String> {
+    let profile = PROFILES
+        .read()
+        .unwrap()
+        .iter()
+        .find(|p| p.name == name)
+        .cloned()
+        .ok_or_else(|| format!("找不到設定檔: '{}'", name))?;
+
+    apply_profile(&profile)
+}
+
+pub fn profiles() -> Vec<AudioProfile> {
+    PROFILES.read().unwrap().clone()
+}
+
+/// 新增或取代同名的設定檔
+pub fn set_profile(profile: AudioProfile) {
+    let mut profiles = PROFILES.write().unwrap();
+    match profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+}
+
+/// 從本地儲存載入設定檔，應在啟動時呼叫
+pub fn load_profiles<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    use tauri_plugin_store::StoreExt;
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+    let profiles = store
+        .get(STORE_KEY)
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+
+    *PROFILES.write().unwrap() = profiles;
+
+    Ok(())
+}
+
+/// 儲存設定檔到本地，並更新快捷鍵與系統匣選單
+fn save_profiles(app: &tauri::AppHandle) -> Result<(), String> {
+    use tauri::Emitter;
+    use tauri_plugin_store::StoreExt;
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+    store.set(STORE_KEY, serde_json::json!(*PROFILES.read().unwrap()));
+
+    store.save().map_err(|e| format!("無法保存設定: {:?}", e))?;
+
+    crate::register_profile_hotkeys(app)?;
+    crate::refresh_tray_menu(app);
+    let _ = app.emit("audio-profiles-updated", ());
+
+    Ok(())
+}
+
+/// 獲取所有設定檔
+#[tauri::command]
+pub fn get_audio_profiles() -> Vec<AudioProfile> {
+    profiles()
+}
+
+/// 將目前的混音器狀態儲存為設定檔，同名時覆蓋
+/// @param hotkey 套用此設定檔的全域快捷鍵，不需要時傳入 None
+#[tauri::command]
+pub fn save_audio_profile(
+    app: tauri::AppHandle,
+    name: String,
+    hotkey: Option<String>,
+) -> Result<AudioProfile, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("設定檔名稱不能為空".to_string());
+    }
+
+    let mut profile = snapshot_profile(name)?;
+    profile.hotkey = hotkey;
+
+    set_profile(profile.clone());
+    save_profiles(&app)?;
+
+    Ok(profile)
+}
+
+/// 刪除設定檔
+#[tauri::command]
+pub fn delete_audio_profile(app: tauri::AppHandle, name: String) -> Result<(), String> {
+    {
+        let mut profiles = PROFILES.write().unwrap();
+        let before = profiles.len();
+        profiles.retain(|p| p.name != name);
+
+        if profiles.len() == before {
+            return Err(format!("找不到設定檔: '{}'", name));
+        }
+    }
+
+    save_profiles(&app)
+}
+
+/// 套用已儲存的設定檔
+#[tauri::command]
+pub fn apply_audio_profile(name: String) -> Result<ApplyProfileResult, String> {
+    apply_profile_by_name(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::mock::{mock_session, MockAudioBackend};
+    use crate::utils::backend_test_lock;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn profiles_restore_the_mixer_and_report_missing_apps() {
+        let _lock = backend_test_lock().await;
+        let audio = Arc::new(MockAudioBackend::fixture());
+        crate::set_audio_backend(audio.clone());
+
+        let mut profile = snapshot_profile("music").unwrap();
+        profile.sessions.push(ProfileSession {
+            group: "c:\\program files\\discord.exe".to_string(),
+            name: "Discord.exe".to_string(),
+            volume: 0.2,
+            is_muted: false,
+        });
+
+        crate::set_default_device("headset".to_string(), None).unwrap();
+        crate::set_default_device_volume(0.1, None).unwrap();
+        audio.update(|state| {
+            state.sessions = vec![
+                mock_session("spotify-2", "Spotify.exe", 300, 0.1),
+                mock_session("chrome-3", "chrome.exe", 301, 0.3),
+            ];
+        });
+
+        let result = apply_profile(&profile).unwrap();
+        assert_eq!(result.unmatched_sessions, vec!["Discord.exe".to_string()]);
+
+        let state = audio.state();
+        assert!(state
+            .devices
+            .iter()
+            .any(|d| d.id == "speakers" && d.is_default));
+        assert!((state.default_volume - 0.5).abs() < 1e-6);
+        // 重新啟動後的會話以分組鍵比對，Chrome 取第一個會話的音量
+        assert!((state.sessions[0].volume - 0.8).abs() < 1e-6);
+        assert!((state.sessions[1].volume - 1.0).abs() < 1e-6);
+
+        profile.default_device = Some("gone".to_string());
+        assert!(apply_profile(&profile).is_err());
+        assert!((audio.state().default_volume - 0.5).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,595 @@
+use crate::audio::{app_matches, apply_session_mute, apply_session_volume, audio_backend};
+use crate::models::{AudioSession, DeviceDirection, MediaInfo};
+use crate::utils::{debug_log, now_millis};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+const STORE_KEY: &str = "rules";
+
+/// 評估紀錄保留的筆數
+const LOG_CAPACITY: usize = 200;
+
+/// 觸發條件，`app` 可填程式名稱、執行檔名稱或完整路徑，`device` 可填裝置 ID 或名稱
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Trigger {
+    /// 程式開始播放聲音
+    SessionStarted {
+        app: String,
+    },
+    /// 程式的音訊會話結束
+    SessionEnded {
+        app: String,
+    },
+    DeviceAdded {
+        device: String,
+    },
+    DeviceRemoved {
+        device: String,
+    },
+    DefaultDeviceChanged {
+        device: String,
+    },
+    /// 媒體開始播放，未指定 app 時任何程式都符合
+    MediaStarted {
+        #[serde(default)]
+        app: Option<String>,
+    },
+    /// 媒體暫停或停止
+    MediaStopped {
+        #[serde(default)]
+        app: Option<String>,
+    },
+    /// 所有媒體都沒有播放超過指定分鐘數，每次閒置只觸發一次
+    MediaIdle {
+        minutes: u64,
+    },
+}
+
+/// 動作，參數與同名的指令相同
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    SetSessionVolume {
+        session_id: String,
+        volume: f32,
+    },
+    SetSessionMute {
+        session_id: String,
+        mute: bool,
+    },
+    /// 未指定裝置時使用觸發此規則的裝置
+    SetDefaultDevice {
+        #[serde(default)]
+        device_id: Option<String>,
+    },
+    SetDefaultDeviceVolume {
+        volume: f32,
+    },
+    MediaPlayPause {
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    MediaPlay {
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    /// 閒置規則應使用暫停而不是播放/暫停，否則會讓已停止的媒體開始播放
+    MediaPause {
+        #[serde(default)]
+        session_id: Option<String>,
+    },
+    ApplyProfile {
+        name: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    /// 新規則留空，儲存時自動產生
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub trigger: Trigger,
+    pub actions: Vec<Action>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 一次規則評估的結果
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleLogEntry {
+    /// Unix 毫秒
+    pub timestamp: u64,
+    pub rule_id: String,
+    pub rule_name: String,
+    /// 觸發的事件描述
+    pub event: String,
+    pub success: bool,
+    /// 失敗的動作與原因
+    pub errors: Vec<String>,
+}
+
+/// 送進規則引擎的事件
+#[derive(Debug, Clone)]
+pub enum RuleEvent {
+    SessionStarted(AudioSession),
+    SessionEnded(AudioSession),
+    DeviceAdded { device_id: String },
+    DeviceRemoved { device_id: String },
+    DefaultDeviceChanged { device_id: String },
+    MediaStarted(MediaInfo),
+    MediaStopped(MediaInfo),
+}
+
+impl RuleEvent {
+    fn describe(&self) -> String {
+        match self {
+            RuleEvent::SessionStarted(s) => format!("session_started: {}", s.name),
+            RuleEvent::SessionEnded(s) => format!("session_ended: {}", s.name),
+            RuleEvent::DeviceAdded { device_id } => format!("device_added: {}", device_id),
+            RuleEvent::DeviceRemoved { device_id } => format!("device_removed: {}", device_id),
+            RuleEvent::DefaultDeviceChanged { device_id } => {
+                format!("default_device_changed: {}", device_id)
+            }
+            RuleEvent::MediaStarted(m) => format!("media_started: {}", m.app_name),
+            RuleEvent::MediaStopped(m) => format!("media_stopped: {}", m.app_name),
+        }
+    }
+
+    fn device_id(&self) -> Option<&str> {
+        match self {
+            RuleEvent::DeviceAdded { device_id }
+            | RuleEvent::DeviceRemoved { device_id }
+            | RuleEvent::DefaultDeviceChanged { device_id } => Some(device_id),
+            _ => None,
+        }
+    }
+}
+
+/// 比對裝置，ID 需完全相同，名稱不分大小寫
+/// 裝置已移除時 device_name 為快取的名稱
+fn device_matches(pattern: &str, device_id: &str, device_name: Option<&str>) -> bool {
+    pattern == device_id || device_name.is_some_and(|name| pattern.eq_ignore_ascii_case(name))
+}
+
+impl Trigger {
+    fn matches(&self, event: &RuleEvent, device_name: Option<&str>) -> bool {
+        match (self, event) {
+            (Trigger::SessionStarted { app }, RuleEvent::SessionStarted(s))
+            | (Trigger::SessionEnded { app }, RuleEvent::SessionEnded(s)) => {
+                app_matches(app, &s.name, &s.path)
+            }
+            (Trigger::DeviceAdded { device }, RuleEvent::DeviceAdded { device_id })
+            | (Trigger::DeviceRemoved { device }, RuleEvent::DeviceRemoved { device_id })
+            | (
+                Trigger::DefaultDeviceChanged { device },
+                RuleEvent::DefaultDeviceChanged { device_id },
+            ) => device_matches(device, device_id, device_name),
+            (Trigger::MediaStarted { app }, RuleEvent::MediaStarted(m))
+            | (Trigger::MediaStopped { app }, RuleEvent::MediaStopped(m)) => app
+                .as_deref()
+                .map_or(true, |app| app_matches(app, &m.app_name, "")),
+            _ => false,
+        }
+    }
+}
+
+impl Action {
+    fn run(&self, device_id: Option<&str>) -> Result<(), String> {
+        match self {
+            // 規則造成的變化不是使用者的選擇，不記住音量
+            Action::SetSessionVolume { session_id, volume } => {
+                apply_session_volume(session_id, *volume).map(|_| ())
+            }
+            Action::SetSessionMute { session_id, mute } => {
+                apply_session_mute(session_id, *mute).map(|_| ())
+            }
+            Action::SetDefaultDevice { device_id: target } => {
+                let target = target
+                    .as_deref()
+                    .or(device_id)
+                    .ok_or_else(|| "沒有指定裝置".to_string())?;
+                crate::set_default_device(target.to_string(), None)
+            }
+            Action::SetDefaultDeviceVolume { volume } => {
+                crate::set_default_device_volume(*volume, None)
+            }
+            Action::MediaPlayPause { session_id } => crate::media_play_pause(session_id.clone()),
+            Action::MediaPlay { session_id } => crate::media_play(session_id.clone()),
+            Action::MediaPause { session_id } => crate::media_pause(session_id.clone()),
+            Action::ApplyProfile { name } => crate::apply_profile_by_name(name).map(|_| ()),
+        }
+    }
+}
+
+/// 媒體播放狀態，用於判斷開始、停止與閒置
+struct MediaState {
+    playing: HashMap<String, MediaInfo>,
+    last_playing: Instant,
+    /// 這次閒置已觸發過的規則
+    idle_fired: HashSet<String>,
+}
+
+lazy_static::lazy_static! {
+    static ref RULES: RwLock<Vec<Rule>> = RwLock::new(Vec::new());
+    static ref RULE_LOG: Mutex<VecDeque<RuleLogEntry>> = Mutex::new(VecDeque::new());
+    /// 裝置 ID 對應的名稱，裝置移除後仍可用名稱比對
+    static ref DEVICE_NAMES: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+    static ref MEDIA_STATE: Mutex<MediaState> = Mutex::new(MediaState {
+        playing: HashMap::new(),
+        last_playing: Instant::now(),
+        idle_fired: HashSet::new(),
+    });
+}
+
+/// 執行規則的所有動作並寫入評估紀錄，單一動作失敗不影響後續動作
+fn run_rule(rule: &Rule, event: String, device_id: Option<&str>) {
+    let errors: Vec<String> = rule
+        .actions
+        .iter()
+        .filter_map(|action| {
+            action
+                .run(device_id)
+                .err()
+                .map(|e| format!("{:?}: {}", action, e))
+        })
+        .collect();
+
+    if !errors.is_empty() {
+        debug_log!("規則 '{}' 執行失敗: {:?}", rule.name, errors);
+    }
+
+    let mut log = RULE_LOG.lock().unwrap();
+    if log.len() >= LOG_CAPACITY {
+        log.pop_front();
+    }
+    log.push_back(RuleLogEntry {
+        timestamp: now_millis(),
+        rule_id: rule.id.clone(),
+        rule_name: rule.name.clone(),
+        event,
+        success: errors.is_empty(),
+        errors,
+    });
+}
+
+/// 查詢裝置名稱並更新快取，已移除的裝置使用快取的名稱
+fn device_name(device_id: &str) -> Option<String> {
+    let mut names = DEVICE_NAMES.lock().unwrap();
+
+    for direction in [DeviceDirection::Render, DeviceDirection::Capture] {
+        if let Ok(devices) = audio_backend().get_devices(direction, true) {
+            for device in devices {
+                names.insert(device.id, device.name);
+            }
+        }
+    }
+
+    names.get(device_id).cloned()
+}
+
+/// 評估所有啟用中的規則，符合條件的規則依序執行
+pub fn handle_rule_event(event: &RuleEvent) {
+    let rules: Vec<Rule> = RULES
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|rule| rule.enabled)
+        .cloned()
+        .collect();
+
+    if rules.is_empty() {
+        return;
+    }
+
+    let device_id = event.device_id();
+    let device_name = device_id.and_then(device_name);
+
+    for rule in rules {
+        if rule.trigger.matches(event, device_name.as_deref()) {
+            run_rule(&rule, event.describe(), device_id);
+        }
+    }
+}
+
+/// 比較前後兩次的音訊會話，產生開始與結束事件
+pub fn session_rule_events(before: &[AudioSession], after: &[AudioSession]) -> Vec<RuleEvent> {
+    let started = after
+        .iter()
+        .filter(|s| !before.iter().any(|b| b.id == s.id))
+        .cloned()
+        .map(RuleEvent::SessionStarted);
+    let ended = before
+        .iter()
+        .filter(|s| !after.iter().any(|a| a.id == s.id))
+        .cloned()
+        .map(RuleEvent::SessionEnded);
+
+    started.chain(ended).collect()
+}
+
+/// 由媒體監聽循環定期呼叫，偵測播放狀態變化與閒置
+pub fn observe_media(sessions: &[MediaInfo]) {
+    let mut events = Vec::new();
+    let mut idle_rules = Vec::new();
+
+    {
+        let mut state = MEDIA_STATE.lock().unwrap();
+
+        for session in sessions.iter().filter(|s| s.is_playing) {
+            if !state.playing.contains_key(&session.session_id) {
+                events.push(RuleEvent::MediaStarted(session.clone()));
+            }
+        }
+
+        let stopped: Vec<String> = state
+            .playing
+            .keys()
+            .filter(|id| {
+                !sessions
+                    .iter()
+                    .any(|s| &s.session_id == *id && s.is_playing)
+            })
+            .cloned()
+            .collect();
+        for id in stopped {
+            if let Some(info) = state.playing.remove(&id) {
+                events.push(RuleEvent::MediaStopped(info));
+            }
+        }
+
+        for session in sessions.iter().filter(|s| s.is_playing) {
+            state
+                .playing
+                .insert(session.session_id.clone(), session.clone());
+        }
+
+        if !state.playing.is_empty() {
+            state.last_playing = Instant::now();
+            state.idle_fired.clear();
+        } else {
+            let idle = state.last_playing.elapsed();
+            for rule in RULES.read().unwrap().iter().filter(|rule| rule.enabled) {
+                if let Trigger::MediaIdle { minutes } = rule.trigger {
+                    if idle >= Duration::from_secs(minutes * 60)
+                        && state.idle_fired.insert(rule.id.clone())
+                    {
+                        idle_rules.push(rule.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    for event in &events {
+        handle_rule_event(event);
+    }
+
+    for rule in idle_rules {
+        if let Trigger::MediaIdle { minutes } = rule.trigger {
+            run_rule(&rule, format!("media_idle: {} 分鐘", minutes), None);
+        }
+    }
+}
+
+pub fn rules() -> Vec<Rule> {
+    RULES.read().unwrap().clone()
+}
+
+/// 新增或取代同 ID 的規則
+pub fn set_rule(mut rule: Rule) -> Rule {
+    if rule.id.is_empty() {
+        rule.id = uuid::Uuid::new_v4().to_string();
+    }
+
+    let mut rules = RULES.write().unwrap();
+    match rules.iter_mut().find(|r| r.id == rule.id) {
+        Some(existing) => *existing = rule.clone(),
+        None => rules.push(rule.clone()),
+    }
+
+    rule
+}
+
+/// 最近的評估紀錄，新的在後
+pub fn rule_log() -> Vec<RuleLogEntry> {
+    RULE_LOG.lock().unwrap().iter().cloned().collect()
+}
+
+/// 從本地儲存載入規則，應在啟動時呼叫
+pub fn load_rules<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<(), String> {
+    use tauri_plugin_store::StoreExt;
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+    let rules = store
+        .get(STORE_KEY)
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+
+    *RULES.write().unwrap() = rules;
+
+    Ok(())
+}
+
+/// 儲存規則到本地
+fn save_rules(app: &tauri::AppHandle) -> Result<(), String> {
+    use tauri_plugin_store::StoreExt;
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+    store.set(STORE_KEY, serde_json::json!(*RULES.read().unwrap()));
+
+    store.save().map_err(|e| format!("無法保存設定: {:?}", e))?;
+
+    Ok(())
+}
+
+/// 獲取所有規則
+#[tauri::command]
+pub fn get_rules() -> Vec<Rule> {
+    rules()
+}
+
+/// 新增或更新規則，回傳儲存後的規則 (含 ID)
+#[tauri::command]
+pub fn save_rule(app: tauri::AppHandle, rule: Rule) -> Result<Rule, String> {
+    if rule.name.trim().is_empty() {
+        return Err("規則名稱不能為空".to_string());
+    }
+
+    let rule = set_rule(rule);
+    save_rules(&app)?;
+
+    Ok(rule)
+}
+
+/// 刪除規則
+#[tauri::command]
+pub fn delete_rule(app: tauri::AppHandle, rule_id: String) -> Result<(), String> {
+    {
+        let mut rules = RULES.write().unwrap();
+        let before = rules.len();
+        rules.retain(|r| r.id != rule_id);
+
+        if rules.len() == before {
+            return Err(format!("找不到規則: '{}'", rule_id));
+        }
+    }
+
+    save_rules(&app)
+}
+
+/// 獲取規則的評估紀錄
+#[tauri::command]
+pub fn get_rule_log() -> Vec<RuleLogEntry> {
+    rule_log()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::mock::{mock_session, MockAudioBackend};
+    use crate::media::mock::MockMediaBackend;
+    use crate::utils::backend_test_lock;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn matching_rules_run_their_actions_and_are_logged() {
+        let _lock = backend_test_lock().await;
+        let audio = Arc::new(MockAudioBackend::fixture());
+        crate::set_audio_backend(audio.clone());
+        crate::forget(None).unwrap();
+
+        let duck = set_rule(Rule {
+            id: String::new(),
+            name: "duck".to_string(),
+            enabled: true,
+            trigger: Trigger::SessionStarted {
+                app: "zoom.exe".to_string(),
+            },
+            actions: vec![
+                Action::SetSessionVolume {
+                    session_id: "Spotify.exe".to_string(),
+                    volume: 0.2,
+                },
+                Action::SetSessionMute {
+                    session_id: "missing.exe".to_string(),
+                    mute: true,
+                },
+            ],
+        });
+        let headset = set_rule(Rule {
+            id: String::new(),
+            name: "headset".to_string(),
+            enabled: true,
+            trigger: Trigger::DeviceAdded {
+                device: "Headset".to_string(),
+            },
+            actions: vec![Action::SetDefaultDevice { device_id: None }],
+        });
+
+        let before = audio.state().sessions;
+        let mut after = before.clone();
+        after.push(mock_session("zoom-1", "Zoom.exe", 400, 1.0));
+        for event in session_rule_events(&before, &after) {
+            handle_rule_event(&event);
+        }
+        handle_rule_event(&RuleEvent::DeviceAdded {
+            device_id: "headset".to_string(),
+        });
+
+        let state = audio.state();
+        assert!((state.sessions[0].volume - 0.2).abs() < 1e-6);
+        assert!(state
+            .devices
+            .iter()
+            .any(|d| d.id == "headset" && d.is_default));
+
+        let log: Vec<RuleLogEntry> = rule_log()
+            .into_iter()
+            .filter(|e| e.rule_id == duck.id || e.rule_id == headset.id)
+            .collect();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].event, "session_started: Zoom.exe");
+        assert!(!log[0].success);
+        assert_eq!(log[0].errors.len(), 1);
+        assert!(log[1].success);
+
+        // 規則調整的音量不會成為 Spotify 記住的音量
+        assert!(crate::volume_memory().entries.is_empty());
+
+        RULES.write().unwrap().clear();
+    }
+
+    #[tokio::test]
+    async fn idle_rules_pause_media_once_per_idle_period() {
+        let _lock = backend_test_lock().await;
+        let media = Arc::new(MockMediaBackend::fixture());
+        crate::set_media_backend(media.clone());
+
+        let idle = set_rule(Rule {
+            id: String::new(),
+            name: "idle".to_string(),
+            enabled: true,
+            trigger: Trigger::MediaIdle { minutes: 0 },
+            actions: vec![Action::MediaPause {
+                session_id: Some("spotify".to_string()),
+            }],
+        });
+
+        // 播放中不算閒置
+        observe_media(&media.state().players);
+        assert!(media.state().commands.is_empty());
+
+        let mut stopped = media.state().players;
+        stopped[0].is_playing = false;
+        observe_media(&stopped);
+        observe_media(&stopped);
+
+        let state = media.state();
+        assert_eq!(state.commands, vec!["pause:spotify"]);
+        assert!(!state.players[0].is_playing);
+
+        let log: Vec<RuleLogEntry> = rule_log()
+            .into_iter()
+            .filter(|e| e.rule_id == idle.id)
+            .collect();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].event, "media_idle: 0 分鐘");
+        assert!(log[0].success);
+
+        RULES.write().unwrap().clear();
+    }
+}
@@ -0,0 +1,85 @@
+use crate::http_server::HttpServer;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+const STORE_KEY: &str = "server";
+
+/// 區域網路服務設定
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerSettings {
+    /// 關閉後手機將無法連線
+    pub enabled: bool,
+    /// 綁定的位址，0.0.0.0 表示所有網路介面
+    pub bind_address: String,
+    /// 偏好的端口，被占用時會自動改用其他端口
+    pub port: u16,
+    /// 同時監聽 IPv6 (::)
+    pub ipv6: bool,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bind_address: "0.0.0.0".to_string(),
+            port: 8800,
+            ipv6: false,
+        }
+    }
+}
+
+/// 區域網路服務目前的狀態
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ServerStatus {
+    pub running: bool,
+    /// 實際綁定的端口
+    pub port: Option<u16>,
+    /// 手機可使用的網址
+    pub urls: Vec<String>,
+    /// 啟動失敗的原因
+    pub error: Option<String>,
+}
+
+/// 載入區域網路服務設定，沒有設定時使用預設值
+pub fn load_server_settings<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> ServerSettings {
+    use tauri_plugin_store::StoreExt;
+
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(STORE_KEY))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// 獲取區域網路服務設定
+#[tauri::command]
+pub fn get_server_settings(app: tauri::AppHandle) -> ServerSettings {
+    load_server_settings(&app)
+}
+
+/// 儲存區域網路服務設定並以新設定重新啟動
+#[tauri::command]
+pub async fn save_server_settings(
+    app: tauri::AppHandle,
+    server: tauri::State<'_, Arc<HttpServer<tauri::Wry>>>,
+    settings: ServerSettings,
+) -> Result<ServerStatus, String> {
+    use tauri_plugin_store::StoreExt;
+
+    let store = app
+        .store("settings.json")
+        .map_err(|e| format!("無法打開儲存: {:?}", e))?;
+
+    store.set(STORE_KEY, serde_json::json!(settings));
+
+    store.save().map_err(|e| format!("無法保存設定: {:?}", e))?;
+
+    Ok(Arc::clone(&server).start(&settings).await)
+}
+
+/// 獲取區域網路服務目前的狀態
+#[tauri::command]
+pub fn get_server_status(server: tauri::State<'_, Arc<HttpServer<tauri::Wry>>>) -> ServerStatus {
+    server.status()
+}
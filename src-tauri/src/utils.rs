@@ -12,6 +12,25 @@ macro_rules! debug_log {
 
 pub(crate) use debug_log;
 
+/// 目前的 Unix 時間 (毫秒)
+pub fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+lazy_static::lazy_static! {
+    static ref BACKEND_TEST_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+}
+
+/// 測試會替換全域的後端，需要依序執行
+#[cfg(test)]
+pub async fn backend_test_lock() -> tokio::sync::MutexGuard<'static, ()> {
+    BACKEND_TEST_LOCK.lock().await
+}
+
 #[cfg(target_os = "windows")]
 pub struct ComGuard;
 
@@ -1,29 +1,62 @@
 'use client';
 
-import { Volume2, VolumeX } from 'lucide-react';
+import { Headphones, Monitor, Speaker, Volume2, VolumeX } from 'lucide-react';
 import { Card } from '@/components/ui/card';
+import { Button } from '@/components/ui/button';
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '@/components/ui/select';
 import { Slider } from '@/components/ui/slider';
 import { Switch } from '@/components/ui/switch';
-import { AudioDevice } from '@/models/home';
+import { AudioDevice, DeviceState, DeviceVolume } from '@/models/home';
+
+const STATE_LABELS: Record<DeviceState, string> = {
+  active: '',
+  disabled: '已停用',
+  not_present: '未連接',
+  unplugged: '未接上',
+};
+
+function DeviceIcon({ device }: { device: AudioDevice }) {
+  switch (device.form_factor) {
+    case 'headphones':
+    case 'headset':
+      return <Headphones className="w-4 h-4 shrink-0" />;
+    case 'hdmi':
+      return <Monitor className="w-4 h-4 shrink-0" />;
+    default:
+      return <Speaker className="w-4 h-4 shrink-0" />;
+  }
+}
 
 interface DeviceSelectorProps {
   devices: AudioDevice[];
+  /** 停用、未接上或已移除的裝置，只顯示不能操作 */
+  inactiveDevices: AudioDevice[];
   defaultDeviceVolume: number;
   defaultDeviceMuted: boolean;
   onSetDefaultDevice: (deviceId: string) => void;
+  onSetCommunicationsDevice: (deviceId: string) => void;
   onVolumeChange: (volume: number) => void;
   onMuteToggle: () => void;
+  deviceVolumes: Record<string, DeviceVolume>;
+  onDeviceVolumeChange: (deviceId: string, volume: number) => void;
+  onDeviceMuteToggle: (deviceId: string) => void;
 }
 
 export function DeviceSelector({
   devices,
+  inactiveDevices,
   defaultDeviceVolume,
   defaultDeviceMuted,
   onSetDefaultDevice,
+  onSetCommunicationsDevice,
   onVolumeChange,
   onMuteToggle,
+  deviceVolumes,
+  onDeviceVolumeChange,
+  onDeviceMuteToggle,
 }: DeviceSelectorProps) {
+  const otherDevices = devices.filter(d => !d.is_default && deviceVolumes[d.id]);
+
   return (
     <Card className="mb-8 rounded-lg p-6 gap-0">
       <label className="text-xl font-semibold mb-4 flex items-center gap-2">
@@ -42,13 +75,34 @@ export function DeviceSelector({
           </SelectTrigger>
           <SelectContent>
             {devices.map((device) => (
-              <SelectItem key={device.id} value={device.id}>
+              <SelectItem key={device.id} value={device.id} title={device.interface_name ?? undefined}>
+                <DeviceIcon device={device} />
                 {device.name} {device.is_default ? '(默認)' : ''}
               </SelectItem>
             ))}
           </SelectContent>
         </Select>
       )}
+      {devices.length > 1 && (
+        <div className="mt-4">
+          <span className="text-sm font-medium">通話使用</span>
+          <Select
+            value={devices.find(d => d.default_roles?.includes('communications'))?.id || ''}
+            onValueChange={onSetCommunicationsDevice}
+          >
+            <SelectTrigger className="w-full mt-2">
+              <SelectValue placeholder="選擇通訊設備" />
+            </SelectTrigger>
+            <SelectContent>
+              {devices.map((device) => (
+                <SelectItem key={device.id} value={device.id}>
+                  {device.name}
+                </SelectItem>
+              ))}
+            </SelectContent>
+          </Select>
+        </div>
+      )}
       <div className="mt-6 space-y-4">
         <div>
           <div className="flex justify-between text-sm mb-3">
@@ -81,6 +135,55 @@ export function DeviceSelector({
             onCheckedChange={onMuteToggle}
           />
         </div>
+
+        {otherDevices.length > 0 && (
+          <div className="space-y-3">
+            <span className="text-sm font-medium">其他裝置</span>
+            {otherDevices.map((device) => {
+              const { volume, is_muted } = deviceVolumes[device.id];
+              return (
+                <div key={device.id} className="flex items-center gap-3">
+                  <Button
+                    variant="outline"
+                    size="icon"
+                    onClick={() => onDeviceMuteToggle(device.id)}
+                    title={is_muted ? '取消靜音' : '靜音'}
+                  >
+                    {is_muted ? <VolumeX className="w-4 h-4" /> : <Volume2 className="w-4 h-4" />}
+                  </Button>
+                  <span className="text-sm truncate w-32" title={device.name}>{device.name}</span>
+                  <Slider
+                    value={[Math.round(volume * 100)]}
+                    onValueChange={(value) => onDeviceVolumeChange(device.id, value[0])}
+                    min={0}
+                    max={100}
+                    step={1}
+                    className="flex-1"
+                  />
+                  <span className="text-sm w-10 text-right">{Math.round(volume * 100)}%</span>
+                </div>
+              );
+            })}
+          </div>
+        )}
+
+        {inactiveDevices.length > 0 && (
+          <div className="space-y-2">
+            <span className="text-sm font-medium">無法使用的裝置</span>
+            {inactiveDevices.map((device) => (
+              <div
+                key={device.id}
+                className="flex items-center gap-3 text-sm text-muted-foreground"
+                title={device.interface_name ?? undefined}
+              >
+                <DeviceIcon device={device} />
+                <span className="truncate">
+                  {device.name} ({STATE_LABELS[device.state]})
+                </span>
+              </div>
+            ))}
+          </div>
+        )}
       </div>
     </Card>
   );
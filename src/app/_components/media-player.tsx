@@ -1,32 +1,123 @@
 'use client';
 
-import { Music, Play, Pause, SkipForward, SkipBack } from 'lucide-react';
+import { useEffect, useState } from 'react';
+import { Music, Play, Pause, SkipForward, SkipBack, Square, Shuffle, Repeat, Repeat1 } from 'lucide-react';
 import { Button } from '@/components/ui/button';
 import { Card } from '@/components/ui/card';
+import { Slider } from '@/components/ui/slider';
 import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '@/components/ui/select';
-import { MediaInfo } from '@/models/media';
+import { MediaInfo, RepeatMode } from '@/models/media';
 
 interface MediaPlayerProps {
   mediaInfo: MediaInfo | null;
+  artworkUrl: string | null;
   allMediaSessions: MediaInfo[];
   selectedSessionId: string;
   onSessionSelect: (sessionId: string) => void;
   onPlayPause: () => void;
   onNext: () => void;
   onPrevious: () => void;
+  onSeek: (positionMs: number) => void;
+  onStop: () => void;
+  onShuffleChange: (shuffle: boolean) => void;
+  onRepeatChange: (repeat: RepeatMode) => void;
+  onRateChange: (rate: number) => void;
+}
+
+const NEXT_REPEAT_MODE: Record<RepeatMode, RepeatMode> = {
+  none: 'list',
+  list: 'track',
+  track: 'none',
+};
+
+const REPEAT_LABELS: Record<RepeatMode, string> = {
+  none: '不重複',
+  list: '重複播放清單',
+  track: '重複單曲',
+};
+
+const PLAYBACK_RATES = [0.5, 0.75, 1, 1.25, 1.5, 2];
+
+function formatTime(ms: number): string {
+  const totalSeconds = Math.floor(ms / 1000);
+  const minutes = Math.floor(totalSeconds / 60);
+  const seconds = totalSeconds % 60;
+  return `${minutes}:${seconds.toString().padStart(2, '0')}`;
+}
+
+function MediaTimelineBar({ mediaInfo, onSeek }: { mediaInfo: MediaInfo; onSeek: (positionMs: number) => void }) {
+  const [now, setNow] = useState(() => Date.now());
+  const [dragging, setDragging] = useState<number | null>(null);
+
+  useEffect(() => {
+    if (!mediaInfo.is_playing) return;
+    const timer = setInterval(() => setNow(Date.now()), 500);
+    return () => clearInterval(timer);
+  }, [mediaInfo.is_playing]);
+
+  if (mediaInfo.position_ms == null || !mediaInfo.end_time_ms) return null;
+
+  const start = mediaInfo.start_time_ms ?? 0;
+  const end = mediaInfo.end_time_ms;
+  // 播放中時以上次回報的時間推算目前位置
+  const elapsed = mediaInfo.is_playing && mediaInfo.timeline_updated_at
+    ? Math.max(0, now - mediaInfo.timeline_updated_at)
+    : 0;
+  const position = Math.min(Math.max(mediaInfo.position_ms + elapsed, start), end);
+  const shown = dragging ?? position;
+
+  return (
+    <div className="mt-4">
+      <Slider
+        value={[shown]}
+        min={start}
+        max={end}
+        step={1000}
+        disabled={!mediaInfo.can_seek}
+        onValueChange={(value) => setDragging(value[0])}
+        onValueCommit={(value) => {
+          setDragging(null);
+          onSeek(value[0]);
+        }}
+        title={mediaInfo.can_seek ? undefined : '當前播放器不支援調整播放位置'}
+      />
+      <div className="flex justify-between text-xs text-muted-foreground mt-1">
+        <span>{formatTime(shown - start)}</span>
+        <span>{formatTime(end - start)}</span>
+      </div>
+    </div>
+  );
 }
 
 export function MediaPlayer({
   mediaInfo,
+  artworkUrl,
   allMediaSessions,
   selectedSessionId,
   onSessionSelect,
   onPlayPause,
   onNext,
   onPrevious,
+  onSeek,
+  onStop,
+  onShuffleChange,
+  onRepeatChange,
+  onRateChange,
 }: MediaPlayerProps) {
+  const [failedArtworkUrl, setFailedArtworkUrl] = useState<string | null>(null);
+
   if (!mediaInfo) return null;
 
+  // 播放器沒有提供封面時伺服器回傳 404，改顯示預設圖示
+  const artworkSrc = mediaInfo.thumbnail
+    ? `data:image/png;base64,${mediaInfo.thumbnail}`
+    : artworkUrl !== failedArtworkUrl ? artworkUrl : null;
+
+  const repeat = mediaInfo.repeat ?? 'none';
+  const rate = mediaInfo.playback_rate ?? 1;
+  const rates = PLAYBACK_RATES.includes(rate) ? PLAYBACK_RATES : [...PLAYBACK_RATES, rate].sort((a, b) => a - b);
+  const hasExtraControls = mediaInfo.can_stop || mediaInfo.can_shuffle || mediaInfo.can_repeat || mediaInfo.can_change_rate;
+
   return (
     <Card className="mb-8 rounded-lg p-6 gap-0">
       <div className="flex items-center justify-between mb-4">
@@ -72,12 +163,13 @@ export function MediaPlayer({
       )}
 
       <div className="flex gap-4">
-        {mediaInfo.thumbnail ? (
+        {artworkSrc ? (
           <div className="shrink-0">
             <img
-              src={`data:image/png;base64,${mediaInfo.thumbnail}`}
+              src={artworkSrc}
               alt="封面"
               className="w-32 h-32 rounded-lg object-cover shadow-lg"
+              onError={() => setFailedArtworkUrl(artworkUrl)}
             />
           </div>
         ) : (
@@ -116,6 +208,8 @@ export function MediaPlayer({
         </div>
       </div>
 
+      <MediaTimelineBar mediaInfo={mediaInfo} onSeek={onSeek} />
+
       <div className="flex items-center justify-center gap-3 mt-6 pt-4 border-t">
         <Button
           onClick={onPrevious}
@@ -150,6 +244,57 @@ export function MediaPlayer({
           <SkipForward className="w-5 h-5" />
         </Button>
       </div>
+
+      {hasExtraControls && (
+        <div className="flex items-center justify-center gap-2 mt-3">
+          {mediaInfo.can_shuffle && (
+            <Button
+              onClick={() => onShuffleChange(!mediaInfo.shuffle)}
+              variant={mediaInfo.shuffle ? 'secondary' : 'ghost'}
+              size="icon"
+              title={mediaInfo.shuffle ? '關閉隨機播放' : '隨機播放'}
+            >
+              <Shuffle className={`w-4 h-4 ${mediaInfo.shuffle ? 'text-blue-600' : ''}`} />
+            </Button>
+          )}
+
+          {mediaInfo.can_stop && (
+            <Button onClick={onStop} variant="ghost" size="icon" title="停止">
+              <Square className="w-4 h-4" />
+            </Button>
+          )}
+
+          {mediaInfo.can_repeat && (
+            <Button
+              onClick={() => onRepeatChange(NEXT_REPEAT_MODE[repeat])}
+              variant={repeat !== 'none' ? 'secondary' : 'ghost'}
+              size="icon"
+              title={REPEAT_LABELS[repeat]}
+            >
+              {repeat === 'track' ? (
+                <Repeat1 className="w-4 h-4 text-blue-600" />
+              ) : (
+                <Repeat className={`w-4 h-4 ${repeat === 'list' ? 'text-blue-600' : ''}`} />
+              )}
+            </Button>
+          )}
+
+          {mediaInfo.can_change_rate && (
+            <Select value={String(rate)} onValueChange={(value) => onRateChange(Number(value))}>
+              <SelectTrigger className="w-20 h-8" title="播放速度">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                {rates.map((r) => (
+                  <SelectItem key={r} value={String(r)}>
+                    {r}x
+                  </SelectItem>
+                ))}
+              </SelectContent>
+            </Select>
+          )}
+        </div>
+      )}
     </Card>
   );
 }
@@ -0,0 +1,75 @@
+'use client';
+
+import { Mic, MicOff } from 'lucide-react';
+import { Card } from '@/components/ui/card';
+import { Button } from '@/components/ui/button';
+import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '@/components/ui/select';
+import { Slider } from '@/components/ui/slider';
+import { AudioDevice } from '@/models/home';
+
+interface MicrophoneSelectorProps {
+  devices: AudioDevice[];
+  volume: number;
+  muted: boolean;
+  onSetDefaultDevice: (deviceId: string) => void;
+  onVolumeChange: (volume: number) => void;
+  onMuteToggle: () => void;
+}
+
+export function MicrophoneSelector({
+  devices,
+  volume,
+  muted,
+  onSetDefaultDevice,
+  onVolumeChange,
+  onMuteToggle,
+}: MicrophoneSelectorProps) {
+  return (
+    <Card className="mb-8 rounded-lg p-6 gap-0">
+      <label className="text-xl font-semibold mb-4 flex items-center gap-2">
+        <Mic className="w-5 h-5" />
+        麥克風
+      </label>
+      {devices.length === 0 ? (
+        <div>目前沒有檢測到麥克風</div>
+      ) : (
+        <>
+          <Select
+            value={devices.find(d => d.is_default)?.id || ''}
+            onValueChange={onSetDefaultDevice}
+          >
+            <SelectTrigger className="w-full">
+              <SelectValue placeholder="選擇麥克風" />
+            </SelectTrigger>
+            <SelectContent>
+              {devices.map((device) => (
+                <SelectItem key={device.id} value={device.id}>
+                  {device.name} {device.is_default ? '(默認)' : ''}
+                </SelectItem>
+              ))}
+            </SelectContent>
+          </Select>
+          <div className="mt-6 flex items-center gap-4">
+            <Button
+              variant={muted ? 'destructive' : 'outline'}
+              size="icon"
+              onClick={onMuteToggle}
+              title={muted ? '取消麥克風靜音' : '麥克風靜音'}
+            >
+              {muted ? <MicOff className="w-4 h-4" /> : <Mic className="w-4 h-4" />}
+            </Button>
+            <Slider
+              value={[volume]}
+              onValueChange={(value) => onVolumeChange(value[0])}
+              min={0}
+              max={100}
+              step={1}
+              className="flex-1"
+            />
+            <span className="text-lg font-bold w-14 text-right">{volume}%</span>
+          </div>
+        </>
+      )}
+    </Card>
+  );
+}
@@ -0,0 +1,54 @@
+'use client';
+
+import { useState } from 'react';
+import { KeyRound } from 'lucide-react';
+import { Card } from '@/components/ui/card';
+import { Button } from '@/components/ui/button';
+import { Input } from '@/components/ui/input';
+
+interface PairingCardProps {
+  onPair: (code: string) => Promise<void>;
+}
+
+export function PairingCard({ onPair }: PairingCardProps) {
+  const [code, setCode] = useState('');
+  const [error, setError] = useState('');
+  const [pairing, setPairing] = useState(false);
+
+  const handlePair = async () => {
+    try {
+      setPairing(true);
+      setError('');
+      await onPair(code);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setPairing(false);
+    }
+  };
+
+  return (
+    <Card className="mb-8 rounded-lg p-6 gap-0">
+      <label className="text-xl font-semibold mb-2 flex items-center gap-2">
+        <KeyRound className="w-5 h-5" />
+        配對此裝置
+      </label>
+      <p className="text-sm text-muted-foreground mb-4">
+        目前為唯讀模式。請在電腦上開啟 設定 → 配對裝置 取得配對碼
+      </p>
+      <div className="flex gap-2">
+        <Input
+          value={code}
+          onChange={(e) => setCode(e.target.value.replace(/\D/g, '').slice(0, 6))}
+          inputMode="numeric"
+          placeholder="6 位數配對碼"
+          className={`flex-1 ${error ? 'border-red-500' : ''}`}
+        />
+        <Button onClick={handlePair} disabled={code.length !== 6 || pairing}>
+          配對
+        </Button>
+      </div>
+      {error && <p className="text-sm text-red-500 mt-2">{error}</p>}
+    </Card>
+  );
+}
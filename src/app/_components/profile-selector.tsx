@@ -0,0 +1,122 @@
+'use client';
+
+import { useState } from 'react';
+import { Layers, Trash2 } from 'lucide-react';
+import { Card } from '@/components/ui/card';
+import { Button } from '@/components/ui/button';
+import { Input } from '@/components/ui/input';
+import { AudioProfile, ApplyProfileResult } from '@/models/home';
+
+interface ProfileSelectorProps {
+  profiles: AudioProfile[];
+  /** 只有電腦上可以新增或刪除設定檔 */
+  editable: boolean;
+  onApply: (name: string) => Promise<ApplyProfileResult>;
+  onSave: (name: string, hotkey: string | null) => Promise<void>;
+  onDelete: (name: string) => Promise<void>;
+}
+
+export function ProfileSelector({
+  profiles,
+  editable,
+  onApply,
+  onSave,
+  onDelete,
+}: ProfileSelectorProps) {
+  const [name, setName] = useState('');
+  const [hotkey, setHotkey] = useState('');
+  const [message, setMessage] = useState('');
+  const [error, setError] = useState('');
+
+  const run = async (action: () => Promise<string>) => {
+    try {
+      setError('');
+      setMessage(await action());
+    } catch (err) {
+      setMessage('');
+      setError(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  const handleApply = (profileName: string) => run(async () => {
+    const result = await onApply(profileName);
+    if (result.unmatched_sessions.length === 0) {
+      return `已套用「${result.profile}」`;
+    }
+    return `已套用「${result.profile}」，未執行的程式: ${result.unmatched_sessions.join('、')}`;
+  });
+
+  const handleSave = () => run(async () => {
+    await onSave(name.trim(), hotkey.trim() || null);
+    setName('');
+    setHotkey('');
+    return `已儲存「${name.trim()}」`;
+  });
+
+  if (profiles.length === 0 && !editable) {
+    return null;
+  }
+
+  return (
+    <Card className="mb-8 rounded-lg p-6 gap-0">
+      <label className="text-xl font-semibold mb-4 flex items-center gap-2">
+        <Layers className="w-5 h-5" />
+        設定檔
+      </label>
+      {profiles.length === 0 ? (
+        <div className="text-sm text-muted-foreground">
+          尚未建立設定檔，將目前的裝置與音量儲存後即可一鍵切換
+        </div>
+      ) : (
+        <div className="flex flex-wrap gap-2">
+          {profiles.map((profile) => (
+            <div key={profile.name} className="flex">
+              <Button
+                variant="outline"
+                onClick={() => handleApply(profile.name)}
+                className={editable ? 'rounded-r-none' : ''}
+                title={profile.hotkey ?? undefined}
+              >
+                {profile.name}
+              </Button>
+              {editable && (
+                <Button
+                  variant="outline"
+                  size="icon"
+                  onClick={() => run(async () => {
+                    await onDelete(profile.name);
+                    return `已刪除「${profile.name}」`;
+                  })}
+                  className="rounded-l-none border-l-0"
+                >
+                  <Trash2 className="w-4 h-4" />
+                </Button>
+              )}
+            </div>
+          ))}
+        </div>
+      )}
+      {editable && (
+        <div className="flex gap-2 mt-4">
+          <Input
+            value={name}
+            onChange={(e) => setName(e.target.value)}
+            placeholder="名稱，例如 會議"
+            className="flex-1"
+          />
+          <Input
+            value={hotkey}
+            onChange={(e) => setHotkey(e.target.value)}
+            placeholder="快捷鍵 (選填)，例如 Ctrl+Alt+1"
+            className="flex-1"
+          />
+          <Button onClick={handleSave} disabled={!name.trim()}>
+            儲存目前狀態
+          </Button>
+        </div>
+      )}
+      {message && <p className="text-sm text-muted-foreground mt-2">{message}</p>}
+      {error && <p className="text-sm text-red-500 mt-2">{error}</p>}
+    </Card>
+  );
+}
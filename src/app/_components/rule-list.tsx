@@ -0,0 +1,208 @@
+'use client';
+
+import { useState } from 'react';
+import { Workflow, Trash2 } from 'lucide-react';
+import { Card } from '@/components/ui/card';
+import { Button } from '@/components/ui/button';
+import { Input } from '@/components/ui/input';
+import { Switch } from '@/components/ui/switch';
+import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '@/components/ui/select';
+import { Action, Rule, RuleLogEntry, Trigger } from '@/models/rules';
+
+const TRIGGERS: { type: Trigger['type']; label: string; placeholder: string }[] = [
+  { type: 'session_started', label: '程式開始播放聲音', placeholder: '程式，例如 Zoom.exe' },
+  { type: 'session_ended', label: '程式結束播放聲音', placeholder: '程式，例如 Zoom.exe' },
+  { type: 'device_added', label: '裝置連接', placeholder: '裝置名稱或 ID' },
+  { type: 'device_removed', label: '裝置移除', placeholder: '裝置名稱或 ID' },
+  { type: 'default_device_changed', label: '預設裝置變更', placeholder: '裝置名稱或 ID' },
+  { type: 'media_started', label: '媒體開始播放', placeholder: '程式 (留空為任何程式)' },
+  { type: 'media_stopped', label: '媒體停止播放', placeholder: '程式 (留空為任何程式)' },
+  { type: 'media_idle', label: '媒體閒置', placeholder: '分鐘數' },
+];
+
+const ACTIONS: { type: Action['type']; label: string; target?: string; value?: string }[] = [
+  { type: 'set_session_volume', label: '設定程式音量', target: '程式，例如 Spotify.exe', value: '音量 %' },
+  { type: 'set_session_mute', label: '程式靜音', target: '程式，例如 Spotify.exe' },
+  { type: 'set_default_device', label: '設為預設裝置', target: '裝置 ID (留空為觸發的裝置)' },
+  { type: 'set_default_device_volume', label: '設定主音量', value: '音量 %' },
+  { type: 'media_play_pause', label: '播放/暫停' },
+  { type: 'media_play', label: '播放' },
+  { type: 'media_pause', label: '暫停' },
+  { type: 'apply_profile', label: '套用設定檔', target: '設定檔名稱' },
+];
+
+function buildTrigger(type: Trigger['type'], value: string): Trigger {
+  switch (type) {
+    case 'media_idle':
+      return { type, minutes: Math.max(1, Number(value) || 10) };
+    case 'media_started':
+    case 'media_stopped':
+      return { type, app: value || null };
+    case 'device_added':
+    case 'device_removed':
+    case 'default_device_changed':
+      return { type, device: value };
+    default:
+      return { type, app: value };
+  }
+}
+
+function buildAction(type: Action['type'], target: string, value: string): Action {
+  switch (type) {
+    case 'set_session_volume':
+      return { type, session_id: target, volume: (Number(value) || 0) / 100 };
+    case 'set_session_mute':
+      return { type, session_id: target, mute: true };
+    case 'set_default_device':
+      return { type, device_id: target || null };
+    case 'set_default_device_volume':
+      return { type, volume: (Number(value) || 0) / 100 };
+    case 'media_play_pause':
+    case 'media_play':
+    case 'media_pause':
+      return { type, session_id: null };
+    case 'apply_profile':
+      return { type, name: target };
+  }
+}
+
+function describeTrigger(trigger: Trigger): string {
+  const label = TRIGGERS.find(t => t.type === trigger.type)?.label ?? trigger.type;
+  if (trigger.type === 'media_idle') return `${label} ${trigger.minutes} 分鐘`;
+  if ('device' in trigger) return `${label}: ${trigger.device}`;
+  return trigger.app ? `${label}: ${trigger.app}` : label;
+}
+
+interface RuleListProps {
+  rules: Rule[];
+  log: RuleLogEntry[];
+  onSave: (rule: Rule) => Promise<void>;
+  onDelete: (ruleId: string) => Promise<void>;
+}
+
+export function RuleList({ rules, log, onSave, onDelete }: RuleListProps) {
+  const [name, setName] = useState('');
+  const [triggerType, setTriggerType] = useState<Trigger['type']>('session_started');
+  const [triggerValue, setTriggerValue] = useState('');
+  const [actionType, setActionType] = useState<Action['type']>('set_session_volume');
+  const [actionTarget, setActionTarget] = useState('');
+  const [actionValue, setActionValue] = useState('');
+  const [error, setError] = useState('');
+
+  const action = ACTIONS.find(a => a.type === actionType)!;
+
+  const handleAdd = async () => {
+    try {
+      setError('');
+      await onSave({
+        id: '',
+        name: name.trim(),
+        enabled: true,
+        trigger: buildTrigger(triggerType, triggerValue.trim()),
+        actions: [buildAction(actionType, actionTarget.trim(), actionValue.trim())],
+      });
+      setName('');
+      setTriggerValue('');
+      setActionTarget('');
+      setActionValue('');
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  return (
+    <Card className="mb-8 rounded-lg p-6 gap-0">
+      <label className="text-xl font-semibold mb-4 flex items-center gap-2">
+        <Workflow className="w-5 h-5" />
+        自動化規則
+      </label>
+
+      {rules.map((rule) => (
+        <div key={rule.id} className="flex items-center justify-between gap-2 text-sm mb-2">
+          <span className="truncate">
+            {rule.name}
+            <span className="text-xs text-muted-foreground ml-2">{describeTrigger(rule.trigger)}</span>
+          </span>
+          <div className="flex items-center gap-2">
+            <Switch
+              checked={rule.enabled}
+              onCheckedChange={(enabled) => onSave({ ...rule, enabled })}
+            />
+            <Button variant="outline" size="icon" onClick={() => onDelete(rule.id)}>
+              <Trash2 className="w-4 h-4" />
+            </Button>
+          </div>
+        </div>
+      ))}
+
+      <div className="grid grid-cols-1 md:grid-cols-2 gap-2 mt-2">
+        <Input value={name} onChange={(e) => setName(e.target.value)} placeholder="規則名稱" />
+        <div />
+        <Select value={triggerType} onValueChange={(v) => setTriggerType(v as Trigger['type'])}>
+          <SelectTrigger className="w-full">
+            <SelectValue />
+          </SelectTrigger>
+          <SelectContent>
+            {TRIGGERS.map((t) => (
+              <SelectItem key={t.type} value={t.type}>當 {t.label}</SelectItem>
+            ))}
+          </SelectContent>
+        </Select>
+        <Input
+          value={triggerValue}
+          onChange={(e) => setTriggerValue(e.target.value)}
+          placeholder={TRIGGERS.find(t => t.type === triggerType)?.placeholder}
+        />
+        <Select value={actionType} onValueChange={(v) => setActionType(v as Action['type'])}>
+          <SelectTrigger className="w-full">
+            <SelectValue />
+          </SelectTrigger>
+          <SelectContent>
+            {ACTIONS.map((a) => (
+              <SelectItem key={a.type} value={a.type}>則 {a.label}</SelectItem>
+            ))}
+          </SelectContent>
+        </Select>
+        <div className="flex gap-2">
+          {action.target && (
+            <Input
+              value={actionTarget}
+              onChange={(e) => setActionTarget(e.target.value)}
+              placeholder={action.target}
+              className="flex-1"
+            />
+          )}
+          {action.value && (
+            <Input
+              value={actionValue}
+              onChange={(e) => setActionValue(e.target.value.replace(/\D/g, '').slice(0, 3))}
+              inputMode="numeric"
+              placeholder={action.value}
+              className="w-24"
+            />
+          )}
+        </div>
+      </div>
+      <Button onClick={handleAdd} disabled={!name.trim()} className="mt-2 self-end">
+        新增規則
+      </Button>
+      {error && <p className="text-sm text-red-500 mt-2">{error}</p>}
+
+      {log.length > 0 && (
+        <div className="mt-4 space-y-1">
+          <label className="text-sm font-medium">最近的執行紀錄</label>
+          {log.slice(-5).reverse().map((entry) => (
+            <p
+              key={`${entry.timestamp}-${entry.rule_id}`}
+              className={`text-xs ${entry.success ? 'text-muted-foreground' : 'text-red-500'}`}
+              title={entry.errors.join('\n')}
+            >
+              {new Date(entry.timestamp).toLocaleTimeString()} {entry.rule_name} ({entry.event})
+              {!entry.success && ` 失敗 ${entry.errors.length} 個動作`}
+            </p>
+          ))}
+        </div>
+      )}
+    </Card>
+  );
+}
@@ -2,20 +2,33 @@
 
 import { Volume2, VolumeX } from 'lucide-react';
 import { Card } from '@/components/ui/card';
+import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '@/components/ui/select';
 import { Slider } from '@/components/ui/slider';
 import { Switch } from '@/components/ui/switch';
-import { AudioSession } from '@/models/home';
+import { AudioDevice, AudioSession } from '@/models/home';
+
+/** 選單中代表跟隨系統預設裝置的值 */
+const FOLLOW_DEFAULT = 'default';
 
 interface SessionListProps {
   sessions: AudioSession[];
-  onVolumeChange: (sessionName: string, volume: number) => void;
-  onMuteToggle: (sessionName: string, currentMuted: boolean) => void;
+  /** 各會話目前的峰值 (0 ~ 1)，以會話 ID 為索引 */
+  levels: Record<string, number>;
+  /** 可選擇的輸出裝置 */
+  devices: AudioDevice[];
+  onVolumeChange: (sessionId: string, volume: number) => void;
+  onMuteToggle: (sessionId: string, currentMuted: boolean) => void;
+  /** deviceId 未指定時改回跟隨系統預設裝置 */
+  onDeviceChange: (sessionId: string, deviceId?: string) => void;
 }
 
 export function SessionList({
   sessions,
+  levels,
+  devices,
   onVolumeChange,
   onMuteToggle,
+  onDeviceChange,
 }: SessionListProps) {
   if (sessions.length === 0) {
     return (
@@ -27,13 +40,13 @@ export function SessionList({
 
   return (
     <div className="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-3 gap-4">
-      {sessions.map((session, index) => (
+      {sessions.map((session) => (
         <Card
-          key={`${session.name}-${index}`}
+          key={session.id}
           className="rounded-lg p-6 shadow-lg gap-0"
         >
           <div className="mb-4 flex items-center justify-between gap-3">
-            <h2 className="text-lg font-semibold truncate flex-1 min-w-0" title={session.name}>
+            <h2 className="text-lg font-semibold truncate flex-1 min-w-0" title={session.path || session.name}>
               {session.name}
             </h2>
             <div className="flex items-center gap-2">
@@ -44,7 +57,7 @@ export function SessionList({
               )}
               <Switch
                 checked={!session.is_muted}
-                onCheckedChange={() => onMuteToggle(session.name, session.is_muted)}
+                onCheckedChange={() => onMuteToggle(session.id, session.is_muted)}
               />
             </div>
           </div>
@@ -56,13 +69,40 @@ export function SessionList({
             </div>
             <Slider
               value={[Math.round(session.volume * 100)]}
-              onValueChange={(value) => onVolumeChange(session.name, value[0])}
+              onValueChange={(value) => onVolumeChange(session.id, value[0])}
               min={0}
               max={100}
               step={1}
               className="w-full"
             />
+            <div className="h-1 mt-3 rounded-full bg-muted overflow-hidden">
+              <div
+                className="h-full bg-green-600 transition-[width] duration-100"
+                style={{ width: `${Math.round((levels[session.id] ?? 0) * 100)}%` }}
+              />
+            </div>
           </div>
+
+          {devices.length > 1 && (
+            <Select
+              value={session.device_id || FOLLOW_DEFAULT}
+              onValueChange={(value) =>
+                onDeviceChange(session.id, value === FOLLOW_DEFAULT ? undefined : value)
+              }
+            >
+              <SelectTrigger className="w-full mt-4" size="sm">
+                <SelectValue placeholder="輸出裝置" />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value={FOLLOW_DEFAULT}>跟隨系統預設</SelectItem>
+                {devices.map((device) => (
+                  <SelectItem key={device.id} value={device.id}>
+                    {device.name} {device.is_default ? '(默認)' : ''}
+                  </SelectItem>
+                ))}
+              </SelectContent>
+            </Select>
+          )}
         </Card>
       ))}
     </div>
@@ -1,24 +1,49 @@
 'use client';
 
 import { useEffect, useState, useRef } from 'react';
-import { audioController } from '@/lib/api';
+import { audioController, DeviceEvent } from '@/lib/api';
 import { Button } from '@/components/ui/button';
 import { RefreshCcw } from 'lucide-react';
-import { AudioSession, AudioDevice } from '@/models/home';
-import { MediaInfo } from '@/models/media';
+import { AudioSession, AudioDevice, AudioProfile, AudioLevels, DeviceVolume } from '@/models/home';
+import { MediaInfo, RepeatMode } from '@/models/media';
 import { MediaPlayer } from './_components/media-player';
 import { DeviceSelector } from './_components/device-selector';
+import { MicrophoneSelector } from './_components/microphone-selector';
 import { SearchBar } from './_components/search-bar';
 import { SessionList } from './_components/session-list';
-
+import { PairingCard } from './_components/pairing-card';
+import { ProfileSelector } from './_components/profile-selector';
+import { RuleList } from './_components/rule-list';
+import { Rule, RuleLogEntry } from '@/models/rules';
+
+
+/**
+ * 套用預設裝置變更，未帶角色的舊版伺服器視為一般用途
+ */
+function applyDefaultDeviceChange(devices: AudioDevice[], { device_id, role = 'console' }: DeviceEvent): AudioDevice[] {
+  return devices.map(d => {
+    const roles = (d.default_roles ?? []).filter(r => r !== role);
+    if (d.id === device_id) roles.push(role);
+    return { ...d, default_roles: roles, is_default: roles.includes('console') };
+  });
+}
 
 export default function Home() {
   const [sessions, setSessions] = useState<AudioSession[]>([]);
+  const [levels, setLevels] = useState<AudioLevels | null>(null);
   const [devices, setDevices] = useState<AudioDevice[]>([]);
+  const [inactiveDevices, setInactiveDevices] = useState<AudioDevice[]>([]);
+  const [microphones, setMicrophones] = useState<AudioDevice[]>([]);
+  const [deviceVolumes, setDeviceVolumes] = useState<Record<string, DeviceVolume>>({});
+  const [profiles, setProfiles] = useState<AudioProfile[]>([]);
+  const [rules, setRules] = useState<Rule[]>([]);
+  const [ruleLog, setRuleLog] = useState<RuleLogEntry[]>([]);
   const [error, setError] = useState<string>('');
   const [loading, setLoading] = useState(true);
   const [defaultDeviceVolume, setDefaultDeviceVolume] = useState<number>(100);
   const [defaultDeviceMuted, setDefaultDeviceMuted] = useState<boolean>(false);
+  const [microphoneVolume, setMicrophoneVolume] = useState<number>(100);
+  const [microphoneMuted, setMicrophoneMuted] = useState<boolean>(false);
   const [mediaInfo, setMediaInfo] = useState<MediaInfo | null>(null);
   const [allMediaSessions, setAllMediaSessions] = useState<MediaInfo[]>([]);
   const [selectedSessionId, setSelectedSessionId] = useState<string>('');
@@ -43,11 +68,48 @@ export default function Home() {
 
   const loadDevices = async () => {
     try {
-      const result = await audioController.getAudioDevices();
+      // 未接上或停用的裝置另外列出，不能設為預設裝置
+      const all = await audioController.getAudioDevices('render', true);
+      const result = all.filter(d => (d.state ?? 'active') === 'active');
       setDevices(result);
+      setInactiveDevices(all.filter(d => (d.state ?? 'active') !== 'active'));
+
+      // 預設裝置的音量另外讀取，這裡只需要其他裝置
+      const volumes = await Promise.all(
+        result.filter(d => !d.is_default).map(async (d) => [d.id, {
+          volume: await audioController.getDeviceVolume(d.id),
+          is_muted: await audioController.getDeviceMute(d.id),
+        }] as const)
+      );
+      setDeviceVolumes(Object.fromEntries(volumes));
     } catch (err) {
       console.error('Failed to load audio devices:', err);
     }
+
+    try {
+      setMicrophones(await audioController.getAudioDevices('capture'));
+    } catch (err) {
+      console.error('Failed to load microphones:', err);
+    }
+  };
+
+  const loadProfiles = async () => {
+    try {
+      setProfiles(await audioController.getAudioProfiles());
+    } catch (err) {
+      console.error('Failed to load audio profiles:', err);
+    }
+  };
+
+  const loadRules = async () => {
+    if (audioController.getConnectionMode() !== 'tauri') return;
+
+    try {
+      setRules(await audioController.getRules());
+      setRuleLog(await audioController.getRuleLog());
+    } catch (err) {
+      console.error('Failed to load rules:', err);
+    }
   };
 
   const loadDefaultDeviceVolume = async () => {
@@ -60,6 +122,15 @@ export default function Home() {
     } catch (err) {
       console.error('Failed to load default device volume:', err);
     }
+
+    try {
+      const volume = await audioController.getDefaultDeviceVolume('capture');
+      setMicrophoneVolume(Math.round(volume * 100));
+      setMicrophoneMuted(await audioController.getDefaultDeviceMute('capture'));
+    } catch (err) {
+      // 沒有麥克風時取不到預設裝置
+      console.error('Failed to load microphone volume:', err);
+    }
   };
 
   const loadAllMediaSessions = async () => {
@@ -124,18 +195,22 @@ export default function Home() {
             const titleChanged = prev.title !== selected.title;
             const artistChanged = prev.artist !== selected.artist;
             const albumChanged = prev.album !== selected.album;
+            const artworkChanged = prev.artwork_hash !== selected.artwork_hash;
             const isPlayingChanged = prev.is_playing !== selected.is_playing;
             const appNameChanged = prev.app_name !== selected.app_name;
             const canGoNextChanged = prev.can_go_next !== selected.can_go_next;
             const canGoPreviousChanged = prev.can_go_previous !== selected.can_go_previous;
 
-            const hasChanged = titleChanged || artistChanged || albumChanged || isPlayingChanged || appNameChanged || canGoNextChanged || canGoPreviousChanged;
+            const hasChanged = titleChanged || artistChanged || albumChanged || artworkChanged || isPlayingChanged || appNameChanged || canGoNextChanged || canGoPreviousChanged;
 
             if (!hasChanged) {
               return prev;
             }
 
-            if (titleChanged || artistChanged || albumChanged) {
+            if (artworkChanged) {
+              if (!selected.artwork_url) {
+                return { ...selected, thumbnail: null };
+              }
               loadMediaThumbnail(selectedSessionId);
               return { ...selected, thumbnail: prev.thumbnail };
             }
@@ -183,7 +258,7 @@ export default function Home() {
           setAllMediaSessions(result);
           setMediaInfo(prev  => {
             loadMediaThumbnail(selectedSessionIdRef.current);
-            return { ...selected, thumbnail: prev?.thumbnail as string | null };
+            return { ...selected, thumbnail: prev?.thumbnail ?? null };
           });
         } else if (attemptCount >= maxAttempts) {
           if (pollingIntervalRef.current) {
@@ -202,6 +277,9 @@ export default function Home() {
       const targetSessionId = sessionId || selectedSessionIdRef.current;
       if (!targetSessionId) return;
 
+      // 透過 WebSocket 連線時封面直接以 HTTP 網址載入
+      if (audioController.getConnectionMode() === 'websocket') return;
+
       const thumbnail = await audioController.getMediaThumbnail(targetSessionId);
 
       if (thumbnail) {
@@ -281,9 +359,57 @@ export default function Home() {
       });
   };
 
+  const handleMediaStop = () => {
+    audioController.mediaStop(selectedSessionIdRef.current || undefined)
+      .then(() => {
+        setTimeout(() => startPollingForData(), 300);
+      })
+      .catch((err) => {
+        console.error('Failed to stop media:', err);
+      });
+  };
+
+  const handleMediaShuffleChange = (shuffle: boolean) => {
+    audioController.mediaSetShuffle(shuffle, selectedSessionIdRef.current || undefined)
+      .then(() => {
+        setTimeout(() => startPollingForData(), 300);
+      })
+      .catch((err) => {
+        console.error('Failed to set shuffle:', err);
+      });
+  };
+
+  const handleMediaRepeatChange = (repeat: RepeatMode) => {
+    audioController.mediaSetRepeat(repeat, selectedSessionIdRef.current || undefined)
+      .then(() => {
+        setTimeout(() => startPollingForData(), 300);
+      })
+      .catch((err) => {
+        console.error('Failed to set repeat mode:', err);
+      });
+  };
+
+  const handleMediaRateChange = (rate: number) => {
+    audioController.mediaSetRate(rate, selectedSessionIdRef.current || undefined)
+      .then(() => {
+        setTimeout(() => startPollingForData(), 300);
+      })
+      .catch((err) => {
+        console.error('Failed to set playback rate:', err);
+      });
+  };
+
+  const handleMediaSeek = (positionMs: number) => {
+    audioController.mediaSeek(positionMs, selectedSessionIdRef.current || undefined)
+      .catch((err) => {
+        console.error('Failed to seek:', err);
+      });
+  };
+
   const handleSetDefaultDevice = async (deviceId: string) => {
     try {
-      await audioController.setDefaultDevice(deviceId);
+      // 明確指定角色，避免覆蓋另外選擇的通訊裝置
+      await audioController.setDefaultDevice(deviceId, ['console', 'multimedia']);
       await loadDevices();
     } catch (err) {
       console.error('Failed to set default device:', err);
@@ -291,28 +417,70 @@ export default function Home() {
     }
   };
 
-  const handleVolumeChange = async (sessionName: string, volume: number) => {
+  const handleDeviceVolumeChange = async (deviceId: string, volume: number) => {
+    try {
+      await audioController.setDeviceVolume(deviceId, volume / 100);
+      setDeviceVolumes(prev => ({ ...prev, [deviceId]: { ...prev[deviceId], volume: volume / 100 } }));
+    } catch (err) {
+      console.error('無法設定裝置音量:', err);
+    }
+  };
+
+  const handleDeviceMuteToggle = async (deviceId: string) => {
+    try {
+      const muted = !deviceVolumes[deviceId]?.is_muted;
+      await audioController.setDeviceMute(deviceId, muted);
+      setDeviceVolumes(prev => ({ ...prev, [deviceId]: { ...prev[deviceId], is_muted: muted } }));
+    } catch (err) {
+      console.error('無法切換裝置靜音:', err);
+    }
+  };
+
+  const handleSetCommunicationsDevice = async (deviceId: string) => {
     try {
-      await audioController.setSessionVolume(sessionName, volume / 100);
+      await audioController.setDefaultDevice(deviceId, ['communications']);
+      await loadDevices();
+    } catch (err) {
+      console.error('Failed to set communications device:', err);
+      alert(`設定通訊裝置失敗: ${err}`);
+    }
+  };
+
+  const handleVolumeChange = async (sessionId: string, volume: number) => {
+    try {
+      await audioController.setSessionVolume(sessionId, volume / 100);
       setSessions(sessions.map(s =>
-        s.name === sessionName ? { ...s, volume: volume / 100 } : s
+        s.id === sessionId ? { ...s, volume: volume / 100 } : s
       ));
     } catch (err) {
       console.error('無法設定音量:', err);
     }
   };
 
-  const handleMuteToggle = async (sessionName: string, currentMuted: boolean) => {
+  const handleMuteToggle = async (sessionId: string, currentMuted: boolean) => {
     try {
-      await audioController.setSessionMute(sessionName, !currentMuted);
+      await audioController.setSessionMute(sessionId, !currentMuted);
         setSessions(sessions.map(s =>
-          s.name === sessionName ? { ...s, is_muted: !currentMuted } : s
+          s.id === sessionId ? { ...s, is_muted: !currentMuted } : s
         ));
     } catch (err) {
       console.error('無法切換靜音:', err);
     }
   };
 
+  const handleSessionDeviceChange = async (sessionId: string, deviceId?: string) => {
+    try {
+      await audioController.setSessionDevice(sessionId, deviceId);
+      const device = deviceId ?? devices.find(d => d.is_default)?.id;
+      setSessions(sessions.map(s =>
+        s.id === sessionId ? { ...s, device_id: device } : s
+      ));
+    } catch (err) {
+      console.error('無法切換輸出裝置:', err);
+      alert(`切換輸出裝置失敗: ${err}`);
+    }
+  };
+
   const handleDefaultDeviceVolumeChange = async (volume: number) => {
     try {
       await audioController.setDefaultDeviceVolume(volume / 100);
@@ -332,6 +500,23 @@ export default function Home() {
     }
   };
 
+  const handleMicrophoneVolumeChange = async (volume: number) => {
+    try {
+      await audioController.setDefaultDeviceVolume(volume / 100, 'capture');
+      setMicrophoneVolume(volume);
+    } catch (err) {
+      console.error('無法設定麥克風音量:', err);
+    }
+  };
+
+  const handleMicrophoneMuteToggle = async () => {
+    try {
+      setMicrophoneMuted(await audioController.toggleMicrophoneMute());
+    } catch (err) {
+      console.error('無法切換麥克風靜音:', err);
+    }
+  };
+
   const handleSessionSelect = (sessionId: string) => {
     selectedSessionIdRef.current = sessionId;
     setSelectedSessionId(sessionId);
@@ -364,7 +549,8 @@ export default function Home() {
     const initConnection = async () => {
       if (audioController.getConnectionMode() === 'websocket' && !audioController.isConnected()) {
         try {
-          const url = 'ws://eazycontroller.local:8800/ws'
+          // 端口可能因設定或衝突而改變，直接連回提供此頁面的伺服器
+          const url = `ws://${window.location.host}/ws`;
 
           let connected = false;
           const lastError: Error | null = null;
@@ -392,6 +578,8 @@ export default function Home() {
             loadDevices(),
             loadDefaultDeviceVolume(),
             loadMediaInfo(),
+            loadProfiles(),
+            loadRules(),
           ]),
           new Promise((_, reject) =>
             setTimeout(() => {
@@ -411,16 +599,24 @@ export default function Home() {
 
     initConnection();
 
-    const mediaRefreshInterval = setInterval(() => {
-      loadAllMediaSessions();
-    }, 2000);
+    const ruleLogInterval = setInterval(() => {
+      loadRules();
+    }, 5000);
 
     let cleanupEventListeners: (() => void) | null = null;
 
     const setupEventListeners = async () => {
       const unlistenMediaInfo = await audioController.onMediaInfoUpdated((mediaInfo) => {
         if (selectedSessionId && mediaInfo.session_id === selectedSessionId) {
-          setMediaInfo(mediaInfo);
+          setMediaInfo(prev => {
+            if (prev && prev.artwork_hash === mediaInfo.artwork_hash) {
+              return { ...mediaInfo, thumbnail: prev.thumbnail };
+            }
+            if (mediaInfo.artwork_url) {
+              loadMediaThumbnail(mediaInfo.session_id);
+            }
+            return mediaInfo;
+          });
         }
 
         setAllMediaSessions(prev => {
@@ -434,7 +630,12 @@ export default function Home() {
         });
       });
 
-      const unlistenThumbnail = await audioController.onMediaThumbnailUpdated(() => {
+      // 播放器開啟或關閉時重新載入，一併處理選擇的來源與封面
+      const unlistenSessionAdded = await audioController.onMediaSessionAdded(() => {
+        loadAllMediaSessions();
+      });
+
+      const unlistenSessionRemoved = await audioController.onMediaSessionRemoved(() => {
         loadAllMediaSessions();
       });
 
@@ -442,17 +643,76 @@ export default function Home() {
         setMediaInfo(null);
       });
 
+      const unlistenTimeline = await audioController.onMediaTimelineChanged(({ session_id, ...timeline }) => {
+        setMediaInfo(prev => prev && prev.session_id === session_id ? { ...prev, ...timeline } : prev);
+        setAllMediaSessions(prev =>
+          prev.map(s => s.session_id === session_id ? { ...s, ...timeline } : s)
+        );
+      });
+
+      const unlistenSessions = await audioController.onAudioSessionsUpdated((sessions) => {
+        setSessions(sessions);
+      });
+
+      const unlistenVolume = await audioController.onDeviceVolumeUpdated(({ volume, is_muted }) => {
+        setDefaultDeviceVolume(Math.round(volume * 100));
+        setDefaultDeviceMuted(is_muted);
+      });
+
+      const unlistenDefaultDevice = await audioController.onDefaultDeviceChanged((event) => {
+        setDevices(prev => applyDefaultDeviceChange(prev, event));
+      });
+
+      const unlistenCaptureVolume = await audioController.onCaptureVolumeUpdated(({ volume, is_muted }) => {
+        setMicrophoneVolume(Math.round(volume * 100));
+        setMicrophoneMuted(is_muted);
+      });
+
+      const unlistenDefaultCapture = await audioController.onDefaultCaptureDeviceChanged((event) => {
+        setMicrophones(prev => applyDefaultDeviceChange(prev, event));
+      });
+
+      const unlistenDeviceAdded = await audioController.onDeviceAdded(() => {
+        loadDevices();
+      });
+
+      const unlistenDeviceRemoved = await audioController.onDeviceRemoved(() => {
+        loadDevices();
+      });
+
+      const unlistenProfiles = audioController.getConnectionMode() === 'tauri'
+        ? await audioController.onAudioProfilesUpdated(() => {
+          loadProfiles();
+        })
+        : () => {};
+
+      const unsubscribeLevels = await audioController.subscribeLevels(setLevels).catch((error) => {
+        console.error('Failed to subscribe to levels:', error);
+        return () => {};
+      });
+
       cleanupEventListeners = () => {
         unlistenMediaInfo();
-        unlistenThumbnail();
+        unlistenSessionAdded();
+        unlistenSessionRemoved();
         unlistenClear();
+        unlistenTimeline();
+        unlistenSessions();
+        unlistenVolume();
+        unlistenDefaultDevice();
+        unlistenCaptureVolume();
+        unlistenDefaultCapture();
+        unlistenDeviceAdded();
+        unlistenDeviceRemoved();
+        unlistenProfiles();
+        unsubscribeLevels();
       };
     };
 
     setupEventListeners();
 
     return () => {
-      clearInterval(mediaRefreshInterval);
+      clearInterval(ruleLogInterval);
       if (cleanupEventListeners) {
         cleanupEventListeners();
       }
@@ -507,6 +767,7 @@ export default function Home() {
                   loadDevices(),
                   loadDefaultDeviceVolume(),
                   loadMediaInfo(),
+                  loadProfiles(),
                 ]);
               } catch (err) {
                 console.error('刷新失敗:', err);
@@ -521,25 +782,79 @@ export default function Home() {
           </Button>
         </div>
 
+        {!audioController.isPaired() && (
+          <PairingCard
+            onPair={async (code) => {
+              await audioController.pair(code);
+              window.location.reload();
+            }}
+          />
+        )}
+
         <MediaPlayer
           mediaInfo={mediaInfo}
+          artworkUrl={mediaInfo ? audioController.getMediaArtworkUrl(mediaInfo) : null}
           allMediaSessions={allMediaSessions}
           selectedSessionId={selectedSessionId}
           onSessionSelect={handleSessionSelect}
           onPlayPause={handleMediaPlayPause}
           onNext={handleMediaNext}
           onPrevious={handleMediaPrevious}
+          onSeek={handleMediaSeek}
+          onStop={handleMediaStop}
+          onShuffleChange={handleMediaShuffleChange}
+          onRepeatChange={handleMediaRepeatChange}
+          onRateChange={handleMediaRateChange}
         />
 
         <DeviceSelector
           devices={devices}
+          inactiveDevices={inactiveDevices}
           defaultDeviceVolume={defaultDeviceVolume}
           defaultDeviceMuted={defaultDeviceMuted}
           onSetDefaultDevice={handleSetDefaultDevice}
+          onSetCommunicationsDevice={handleSetCommunicationsDevice}
           onVolumeChange={handleDefaultDeviceVolumeChange}
           onMuteToggle={handleDefaultDeviceMuteToggle}
+          deviceVolumes={deviceVolumes}
+          onDeviceVolumeChange={handleDeviceVolumeChange}
+          onDeviceMuteToggle={handleDeviceMuteToggle}
+        />
+
+        <MicrophoneSelector
+          devices={microphones}
+          volume={microphoneVolume}
+          muted={microphoneMuted}
+          onSetDefaultDevice={handleSetDefaultDevice}
+          onVolumeChange={handleMicrophoneVolumeChange}
+          onMuteToggle={handleMicrophoneMuteToggle}
         />
 
+        <ProfileSelector
+          profiles={profiles}
+          editable={audioController.getConnectionMode() === 'tauri'}
+          onApply={(name) => audioController.applyAudioProfile(name)}
+          onSave={async (name, hotkey) => {
+            await audioController.saveAudioProfile(name, hotkey);
+          }}
+          onDelete={(name) => audioController.deleteAudioProfile(name)}
+        />
+
+        {audioController.getConnectionMode() === 'tauri' && (
+          <RuleList
+            rules={rules}
+            log={ruleLog}
+            onSave={async (rule) => {
+              await audioController.saveRule(rule);
+              await loadRules();
+            }}
+            onDelete={async (ruleId) => {
+              await audioController.deleteRule(ruleId);
+              await loadRules();
+            }}
+          />
+        )}
+
         <SearchBar
           searchQuery={searchQuery}
           filterType={filterType}
@@ -563,8 +878,11 @@ export default function Home() {
         ) : (
           <SessionList
             sessions={filteredSessions}
+            levels={levels?.sessions ?? {}}
+            devices={devices}
             onVolumeChange={handleVolumeChange}
             onMuteToggle={handleMuteToggle}
+            onDeviceChange={handleSessionDeviceChange}
           />
         )}
       </div>
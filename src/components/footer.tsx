@@ -13,6 +13,9 @@ import {
   DialogTitle,
 } from '@/components/ui/dialog';
 import { Input } from '@/components/ui/input';
+import { Switch } from '@/components/ui/switch';
+import { audioController, PairedDevice, PairingCode, ServerSettings, ServerStatus } from '@/lib/api';
+import { DuckingSettings, VolumeMemory } from '@/models/home';
 
 export default function Footer() {
   const [isExpanded, setIsExpanded] = useState(false);
@@ -25,6 +28,18 @@ export default function Footer() {
   const [savedStandardHotkey, setSavedStandardHotkey] = useState<string>('');
   const [error, setError] = useState<string>('');
   const [isTauri, setIsTauri] = useState(false);
+  const [pairingCode, setPairingCode] = useState<PairingCode | null>(null);
+  const [pairedDevices, setPairedDevices] = useState<PairedDevice[]>([]);
+  const [serverSettings, setServerSettings] = useState<ServerSettings | null>(null);
+  const [serverStatus, setServerStatus] = useState<ServerStatus | null>(null);
+  const [isSavingServer, setIsSavingServer] = useState(false);
+  const [volumeMemory, setVolumeMemory] = useState<VolumeMemory | null>(null);
+  const [excludedInput, setExcludedInput] = useState('');
+  const [duckingSettings, setDuckingSettings] = useState<DuckingSettings | null>(null);
+  const [priorityAppsInput, setPriorityAppsInput] = useState('');
+  const [isDucking, setIsDucking] = useState(false);
+  const [micMuteHotkey, setMicMuteHotkey] = useState('');
+  const [micMuteHotkeyError, setMicMuteHotkeyError] = useState('');
 
   useEffect(() => {
     let checkTauri = false;
@@ -165,6 +180,178 @@ export default function Footer() {
     handleDialogChange();
   }, [isSettingsOpen, savedStandardHotkey, isTauri]);
 
+  useEffect(() => {
+    if (!isTauri || !isSettingsOpen) return;
+
+    const loadPairedDevices = async () => {
+      try {
+        setPairedDevices(await audioController.getPairedDevices());
+      } catch (error) {
+        console.error('Failed to load paired devices:', error);
+      }
+    };
+
+    loadPairedDevices();
+
+    let unlisten: (() => void) | null = null;
+    audioController.onPairedDevicesUpdated(() => {
+      setPairingCode(null);
+      loadPairedDevices();
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [isSettingsOpen, isTauri]);
+
+  useEffect(() => {
+    if (!isTauri) return;
+
+    const loadServer = async () => {
+      try {
+        setServerSettings(await audioController.getServerSettings());
+        setServerStatus(await audioController.getServerStatus());
+      } catch (error) {
+        console.error('Failed to load server settings:', error);
+      }
+    };
+
+    loadServer();
+
+    let unlisten: (() => void) | null = null;
+    audioController.onServerStatusChanged((status) => {
+      setServerStatus(status);
+    }).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [isTauri]);
+
+  const handleSaveServerSettings = async () => {
+    if (!serverSettings) return;
+
+    try {
+      setIsSavingServer(true);
+      setServerStatus(await audioController.saveServerSettings(serverSettings));
+    } catch (error) {
+      setServerStatus({
+        running: false,
+        port: null,
+        urls: [],
+        error: error instanceof Error ? error.message : String(error),
+      });
+    } finally {
+      setIsSavingServer(false);
+    }
+  };
+
+  const loadVolumeMemory = async () => {
+    try {
+      const memory = await audioController.getVolumeMemory();
+      setVolumeMemory(memory);
+      setExcludedInput(memory.excluded.join(', '));
+    } catch (error) {
+      console.error('Failed to load volume memory:', error);
+    }
+  };
+
+  useEffect(() => {
+    if (!isTauri || !isSettingsOpen) return;
+    loadVolumeMemory();
+  }, [isSettingsOpen, isTauri]);
+
+  const handleClearVolumeMemory = async (group?: string) => {
+    try {
+      await audioController.clearVolumeMemory(group);
+      await loadVolumeMemory();
+    } catch (error) {
+      console.error('Failed to clear volume memory:', error);
+    }
+  };
+
+  const handleSaveExcluded = async () => {
+    try {
+      const excluded = excludedInput.split(',').map(e => e.trim()).filter(e => e);
+      await audioController.setVolumeMemoryExcluded(excluded);
+      await loadVolumeMemory();
+    } catch (error) {
+      console.error('Failed to save excluded apps:', error);
+    }
+  };
+
+  useEffect(() => {
+    if (!isTauri || !isSettingsOpen) return;
+
+    Promise.all([audioController.getDuckingSettings(), audioController.getDuckingActive()])
+      .then(([settings, active]) => {
+        setDuckingSettings(settings);
+        setPriorityAppsInput(settings.priority_apps.join(', '));
+        setIsDucking(active);
+      })
+      .catch((error) => console.error('Failed to load ducking settings:', error));
+
+    let unlisten: (() => void) | undefined;
+    audioController.onDuckingChanged(setIsDucking).then((fn) => {
+      unlisten = fn;
+    });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [isSettingsOpen, isTauri]);
+
+  useEffect(() => {
+    if (!isTauri || !isSettingsOpen) return;
+
+    audioController.loadMicMuteHotkey()
+      .then(setMicMuteHotkey)
+      .catch((error) => console.error('Failed to load microphone hotkey:', error));
+  }, [isSettingsOpen, isTauri]);
+
+  const handleSaveMicMuteHotkey = async () => {
+    try {
+      setMicMuteHotkeyError('');
+      await audioController.saveMicMuteHotkey(micMuteHotkey.trim());
+    } catch (error) {
+      setMicMuteHotkeyError(`無法註冊快捷鍵: ${error}`);
+    }
+  };
+
+  const handleSaveDucking = async (settings: DuckingSettings) => {
+    try {
+      const next = {
+        ...settings,
+        priority_apps: priorityAppsInput.split(',').map(e => e.trim()).filter(e => e),
+      };
+      await audioController.saveDuckingSettings(next);
+      setDuckingSettings(next);
+    } catch (error) {
+      console.error('Failed to save ducking settings:', error);
+    }
+  };
+
+  const handleCreatePairingCode = async () => {
+    try {
+      setPairingCode(await audioController.createPairingCode());
+    } catch (error) {
+      console.error('Failed to create pairing code:', error);
+    }
+  };
+
+  const handleRevokeDevice = async (deviceId: string) => {
+    try {
+      await audioController.revokePairedDevice(deviceId);
+      setPairedDevices(prev => prev.filter(d => d.id !== deviceId));
+    } catch (error) {
+      console.error('Failed to revoke device:', error);
+    }
+  };
+
   const handleSaveHotkey = async () => {
     try {
       setError('');
@@ -281,7 +468,7 @@ export default function Footer() {
           <DialogHeader>
             <DialogTitle>設定</DialogTitle>
             <DialogDescription>
-              設定全局快捷鍵來顯示/隱藏應用視窗
+              設定全局快捷鍵、配對可以控制此電腦的裝置與區域網路服務
             </DialogDescription>
           </DialogHeader>
           <div className="space-y-4 py-4">
@@ -325,6 +512,197 @@ export default function Footer() {
                 </span>
               </p>
             </div>
+            <div className="space-y-2">
+              <label className="text-sm font-medium">麥克風靜音快捷鍵</label>
+              <div className="flex gap-2">
+                <Input
+                  value={micMuteHotkey}
+                  onChange={(e) => setMicMuteHotkey(e.target.value)}
+                  placeholder="例如 Ctrl+Alt+M，留空為不使用"
+                  className={`flex-1 ${micMuteHotkeyError ? 'border-red-500' : ''}`}
+                />
+                <Button onClick={handleSaveMicMuteHotkey}>
+                  儲存
+                </Button>
+              </div>
+              {micMuteHotkeyError && (
+                <p className="text-sm text-red-500">{micMuteHotkeyError}</p>
+              )}
+              <p className="text-xs text-muted-foreground">
+                在任何程式中按下即可切換預設麥克風的靜音，也可以從系統匣選單切換
+              </p>
+            </div>
+            <div className="space-y-2">
+              <label className="text-sm font-medium">配對裝置</label>
+              <div className="flex gap-2 items-center">
+                <Input
+                  value={pairingCode?.code ?? ''}
+                  readOnly
+                  placeholder="點擊產生配對碼"
+                  className="flex-1 font-mono tracking-widest"
+                />
+                <Button onClick={handleCreatePairingCode}>
+                  產生
+                </Button>
+              </div>
+              <p className="text-xs text-muted-foreground">
+                在手機上輸入配對碼後即可控制電腦，配對碼 {Math.round((pairingCode?.expires_in ?? 300) / 60)} 分鐘內有效。未配對的裝置只能查看狀態
+              </p>
+              {pairedDevices.map((device) => (
+                <div key={device.id} className="flex items-center justify-between gap-2 text-sm">
+                  <span className="truncate" title={device.name}>
+                    {device.name}
+                    <span className="text-xs text-muted-foreground ml-2">
+                      {new Date(device.paired_at * 1000).toLocaleDateString()}
+                    </span>
+                  </span>
+                  <Button variant="outline" size="sm" onClick={() => handleRevokeDevice(device.id)}>
+                    撤銷
+                  </Button>
+                </div>
+              ))}
+            </div>
+            {volumeMemory && (
+              <div className="space-y-2">
+                <div className="flex items-center justify-between">
+                  <label className="text-sm font-medium">記住程式音量</label>
+                  <Button
+                    variant="outline"
+                    size="sm"
+                    onClick={() => handleClearVolumeMemory()}
+                    disabled={Object.keys(volumeMemory.entries).length === 0}
+                  >
+                    全部清除
+                  </Button>
+                </div>
+                <p className="text-xs text-muted-foreground">
+                  程式重新啟動後會自動套用最後設定的音量與靜音狀態
+                </p>
+                {Object.entries(volumeMemory.entries).map(([group, entry]) => (
+                  <div key={group} className="flex items-center justify-between gap-2 text-sm">
+                    <span className="truncate" title={group}>
+                      {entry.name}
+                      <span className="text-xs text-muted-foreground ml-2">
+                        {entry.is_muted ? '靜音' : `${Math.round(entry.volume * 100)}%`}
+                      </span>
+                    </span>
+                    <Button variant="outline" size="sm" onClick={() => handleClearVolumeMemory(group)}>
+                      清除
+                    </Button>
+                  </div>
+                ))}
+                <div className="flex gap-2">
+                  <Input
+                    value={excludedInput}
+                    onChange={(e) => setExcludedInput(e.target.value)}
+                    placeholder="不記住的程式，例如 chrome.exe, obs64.exe"
+                    className="flex-1"
+                  />
+                  <Button onClick={handleSaveExcluded}>
+                    儲存
+                  </Button>
+                </div>
+              </div>
+            )}
+            {duckingSettings && (
+              <div className="space-y-2">
+                <div className="flex items-center justify-between">
+                  <label className="text-sm font-medium">通話時降低其他程式音量</label>
+                  <Switch
+                    checked={duckingSettings.enabled}
+                    onCheckedChange={(enabled) => handleSaveDucking({ ...duckingSettings, enabled })}
+                  />
+                </div>
+                <p className="text-xs text-muted-foreground">
+                  {isDucking
+                    ? '正在降低音量，通話結束後會還原，期間手動調整的音量會保留'
+                    : '下列程式播放聲音時，其他程式的音量會自動降低'}
+                </p>
+                <div className="flex gap-2">
+                  <Input
+                    value={priorityAppsInput}
+                    onChange={(e) => setPriorityAppsInput(e.target.value)}
+                    placeholder="例如 Teams.exe, Discord.exe"
+                    className="flex-1"
+                  />
+                  <Input
+                    value={Math.round(duckingSettings.amount * 100)}
+                    onChange={(e) => {
+                      const amount = Number(e.target.value.replace(/\D/g, '').slice(0, 3));
+                      setDuckingSettings({ ...duckingSettings, amount: Math.min(amount, 100) / 100 });
+                    }}
+                    inputMode="numeric"
+                    title="降低的比例 (%)"
+                    className="w-20 font-mono"
+                  />
+                  <Button onClick={() => handleSaveDucking(duckingSettings)}>
+                    儲存
+                  </Button>
+                </div>
+              </div>
+            )}
+            {serverSettings && (
+              <div className="space-y-2">
+                <div className="flex items-center justify-between">
+                  <label className="text-sm font-medium">區域網路服務</label>
+                  <Switch
+                    checked={serverSettings.enabled}
+                    onCheckedChange={(enabled) => setServerSettings({ ...serverSettings, enabled })}
+                  />
+                </div>
+                <div className="flex gap-2">
+                  <Input
+                    value={serverSettings.bind_address}
+                    onChange={(e) => setServerSettings({ ...serverSettings, bind_address: e.target.value.trim() })}
+                    disabled={!serverSettings.enabled}
+                    placeholder="0.0.0.0"
+                    className="flex-1 font-mono"
+                  />
+                  <Input
+                    value={serverSettings.port}
+                    onChange={(e) => {
+                      const port = Number(e.target.value.replace(/\D/g, '').slice(0, 5));
+                      setServerSettings({ ...serverSettings, port: Math.min(port, 65535) });
+                    }}
+                    disabled={!serverSettings.enabled}
+                    inputMode="numeric"
+                    className="w-24 font-mono"
+                  />
+                  <Button onClick={handleSaveServerSettings} disabled={isSavingServer}>
+                    儲存
+                  </Button>
+                </div>
+                <div className="flex items-center gap-2 text-sm">
+                  <Switch
+                    checked={serverSettings.ipv6}
+                    onCheckedChange={(ipv6) => setServerSettings({ ...serverSettings, ipv6 })}
+                    disabled={!serverSettings.enabled}
+                  />
+                  同時監聽 IPv6
+                </div>
+                {serverStatus?.error && (
+                  <div className="text-sm text-red-500 flex items-start gap-2 p-2 bg-red-50 dark:bg-red-950/20 rounded-md border border-red-200 dark:border-red-900">
+                    <X className="h-4 w-4 mt-0.5 shrink-0" />
+                    <span>{serverStatus.error}</span>
+                  </div>
+                )}
+                {serverStatus?.running && (
+                  <p className="text-xs text-muted-foreground">
+                    {serverStatus.port !== serverSettings.port && (
+                      <>端口 {serverSettings.port} 已被占用，改用 {serverStatus.port}<br /></>
+                    )}
+                    {serverStatus.urls.map((url) => (
+                      <span key={url} className="block font-mono">{url}</span>
+                    ))}
+                  </p>
+                )}
+                {!serverSettings.enabled && (
+                  <p className="text-xs text-muted-foreground">
+                    關閉後手機將無法連線
+                  </p>
+                )}
+              </div>
+            )}
           </div>
         </DialogContent>
       </Dialog>
@@ -1,11 +1,51 @@
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { WebSocketClient } from './websocket';
-import { AudioSession, AudioDevice } from '@/models/home';
-import { MediaInfo } from '@/models/media';
+import { AudioSession, AudioDevice, AudioProfile, ApplyProfileResult, VolumeMemory, DuckingSettings, AudioLevels, DeviceDirection, DeviceRole } from '@/models/home';
+import { MediaInfo, MediaTimeline, RepeatMode } from '@/models/media';
+import { Rule, RuleLogEntry } from '@/models/rules';
 
 export type EventCallback<T> = (data: T) => void;
 
+export interface DeviceVolumeEvent {
+  volume: number;
+  is_muted: boolean;
+}
+
+export interface DeviceEvent {
+  device_id: string;
+  direction?: DeviceDirection;
+  /** 預設裝置變更時的角色 */
+  role?: DeviceRole;
+}
+
+export interface PairingCode {
+  code: string;
+  expires_in: number;
+}
+
+export interface PairedDevice {
+  id: string;
+  name: string;
+  paired_at: number;
+}
+
+export interface ServerSettings {
+  enabled: boolean;
+  bind_address: string;
+  port: number;
+  ipv6: boolean;
+}
+
+export interface ServerStatus {
+  running: boolean;
+  port: number | null;
+  urls: string[];
+  error: string | null;
+}
+
+const TOKEN_STORAGE_KEY = 'eazy_controller_token';
+
 class AudioControllerAPI {
   private wsClient: WebSocketClient | null = null;
   private wsUrl: string = '';
@@ -30,10 +70,97 @@ class AudioControllerAPI {
     }
 
     this.wsUrl = url;
+
+    const token = this.getToken();
+    if (token) {
+      try {
+        this.wsClient = new WebSocketClient(`${url}?token=${encodeURIComponent(token)}`);
+        await this.wsClient.connect();
+        return;
+      } catch (err) {
+        // 配對已被撤銷時伺服器會拒絕連線，改以唯讀模式連線
+        console.warn('配對已失效，改以唯讀模式連線:', err);
+        this.wsClient?.disconnect();
+        localStorage.removeItem(TOKEN_STORAGE_KEY);
+      }
+    }
+
     this.wsClient = new WebSocketClient(url);
     await this.wsClient.connect();
   }
 
+  private getToken(): string | null {
+    if (typeof window === 'undefined') return null;
+    return localStorage.getItem(TOKEN_STORAGE_KEY);
+  }
+
+  /**
+   * 是否已配對，Tauri 本機視為已配對
+   */
+  isPaired(): boolean {
+    return this.isTauri || this.getToken() !== null;
+  }
+
+  /**
+   * 以電腦上顯示的配對碼換取 token，需重新連線後生效
+   */
+  async pair(code: string): Promise<void> {
+    if (!this.wsUrl) throw new Error('WebSocket 未初始化');
+
+    const pairUrl = this.wsUrl.replace(/^ws/, 'http').replace(/\/ws$/, '/pair');
+    const response = await fetch(pairUrl, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ code }),
+    });
+    const result = await response.json();
+
+    if (!response.ok) {
+      throw new Error(result.message || '配對失敗');
+    }
+
+    localStorage.setItem(TOKEN_STORAGE_KEY, result.token);
+  }
+
+  async createPairingCode(): Promise<PairingCode> {
+    return invoke<PairingCode>('create_pairing_code');
+  }
+
+  async getPairedDevices(): Promise<PairedDevice[]> {
+    return invoke<PairedDevice[]>('get_paired_devices');
+  }
+
+  async revokePairedDevice(deviceId: string): Promise<void> {
+    return invoke('revoke_paired_device', { deviceId });
+  }
+
+  async onPairedDevicesUpdated(callback: EventCallback<void>): Promise<() => void> {
+    return listen('paired-devices-updated', () => {
+      callback();
+    });
+  }
+
+  async getServerSettings(): Promise<ServerSettings> {
+    return invoke<ServerSettings>('get_server_settings');
+  }
+
+  /**
+   * 儲存區域網路服務設定，服務會以新設定重新啟動
+   */
+  async saveServerSettings(settings: ServerSettings): Promise<ServerStatus> {
+    return invoke<ServerStatus>('save_server_settings', { settings });
+  }
+
+  async getServerStatus(): Promise<ServerStatus> {
+    return invoke<ServerStatus>('get_server_status');
+  }
+
+  async onServerStatusChanged(callback: EventCallback<ServerStatus>): Promise<() => void> {
+    return listen<ServerStatus>('server-status-changed', (event) => {
+      callback(event.payload);
+    });
+  }
+
   /**
    * 檢查連接狀態
    */
@@ -51,86 +178,279 @@ class AudioControllerAPI {
     return this.isTauri ? 'tauri' : 'websocket';
   }
 
-  async getAudioSessions(): Promise<AudioSession[]> {
+  async getAudioSessions(grouped: boolean = false): Promise<AudioSession[]> {
     if (this.isTauri) {
-      const result = await invoke<AudioSession[]>('get_audio_sessions');
+      const result = await invoke<AudioSession[]>('get_audio_sessions', { grouped });
       return result;
     } else {
       if (!this.wsClient) throw new Error('WebSocket 未初始化');
-      return this.wsClient.send('get_audio_sessions') as Promise<AudioSession[]>;
+      return this.wsClient.send('get_audio_sessions', { grouped }) as Promise<AudioSession[]>;
+    }
+  }
+
+  async setSessionVolume(sessionId: string, volume: number): Promise<void> {
+    if (this.isTauri) {
+      return invoke('set_session_volume', { sessionId, volume });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      await this.wsClient.send('set_session_volume', { session_id: sessionId, volume });
     }
   }
 
-  async setSessionVolume(sessionName: string, volume: number): Promise<void> {
+  async setSessionMute(sessionId: string, mute: boolean): Promise<void> {
     if (this.isTauri) {
-      return invoke('set_session_volume', { sessionName, volume });
+      return invoke('set_session_mute', { sessionId, mute });
     } else {
       if (!this.wsClient) throw new Error('WebSocket 未初始化');
-      await this.wsClient.send('set_session_volume', { session_name: sessionName, volume });
+      await this.wsClient.send('set_session_mute', { session_id: sessionId, mute });
     }
   }
 
-  async setSessionMute(sessionName: string, mute: boolean): Promise<void> {
+  /**
+   * 將程式改用指定的輸出裝置，不指定裝置時改回跟隨系統預設裝置
+   */
+  async setSessionDevice(sessionId: string, deviceId?: string): Promise<void> {
     if (this.isTauri) {
-      return invoke('set_session_mute', { sessionName, mute });
+      return invoke('set_session_device', { sessionId, deviceId: deviceId ?? null });
     } else {
       if (!this.wsClient) throw new Error('WebSocket 未初始化');
-      await this.wsClient.send('set_session_mute', { session_name: sessionName, mute });
+      await this.wsClient.send('set_session_device', { session_id: sessionId, device_id: deviceId ?? null });
     }
   }
 
-  async getAudioDevices(): Promise<AudioDevice[]> {
+  /**
+   * 列出輸出裝置，或指定 'capture' 列出麥克風
+   */
+  async getAudioDevices(direction: DeviceDirection = 'render', includeInactive: boolean = false): Promise<AudioDevice[]> {
     if (this.isTauri) {
-      const result = await invoke<AudioDevice[]>('get_audio_devices');
+      const result = await invoke<AudioDevice[]>('get_audio_devices', { direction, includeInactive });
       return result;
     } else {
       if (!this.wsClient) throw new Error('WebSocket 未初始化');
-      return this.wsClient.send('get_audio_devices') as Promise<AudioDevice[]>;
+      return this.wsClient.send('get_audio_devices', { direction, include_inactive: includeInactive }) as Promise<AudioDevice[]>;
     }
   }
 
-  async setDefaultDevice(deviceId: string): Promise<void> {
+  /**
+   * 設定預設裝置，未指定角色時為一般與多媒體，不影響通訊裝置
+   */
+  async setDefaultDevice(deviceId: string, roles?: DeviceRole[]): Promise<void> {
     if (this.isTauri) {
-      return invoke('set_default_device', { deviceId });
+      return invoke('set_default_device', { deviceId, roles });
     } else {
       if (!this.wsClient) throw new Error('WebSocket 未初始化');
-      await this.wsClient.send('set_default_device', { device_id: deviceId });
+      await this.wsClient.send('set_default_device', { device_id: deviceId, roles });
     }
   }
 
-  async getDefaultDeviceVolume(): Promise<number> {
+  async getDefaultDeviceVolume(direction: DeviceDirection = 'render'): Promise<number> {
     if (this.isTauri) {
-      return invoke<number>('get_default_device_volume');
+      return invoke<number>('get_default_device_volume', { direction });
     } else {
       if (!this.wsClient) throw new Error('WebSocket 未初始化');
-      return this.wsClient.send('get_default_device_volume') as Promise<number>;
+      return this.wsClient.send('get_default_device_volume', { direction }) as Promise<number>;
     }
   }
 
-  async setDefaultDeviceVolume(volume: number): Promise<void> {
+  async setDefaultDeviceVolume(volume: number, direction: DeviceDirection = 'render'): Promise<void> {
     if (this.isTauri) {
-      return invoke('set_default_device_volume', { volume });
+      return invoke('set_default_device_volume', { volume, direction });
     } else {
       if (!this.wsClient) throw new Error('WebSocket 未初始化');
-      await this.wsClient.send('set_default_device_volume', { volume });
+      await this.wsClient.send('set_default_device_volume', { volume, direction });
     }
   }
 
-  async getDefaultDeviceMute(): Promise<boolean> {
+  async getDefaultDeviceMute(direction: DeviceDirection = 'render'): Promise<boolean> {
     if (this.isTauri) {
-      return invoke<boolean>('get_default_device_mute');
+      return invoke<boolean>('get_default_device_mute', { direction });
     } else {
       if (!this.wsClient) throw new Error('WebSocket 未初始化');
-      return this.wsClient.send('get_default_device_mute') as Promise<boolean>;
+      return this.wsClient.send('get_default_device_mute', { direction }) as Promise<boolean>;
     }
   }
 
-  async setDefaultDeviceMute(mute: boolean): Promise<void> {
+  async setDefaultDeviceMute(mute: boolean, direction: DeviceDirection = 'render'): Promise<void> {
     if (this.isTauri) {
-      return invoke('set_default_device_mute', { mute });
+      return invoke('set_default_device_mute', { mute, direction });
     } else {
       if (!this.wsClient) throw new Error('WebSocket 未初始化');
-      await this.wsClient.send('set_default_device_mute', { mute });
+      await this.wsClient.send('set_default_device_mute', { mute, direction });
+    }
+  }
+
+  /**
+   * 讀取任一裝置的音量，不需要先設為預設裝置
+   */
+  async getDeviceVolume(deviceId: string): Promise<number> {
+    if (this.isTauri) {
+      return invoke<number>('get_device_volume', { deviceId });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      return this.wsClient.send('get_device_volume', { device_id: deviceId }) as Promise<number>;
+    }
+  }
+
+  async setDeviceVolume(deviceId: string, volume: number): Promise<void> {
+    if (this.isTauri) {
+      return invoke('set_device_volume', { deviceId, volume });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      await this.wsClient.send('set_device_volume', { device_id: deviceId, volume });
+    }
+  }
+
+  async getDeviceMute(deviceId: string): Promise<boolean> {
+    if (this.isTauri) {
+      return invoke<boolean>('get_device_mute', { deviceId });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      return this.wsClient.send('get_device_mute', { device_id: deviceId }) as Promise<boolean>;
+    }
+  }
+
+  async setDeviceMute(deviceId: string, mute: boolean): Promise<void> {
+    if (this.isTauri) {
+      return invoke('set_device_mute', { deviceId, mute });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      await this.wsClient.send('set_device_mute', { device_id: deviceId, mute });
+    }
+  }
+
+  /**
+   * 切換預設麥克風的靜音，回傳切換後是否靜音
+   */
+  async toggleMicrophoneMute(): Promise<boolean> {
+    if (this.isTauri) {
+      return invoke<boolean>('toggle_microphone_mute');
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      return this.wsClient.send('toggle_microphone_mute') as Promise<boolean>;
+    }
+  }
+
+  async loadMicMuteHotkey(): Promise<string> {
+    return invoke<string>('load_mic_mute_hotkey');
+  }
+
+  /**
+   * 儲存麥克風靜音快捷鍵並立即註冊，空字串表示不使用
+   */
+  async saveMicMuteHotkey(hotkey: string): Promise<void> {
+    return invoke('save_mic_mute_hotkey', { hotkey });
+  }
+
+  async getAudioProfiles(): Promise<AudioProfile[]> {
+    if (this.isTauri) {
+      return invoke<AudioProfile[]>('get_audio_profiles');
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      return this.wsClient.send('get_profiles') as Promise<AudioProfile[]>;
+    }
+  }
+
+  /**
+   * 套用設定檔，回傳目前沒有在執行、無法套用的程式
+   */
+  async applyAudioProfile(name: string): Promise<ApplyProfileResult> {
+    if (this.isTauri) {
+      return invoke<ApplyProfileResult>('apply_audio_profile', { name });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      return this.wsClient.send('apply_profile', { name }) as Promise<ApplyProfileResult>;
+    }
+  }
+
+  /**
+   * 將目前的混音器狀態儲存為設定檔，只能在電腦上使用
+   */
+  async saveAudioProfile(name: string, hotkey: string | null = null): Promise<AudioProfile> {
+    return invoke<AudioProfile>('save_audio_profile', { name, hotkey });
+  }
+
+  async deleteAudioProfile(name: string): Promise<void> {
+    return invoke('delete_audio_profile', { name });
+  }
+
+  async onAudioProfilesUpdated(callback: EventCallback<void>): Promise<() => void> {
+    return listen('audio-profiles-updated', () => {
+      callback();
+    });
+  }
+
+  async getVolumeMemory(): Promise<VolumeMemory> {
+    if (this.isTauri) {
+      return invoke<VolumeMemory>('get_volume_memory');
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      return this.wsClient.send('get_volume_memory') as Promise<VolumeMemory>;
+    }
+  }
+
+  /**
+   * 清除記住的音量，未指定 group 時清除全部
+   */
+  async clearVolumeMemory(group?: string): Promise<void> {
+    if (this.isTauri) {
+      return invoke('clear_volume_memory', { group: group || null });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      await this.wsClient.send('clear_volume_memory', { group: group || null });
+    }
+  }
+
+  async setVolumeMemoryExcluded(excluded: string[]): Promise<void> {
+    return invoke('set_volume_memory_excluded', { excluded });
+  }
+
+  async getDuckingSettings(): Promise<DuckingSettings> {
+    return invoke<DuckingSettings>('get_ducking_settings');
+  }
+
+  /**
+   * 儲存自動降低音量的設定，會立即依目前的會話生效
+   */
+  async saveDuckingSettings(settings: DuckingSettings): Promise<void> {
+    return invoke('save_ducking_settings', { settings });
+  }
+
+  async getDuckingActive(): Promise<boolean> {
+    return invoke<boolean>('get_ducking_active');
+  }
+
+  async onDuckingChanged(callback: EventCallback<boolean>): Promise<() => void> {
+    return listen<{ active: boolean }>('ducking-changed', (event) => {
+      callback(event.payload.active);
+    });
+  }
+
+  async getRules(): Promise<Rule[]> {
+    if (this.isTauri) {
+      return invoke<Rule[]>('get_rules');
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      return this.wsClient.send('get_rules') as Promise<Rule[]>;
+    }
+  }
+
+  /**
+   * 新增或更新規則，新規則的 id 留空
+   */
+  async saveRule(rule: Rule): Promise<Rule> {
+    return invoke<Rule>('save_rule', { rule });
+  }
+
+  async deleteRule(ruleId: string): Promise<void> {
+    return invoke('delete_rule', { ruleId });
+  }
+
+  async getRuleLog(): Promise<RuleLogEntry[]> {
+    if (this.isTauri) {
+      return invoke<RuleLogEntry[]>('get_rule_log');
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      return this.wsClient.send('get_rule_log') as Promise<RuleLogEntry[]>;
     }
   }
 
@@ -162,6 +482,17 @@ class AudioControllerAPI {
     }
   }
 
+  /**
+   * 專輯封面的 HTTP 網址，網址會隨封面雜湊改變，換歌時圖片才會重新下載
+   * Tauri 中沒有經過 HTTP 服務，回傳 null 並改用 getMediaThumbnail
+   */
+  getMediaArtworkUrl(info: MediaInfo): string | null {
+    if (this.isTauri || !this.wsUrl || !info.artwork_url || !info.artwork_hash) return null;
+
+    const baseUrl = this.wsUrl.replace(/^ws/, 'http').replace(/\/ws$/, '');
+    return `${baseUrl}${info.artwork_url}?v=${encodeURIComponent(info.artwork_hash)}`;
+  }
+
   async mediaPlayPause(sessionId?: string): Promise<void> {
     if (this.isTauri) {
       return invoke('media_play_pause', { sessionId: sessionId || null });
@@ -171,6 +502,33 @@ class AudioControllerAPI {
     }
   }
 
+  async mediaPlay(sessionId?: string): Promise<void> {
+    if (this.isTauri) {
+      return invoke('media_play', { sessionId: sessionId || null });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      await this.wsClient.send('media_play', { session_id: sessionId || null });
+    }
+  }
+
+  async mediaPause(sessionId?: string): Promise<void> {
+    if (this.isTauri) {
+      return invoke('media_pause', { sessionId: sessionId || null });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      await this.wsClient.send('media_pause', { session_id: sessionId || null });
+    }
+  }
+
+  async mediaStop(sessionId?: string): Promise<void> {
+    if (this.isTauri) {
+      return invoke('media_stop', { sessionId: sessionId || null });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      await this.wsClient.send('media_stop', { session_id: sessionId || null });
+    }
+  }
+
   async mediaNext(sessionId?: string): Promise<void> {
     if (this.isTauri) {
       return invoke('media_next', { sessionId: sessionId || null });
@@ -189,6 +547,42 @@ class AudioControllerAPI {
     }
   }
 
+  async mediaSeek(positionMs: number, sessionId?: string): Promise<void> {
+    if (this.isTauri) {
+      return invoke('media_seek', { sessionId: sessionId || null, positionMs });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      await this.wsClient.send('media_seek', { session_id: sessionId || null, position_ms: positionMs });
+    }
+  }
+
+  async mediaSetShuffle(shuffle: boolean, sessionId?: string): Promise<void> {
+    if (this.isTauri) {
+      return invoke('media_set_shuffle', { sessionId: sessionId || null, shuffle });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      await this.wsClient.send('media_set_shuffle', { session_id: sessionId || null, shuffle });
+    }
+  }
+
+  async mediaSetRepeat(repeat: RepeatMode, sessionId?: string): Promise<void> {
+    if (this.isTauri) {
+      return invoke('media_set_repeat', { sessionId: sessionId || null, repeat });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      await this.wsClient.send('media_set_repeat', { session_id: sessionId || null, repeat });
+    }
+  }
+
+  async mediaSetRate(rate: number, sessionId?: string): Promise<void> {
+    if (this.isTauri) {
+      return invoke('media_set_rate', { sessionId: sessionId || null, rate });
+    } else {
+      if (!this.wsClient) throw new Error('WebSocket 未初始化');
+      await this.wsClient.send('media_set_rate', { session_id: sessionId || null, rate });
+    }
+  }
+
   async onMediaInfoUpdated(callback: EventCallback<MediaInfo>): Promise<() => void> {
     if (this.isTauri) {
       const unlisten = await listen<MediaInfo>('media-info-updated', (event) => {
@@ -201,27 +595,96 @@ class AudioControllerAPI {
     }
   }
 
-  async onMediaThumbnailUpdated(callback: EventCallback<string>): Promise<() => void> {
+  async onMediaInfoCleared(callback: EventCallback<void>): Promise<() => void> {
     if (this.isTauri) {
-      const unlisten = await listen<string>('media-thumbnail-updated', (event) => {
-        callback(event.payload);
+      const unlisten = await listen('media-info-cleared', () => {
+        callback();
       });
       return unlisten;
     } else {
       if (!this.wsClient) throw new Error('WebSocket 未初始化');
-      return this.wsClient.on('media_thumbnail_updated', callback as EventCallback<unknown>) as () => void;
+      return this.wsClient.on('media_info_cleared', callback as EventCallback<unknown>) as () => void;
     }
   }
 
-  async onMediaInfoCleared(callback: EventCallback<void>): Promise<() => void> {
+  async onMediaSessionAdded(callback: EventCallback<MediaInfo>): Promise<() => void> {
+    return this.subscribe('media_session_added', callback);
+  }
+
+  async onMediaSessionUpdated(callback: EventCallback<MediaInfo>): Promise<() => void> {
+    return this.subscribe('media_session_updated', callback);
+  }
+
+  async onMediaSessionRemoved(callback: EventCallback<{ session_id: string }>): Promise<() => void> {
+    return this.subscribe('media_session_removed', callback);
+  }
+
+  async onMediaTimelineChanged(callback: EventCallback<MediaTimeline>): Promise<() => void> {
+    return this.subscribe('media_timeline_changed', callback);
+  }
+
+  async onAudioSessionsUpdated(callback: EventCallback<AudioSession[]>): Promise<() => void> {
+    return this.subscribe('audio_sessions_updated', callback);
+  }
+
+  async onDeviceVolumeUpdated(callback: EventCallback<DeviceVolumeEvent>): Promise<() => void> {
+    return this.subscribe('device_volume_updated', callback);
+  }
+
+  async onDefaultDeviceChanged(callback: EventCallback<DeviceEvent>): Promise<() => void> {
+    return this.subscribe('default_device_changed', callback);
+  }
+
+  async onCaptureVolumeUpdated(callback: EventCallback<DeviceVolumeEvent>): Promise<() => void> {
+    return this.subscribe('capture_volume_updated', callback);
+  }
+
+  async onDefaultCaptureDeviceChanged(callback: EventCallback<DeviceEvent>): Promise<() => void> {
+    return this.subscribe('default_capture_device_changed', callback);
+  }
+
+  async onDeviceAdded(callback: EventCallback<DeviceEvent>): Promise<() => void> {
+    return this.subscribe('device_added', callback);
+  }
+
+  async onDeviceRemoved(callback: EventCallback<DeviceEvent>): Promise<() => void> {
+    return this.subscribe('device_removed', callback);
+  }
+
+  /**
+   * 訂閱音量峰值，只有訂閱後伺服器才會送出，回傳的函式會取消訂閱
+   */
+  async subscribeLevels(callback: EventCallback<AudioLevels>, intervalMs: number = 100): Promise<() => void> {
+    const unlisten = await this.subscribe('levels', callback);
+
     if (this.isTauri) {
-      const unlisten = await listen('media-info-cleared', () => {
-        callback();
+      await invoke('subscribe_audio_levels', { intervalMs });
+      return () => {
+        unlisten();
+        invoke('unsubscribe_audio_levels');
+      };
+    }
+
+    if (!this.wsClient) throw new Error('WebSocket 未初始化');
+    await this.wsClient.send('subscribe_levels', { interval_ms: intervalMs });
+    return () => {
+      unlisten();
+      this.wsClient?.send('unsubscribe_levels').catch(() => {});
+    };
+  }
+
+  /**
+   * 訂閱後端推送的事件，Tauri 事件名稱為 WebSocket 類型的 kebab-case
+   */
+  private async subscribe<T>(type: string, callback: EventCallback<T>): Promise<() => void> {
+    if (this.isTauri) {
+      const unlisten = await listen<T>(type.replace(/_/g, '-'), (event) => {
+        callback(event.payload);
       });
       return unlisten;
     } else {
       if (!this.wsClient) throw new Error('WebSocket 未初始化');
-      return this.wsClient.on('media_info_cleared', callback as EventCallback<unknown>) as () => void;
+      return this.wsClient.on(type, callback as EventCallback<unknown>) as () => void;
     }
   }
 
@@ -1,7 +1,32 @@
+export type WebSocketError = {
+  code: string;
+  message: string;
+};
+
 export type WebSocketMessage = {
+  id?: number;
   type: string;
   data?: unknown;
   message?: string;
+  error?: WebSocketError;
+};
+
+/**
+ * 伺服器回傳的錯誤，code 可用於判斷錯誤種類
+ */
+export class WebSocketRequestError extends Error {
+  code: string;
+
+  constructor(error: WebSocketError) {
+    super(error.message);
+    this.code = error.code;
+  }
+}
+
+type PendingRequest = {
+  resolve: (data: unknown) => void;
+  reject: (error: Error) => void;
+  timeout: ReturnType<typeof setTimeout>;
 };
 
 export type EventCallback<T> = (data: T) => void;
@@ -14,6 +39,8 @@ export class WebSocketClient {
   private maxReconnectAttempts: number = 10;
   private eventListeners: Map<string, Set<EventCallback<unknown>>> = new Map();
   private isIntentionallyClosed: boolean = false;
+  private nextRequestId: number = 1;
+  private pendingRequests: Map<number, PendingRequest> = new Map();
 
   constructor(url: string) {
     this.url = url;
@@ -47,6 +74,7 @@ export class WebSocketClient {
 
         this.ws.onclose = () => {
           console.log('WebSocket 已斷線');
+          this.rejectPendingRequests('WebSocket 已斷線');
           if (!this.isIntentionallyClosed) {
             this.attemptReconnect();
           }
@@ -73,14 +101,24 @@ export class WebSocketClient {
   }
 
   private handleMessage(message: WebSocketMessage) {
-    if (message.type === 'media_info_updated' ||
-        message.type === 'media_thumbnail_updated' ||
-        message.type === 'media_info_cleared') {
-      this.emitEvent(message.type, message.data);
+    if (message.id !== undefined) {
+      const pending = this.pendingRequests.get(message.id);
+      if (pending) {
+        this.pendingRequests.delete(message.id);
+        clearTimeout(pending.timeout);
+
+        if (message.type === 'error') {
+          pending.reject(new WebSocketRequestError(
+            message.error || { code: 'unknown', message: message.message || '未知錯誤' }
+          ));
+        } else {
+          pending.resolve('data' in message ? message.data : message.message);
+        }
+      }
       return;
     }
 
-    this.emitEvent(message.type, message.data || message.message);
+    this.emitEvent(message.type, message.data ?? message.message);
   }
 
   private emitEvent(eventType: string, data: unknown) {
@@ -97,55 +135,26 @@ export class WebSocketClient {
         return;
       }
 
-      const message = { type, data };
-
-      try {
-        this.ws.send(JSON.stringify(message));
+      const id = this.nextRequestId++;
+      const message = { id, type, data };
 
-        const responseType = this.getResponseType(type);
-        const cleanup = this.on(responseType, (responseData: unknown) => {
-          cleanup();
-          resolve(responseData);
-        });
+      const timeout = setTimeout(() => {
+        this.pendingRequests.delete(id);
+        reject(new Error('請求超時'));
+      }, 10000);
 
-        const errorCleanup = this.on('error', (error: unknown) => {
-          errorCleanup();
-          cleanup();
-          reject(new Error(error as string));
-        });
+      this.pendingRequests.set(id, { resolve, reject, timeout });
 
-        setTimeout(() => {
-          cleanup();
-          errorCleanup();
-          reject(new Error('請求超時'));
-        }, 10000);
+      try {
+        this.ws.send(JSON.stringify(message));
       } catch (error) {
+        clearTimeout(timeout);
+        this.pendingRequests.delete(id);
         reject(error);
       }
     });
   }
 
-  private getResponseType(requestType: string): string {
-    const typeMap: Record<string, string> = {
-      'get_audio_sessions': 'audio_sessions',
-      'get_audio_devices': 'audio_devices',
-      'get_default_device_volume': 'default_device_volume',
-      'get_default_device_mute': 'default_device_mute',
-      'get_all_media_sessions': 'all_media_sessions',
-      'get_media_info': 'media_info',
-      'get_media_thumbnail': 'media_thumbnail',
-      'set_session_volume': 'success',
-      'set_session_mute': 'success',
-      'set_default_device': 'success',
-      'set_default_device_volume': 'success',
-      'set_default_device_mute': 'success',
-      'media_play_pause': 'success',
-      'media_next': 'success',
-      'media_previous': 'success',
-    };
-    return typeMap[requestType] || 'success';
-  }
-
   on(eventType: string, callback: EventCallback<unknown>): () => void {
     if (!this.eventListeners.has(eventType)) {
       this.eventListeners.set(eventType, new Set());
@@ -160,8 +169,17 @@ export class WebSocketClient {
     };
   }
 
+  private rejectPendingRequests(reason: string) {
+    this.pendingRequests.forEach((pending) => {
+      clearTimeout(pending.timeout);
+      pending.reject(new Error(reason));
+    });
+    this.pendingRequests.clear();
+  }
+
   disconnect() {
     this.isIntentionallyClosed = true;
+    this.rejectPendingRequests('WebSocket 已斷線');
     if (this.ws) {
       this.ws.close();
       this.ws = null;
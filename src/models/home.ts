@@ -1,11 +1,95 @@
 export interface AudioSession {
+    id: string;
     name: string;
+    pid: number;
+    path: string;
+    group: string;
     volume: number;
     is_muted: boolean;
+    is_active: boolean;
+    /** 目前使用的輸出裝置 ID */
+    device_id?: string | null;
   }
   
+export type DeviceDirection = 'render' | 'capture';
+
+export type DeviceRole = 'console' | 'multimedia' | 'communications';
+
+export type DeviceState = 'active' | 'disabled' | 'not_present' | 'unplugged';
+
+export type FormFactor =
+  | 'speakers'
+  | 'headphones'
+  | 'headset'
+  | 'handset'
+  | 'microphone'
+  | 'line_level'
+  | 'hdmi'
+  | 'spdif'
+  | 'network'
+  | 'unknown';
+
 export interface AudioDevice {
     id: string;
     name: string;
     is_default: boolean;
-  }
\ No newline at end of file
+    direction: DeviceDirection;
+    default_roles: DeviceRole[];
+    state: DeviceState;
+    /** 音效卡或介面的名稱 */
+    interface_name?: string | null;
+    form_factor: FormFactor;
+    /** 插孔是否接上，不支援偵測時為 null */
+    jack_connected?: boolean | null;
+    channels?: number | null;
+    sample_rate?: number | null;
+  }
+
+export interface DeviceVolume {
+    volume: number;
+    is_muted: boolean;
+  }
+
+export interface ProfileSession {
+    group: string;
+    name: string;
+    volume: number;
+    is_muted: boolean;
+  }
+
+export interface AudioProfile {
+    name: string;
+    default_device: string | null;
+    volume: number;
+    is_muted: boolean;
+    sessions: ProfileSession[];
+    hotkey: string | null;
+  }
+
+export interface ApplyProfileResult {
+    profile: string;
+    unmatched_sessions: string[];
+  }
+
+export interface RememberedVolume {
+    name: string;
+    volume: number;
+    is_muted: boolean;
+  }
+
+export interface VolumeMemory {
+    entries: Record<string, RememberedVolume>;
+    excluded: string[];
+  }
+
+export interface DuckingSettings {
+    enabled: boolean;
+    priority_apps: string[];
+    amount: number;
+    fade_ms: number;
+  }
+
+export interface AudioLevels {
+    devices: Record<string, number>;
+    sessions: Record<string, number>;
+  }
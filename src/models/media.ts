@@ -1,3 +1,5 @@
+export type RepeatMode = 'none' | 'track' | 'list';
+
 export interface MediaInfo {
   session_id: string; 
   app_name: string;
@@ -5,8 +7,40 @@ export interface MediaInfo {
   artist: string;
   album: string;
   is_playing: boolean;
-  thumbnail: string | null;
+  /** 前端透過 getMediaThumbnail 載入的 Base64 封面，後端不提供此欄位 */
+  thumbnail?: string | null;
+  artwork_url?: string | null;
+  artwork_hash?: string | null;
   can_go_next: boolean;   
   can_go_previous: boolean;  
+  position_ms?: number | null;
+  start_time_ms?: number | null;
+  end_time_ms?: number | null;
+  timeline_updated_at?: number | null;
+  can_seek?: boolean;
+  shuffle?: boolean | null;
+  repeat?: RepeatMode | null;
+  playback_rate?: number | null;
+  can_play?: boolean;
+  can_pause?: boolean;
+  can_stop?: boolean;
+  can_play_pause_toggle?: boolean;
+  can_record?: boolean;
+  can_fast_forward?: boolean;
+  can_rewind?: boolean;
+  can_channel_up?: boolean;
+  can_channel_down?: boolean;
+  can_shuffle?: boolean;
+  can_repeat?: boolean;
+  can_change_rate?: boolean;
+}
+
+export interface MediaTimeline {
+  session_id: string;
+  position_ms: number | null;
+  start_time_ms: number | null;
+  end_time_ms: number | null;
+  timeline_updated_at: number | null;
+  is_playing: boolean;
 }
 
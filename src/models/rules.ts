@@ -0,0 +1,36 @@
+export type Trigger =
+  | { type: 'session_started'; app: string }
+  | { type: 'session_ended'; app: string }
+  | { type: 'device_added'; device: string }
+  | { type: 'device_removed'; device: string }
+  | { type: 'default_device_changed'; device: string }
+  | { type: 'media_started'; app: string | null }
+  | { type: 'media_stopped'; app: string | null }
+  | { type: 'media_idle'; minutes: number };
+
+export type Action =
+  | { type: 'set_session_volume'; session_id: string; volume: number }
+  | { type: 'set_session_mute'; session_id: string; mute: boolean }
+  | { type: 'set_default_device'; device_id: string | null }
+  | { type: 'set_default_device_volume'; volume: number }
+  | { type: 'media_play_pause'; session_id: string | null }
+  | { type: 'media_play'; session_id: string | null }
+  | { type: 'media_pause'; session_id: string | null }
+  | { type: 'apply_profile'; name: string };
+
+export interface Rule {
+  id: string;
+  name: string;
+  enabled: boolean;
+  trigger: Trigger;
+  actions: Action[];
+}
+
+export interface RuleLogEntry {
+  timestamp: number;
+  rule_id: string;
+  rule_name: string;
+  event: string;
+  success: boolean;
+  errors: string[];
+}